@@ -19,10 +19,7 @@ pub struct DemoData {
 async fn main() -> anyhow::Result<()> {
     logger::init_with_default().context("Failed to initialize logger")?;
 
-    let cluster_id = "xxx-yyy".to_string();
-    let bootstrap_servers = "localhost:9092".to_string();
-
-    let config = KafkaClientConfig::new(cluster_id, bootstrap_servers);
+    let config = KafkaClientConfig::local("xxx-yyy");
 
     tracing::info!("init request sender with config: {:?}", config);
 
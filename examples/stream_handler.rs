@@ -1,6 +1,7 @@
 use anyhow::Context;
-use rust_common::kafka::core::KafkaClientConfig;
+use rust_common::kafka::core::{KafkaClientConfig, Shutdown};
 use rust_common::logger;
+use std::time::Duration;
 use tokio::signal;
 
 #[tokio::main]
@@ -8,10 +9,9 @@ async fn main() -> anyhow::Result<()> {
     logger::init_with_default().context("Failed to initialize logger")?;
 
     let cluster_id = "test-service-rust".to_string();
-    let bootstrap_servers = "localhost:9092".to_string();
     let topics = vec![cluster_id.clone()];
 
-    let config = KafkaClientConfig::new(cluster_id, bootstrap_servers).with_topics(topics);
+    let config = KafkaClientConfig::local(cluster_id).with_topics(topics);
 
     tracing::info!("init stream handler with config: {:?}", config);
 
@@ -35,15 +35,16 @@ async fn main() -> anyhow::Result<()> {
         }
     ];
 
-    let background_task = rust_common::kafka::StreamHandler::new(config, routes)?
-        .start()
-        .await?;
+    let stream_handler = rust_common::kafka::StreamHandler::new(config, routes)?;
+    let background_task = stream_handler.start().await?;
 
     tracing::info!("stream handler started");
 
     signal::ctrl_c().await?;
     tracing::info!("Shutting down...");
-    background_task.abort();
+    stream_handler
+        .stop(background_task, Shutdown::graceful(Duration::from_secs(10)))
+        .await;
 
     Ok(())
 }
@@ -0,0 +1,55 @@
+//! # Bootstrap Module
+//!
+//! Provides a one-call setup for services that log and talk to Kafka.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use rust_common::{bootstrap, kafka::StreamHandler, routes};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let config = bootstrap()?;
+//! let handler = StreamHandler::new(config, routes!())?;
+//! let task = handler.start().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+
+use crate::{kafka::KafkaClientConfig, logger};
+
+/// Initializes the logger with its default configuration and loads the Kafka
+/// configuration from the environment.
+///
+/// See `KafkaClientConfig::from_env` for the variables that are read.
+///
+/// # Returns
+///
+/// * `Result<KafkaClientConfig>` - The Kafka configuration, or an error describing which step failed.
+pub fn bootstrap() -> Result<KafkaClientConfig> {
+    logger::init_with_default().context("failed to bootstrap: logger initialization failed")?;
+
+    let config = KafkaClientConfig::from_env()
+        .context("failed to bootstrap: invalid Kafka configuration")?;
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bootstrap_from_env() {
+        // Only runs when the environment provides a Kafka configuration, since the
+        // logger can be installed once per process.
+        if std::env::var(KafkaClientConfig::ENV_BOOTSTRAP_SERVERS).is_err() {
+            return;
+        }
+
+        let config = bootstrap().unwrap();
+        assert!(!config.get_cluster_id().is_empty());
+        assert!(logger::is_initialized());
+    }
+}
@@ -0,0 +1,392 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::{select, sync::mpsc, task::JoinHandle, time::sleep};
+use tracing::{error, info, warn};
+
+use crate::kafka::{KafkaError, ParsedMessage, RetryPolicy};
+
+/// Handler invoked once per accumulated batch of messages.
+pub type BatchHandler = Arc<
+    dyn Fn(Vec<ParsedMessage>) -> Pin<Box<dyn Future<Output = Result<(), KafkaError>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Callback invoked with the entries of a batch once it is done with, and whether its handler
+/// succeeded.
+///
+/// A failed batch is passed to it once its `BatchFailurePolicy` gives up on it, so its offsets
+/// do not hold back the commits of later messages.
+pub type BatchCommitter = Arc<dyn Fn(&[BatchEntry], bool) + Send + Sync>;
+
+/// BatchFailurePolicy decides what happens to a batch whose handler fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFailurePolicy {
+    /// Calls the handler again with the batch, with the policy's backoff, and commits the batch
+    /// as failed once the retries are exhausted.
+    Retry(RetryPolicy),
+    /// Commits the batch as failed at once, logging the error.
+    CommitAndLog,
+}
+
+impl Default for BatchFailurePolicy {
+    /// Retries with `RetryPolicy::default()`.
+    fn default() -> Self {
+        Self::Retry(RetryPolicy::default())
+    }
+}
+
+/// BatchConfig controls when an accumulated batch is flushed to its handler.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchConfig {
+    /// Maximum number of messages in a batch
+    pub batch_size: usize,
+    /// Maximum time to wait for a batch to fill after its first message arrives
+    pub batch_timeout: Duration,
+    /// What happens to a batch whose handler fails
+    pub failure_policy: BatchFailurePolicy,
+}
+
+impl BatchConfig {
+    /// Creates a new BatchConfig.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_size` - The maximum number of messages per batch (at least 1).
+    /// * `batch_timeout` - The maximum time to wait before flushing a partial batch.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of BatchConfig.
+    pub fn new(batch_size: usize, batch_timeout: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            batch_timeout,
+            failure_policy: BatchFailurePolicy::default(),
+        }
+    }
+
+    /// Sets what happens to a batch whose handler fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The failure policy of the batch route.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated BatchConfig instance.
+    pub fn with_failure_policy(mut self, policy: BatchFailurePolicy) -> Self {
+        self.failure_policy = policy;
+        self
+    }
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self::new(100, Duration::from_secs(1))
+    }
+}
+
+/// BatchRoute pairs a batch handler with its flush configuration.
+#[derive(Clone)]
+pub struct BatchRoute {
+    pub config: BatchConfig,
+    pub handler: BatchHandler,
+}
+
+/// BatchEntry is a parsed message together with the Kafka position it was read from.
+#[derive(Debug, Clone)]
+pub struct BatchEntry {
+    pub message: ParsedMessage,
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+/// Batcher accumulates messages for a single URI and flushes them to the batch handler
+/// when either `batch_size` messages are collected or `batch_timeout` elapses.
+#[derive(Clone)]
+pub struct Batcher {
+    sender: mpsc::Sender<BatchEntry>,
+}
+
+impl Batcher {
+    /// Spawns the background task that accumulates and flushes batches.
+    ///
+    /// The task exits once every clone of the returned Batcher is dropped,
+    /// flushing any partial batch first.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI the batch route is registered for (used for logging).
+    /// * `route` - The batch handler and its configuration.
+    /// * `committer` - Called with the entries of each batch once it succeeded or was given up on.
+    ///
+    /// # Returns
+    ///
+    /// * `(Self, JoinHandle<()>)` - The batcher and a handle to its background task.
+    pub fn spawn(
+        uri: String,
+        route: BatchRoute,
+        committer: BatchCommitter,
+    ) -> (Self, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel(route.config.batch_size * 2);
+        let task = tokio::spawn(Self::run(uri, route, committer, receiver));
+        (Self { sender }, task)
+    }
+
+    /// Adds a message to the current batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The message and its Kafka position.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns an error if the batch task has stopped.
+    pub async fn push(&self, entry: BatchEntry) -> Result<(), KafkaError> {
+        self.sender.send(entry).await.map_err(|_| {
+            KafkaError::InternalServerError("batch processor is not running".to_string())
+        })
+    }
+
+    async fn run(
+        uri: String,
+        route: BatchRoute,
+        committer: BatchCommitter,
+        mut receiver: mpsc::Receiver<BatchEntry>,
+    ) {
+        while let Some(first) = receiver.recv().await {
+            let mut batch = vec![first];
+            let deadline = sleep(route.config.batch_timeout);
+            tokio::pin!(deadline);
+
+            while batch.len() < route.config.batch_size {
+                select! {
+                    entry = receiver.recv() => match entry {
+                        Some(entry) => batch.push(entry),
+                        None => break,
+                    },
+                    _ = &mut deadline => break,
+                }
+            }
+
+            Self::flush(&uri, &route, &committer, batch).await;
+        }
+    }
+
+    async fn flush(
+        uri: &str,
+        route: &BatchRoute,
+        committer: &BatchCommitter,
+        batch: Vec<BatchEntry>,
+    ) {
+        let size = batch.len();
+        let messages: Vec<ParsedMessage> =
+            batch.iter().map(|entry| entry.message.clone()).collect();
+        let mut retry = 0;
+
+        loop {
+            let e = match (route.handler)(messages.clone()).await {
+                Ok(()) => {
+                    committer(&batch, true);
+                    info!("handled batch of {} messages for uri: {}", size, uri);
+                    return;
+                }
+                Err(e) => e,
+            };
+
+            match route.config.failure_policy {
+                BatchFailurePolicy::Retry(policy) if retry < policy.max_retries => {
+                    retry += 1;
+                    let backoff = policy.backoff(retry);
+                    warn!(
+                        "error handling batch of {} messages for uri {} (retry {}/{} in {:?}): {}",
+                        size, uri, retry, policy.max_retries, backoff, e
+                    );
+                    sleep(backoff).await;
+                }
+                _ => {
+                    error!(
+                        "error handling batch of {} messages for uri {}, giving up on it: {}",
+                        size, uri, e
+                    );
+                    committer(&batch, false);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use crate::kafka::MessageType;
+
+    fn entry(offset: i64) -> BatchEntry {
+        BatchEntry {
+            message: ParsedMessage {
+                message_type: MessageType::Message,
+                source_id: "test".to_string(),
+                transaction_id: format!("tx-{}", offset),
+                message_id: format!("msg-{}", offset),
+                uri: "/api/v1/bulk".to_string(),
+                response_destination: None,
+                content_encoding: None,
+                deadline_ms: None,
+                path_params: Default::default(),
+                data: serde_json::json!({ "offset": offset }),
+            },
+            topic: "test-topic".to_string(),
+            partition: 0,
+            offset,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batcher_flushes_full_batch_once() {
+        let received: Arc<Mutex<Vec<Vec<String>>>> = Arc::new(Mutex::new(Vec::new()));
+        let commits: Arc<Mutex<Vec<usize>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let received_for_handler = received.clone();
+        let handler: BatchHandler = Arc::new(move |messages: Vec<ParsedMessage>| {
+            let received = received_for_handler.clone();
+            Box::pin(async move {
+                let ids = messages.into_iter().map(|m| m.message_id).collect();
+                received.lock().unwrap().push(ids);
+                Ok(())
+            })
+        });
+
+        let commits_for_committer = commits.clone();
+        let committer: BatchCommitter = Arc::new(move |entries: &[BatchEntry], _| {
+            commits_for_committer.lock().unwrap().push(entries.len());
+        });
+
+        let route = BatchRoute {
+            config: BatchConfig::new(3, Duration::from_secs(60)),
+            handler,
+        };
+        let (batcher, task) = Batcher::spawn("/api/v1/bulk".to_string(), route, committer);
+
+        for offset in 0..3 {
+            batcher.push(entry(offset)).await.unwrap();
+        }
+        drop(batcher);
+        task.await.unwrap();
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![vec![
+                "msg-0".to_string(),
+                "msg-1".to_string(),
+                "msg-2".to_string()
+            ]]
+        );
+        assert_eq!(*commits.lock().unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_batcher_flushes_partial_batch_on_timeout() {
+        let commits: Arc<Mutex<Vec<usize>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let handler: BatchHandler = Arc::new(|_| Box::pin(async { Ok(()) }));
+        let commits_for_committer = commits.clone();
+        let committer: BatchCommitter = Arc::new(move |entries: &[BatchEntry], _| {
+            commits_for_committer.lock().unwrap().push(entries.len());
+        });
+
+        let route = BatchRoute {
+            config: BatchConfig::new(10, Duration::from_millis(20)),
+            handler,
+        };
+        let (batcher, _task) = Batcher::spawn("/api/v1/bulk".to_string(), route, committer);
+
+        batcher.push(entry(0)).await.unwrap();
+        batcher.push(entry(1)).await.unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(*commits.lock().unwrap(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_batcher_commits_failed_batch_after_retries() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let commits = Arc::new(Mutex::new(Vec::new()));
+
+        // The first batch always fails, the second one succeeds
+        let calls_for_handler = calls.clone();
+        let handler: BatchHandler = Arc::new(move |messages: Vec<ParsedMessage>| {
+            let first = messages[0].data["offset"].as_i64().unwrap();
+            calls_for_handler.lock().unwrap().push(first);
+            Box::pin(async move {
+                if first == 0 {
+                    return Err(KafkaError::InternalServerError("db down".to_string()));
+                }
+                Ok(())
+            })
+        });
+        let commits_for_committer = commits.clone();
+        let committer: BatchCommitter = Arc::new(move |entries: &[BatchEntry], handled| {
+            let offsets: Vec<i64> = entries.iter().map(|entry| entry.offset).collect();
+            commits_for_committer
+                .lock()
+                .unwrap()
+                .push((offsets, handled));
+        });
+
+        let policy = RetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(1));
+        let route = BatchRoute {
+            config: BatchConfig::new(2, Duration::from_secs(60))
+                .with_failure_policy(BatchFailurePolicy::Retry(policy)),
+            handler,
+        };
+        let (batcher, task) = Batcher::spawn("/api/v1/bulk".to_string(), route, committer);
+
+        for offset in 0..4 {
+            batcher.push(entry(offset)).await.unwrap();
+        }
+        drop(batcher);
+        task.await.unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), vec![0, 0, 0, 2]);
+        assert_eq!(
+            *commits.lock().unwrap(),
+            vec![(vec![0, 1], false), (vec![2, 3], true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batcher_commits_failed_batch_at_once_with_commit_and_log() {
+        let calls = Arc::new(Mutex::new(0));
+        let commits: Arc<Mutex<Vec<bool>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let calls_for_handler = calls.clone();
+        let handler: BatchHandler = Arc::new(move |_| {
+            *calls_for_handler.lock().unwrap() += 1;
+            Box::pin(async { Err(KafkaError::InternalServerError("db down".to_string())) })
+        });
+        let commits_for_committer = commits.clone();
+        let committer: BatchCommitter = Arc::new(move |_: &[BatchEntry], handled| {
+            commits_for_committer.lock().unwrap().push(handled);
+        });
+
+        let route = BatchRoute {
+            config: BatchConfig::new(2, Duration::from_secs(60))
+                .with_failure_policy(BatchFailurePolicy::CommitAndLog),
+            handler,
+        };
+        let (batcher, task) = Batcher::spawn("/api/v1/bulk".to_string(), route, committer);
+
+        batcher.push(entry(0)).await.unwrap();
+        batcher.push(entry(1)).await.unwrap();
+        drop(batcher);
+        task.await.unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), 1);
+        assert_eq!(*commits.lock().unwrap(), vec![false]);
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{Read, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::kafka::core::KafkaError;
+
+/// Content encoding marking a `data` field compressed with gzip and encoded as base64.
+pub const GZIP_ENCODING: &str = "gzip";
+
+/// Compresses the `data` field of a serialized envelope when it exceeds a size threshold.
+///
+/// The compressed data replaces the original as a base64 string and the envelope is marked
+/// with `"contentEncoding": "gzip"`. Values without a `data` field are left unchanged.
+///
+/// # Arguments
+///
+/// * `envelope` - The serialized message envelope.
+/// * `threshold` - The serialized size of `data` in bytes above which it is compressed.
+///
+/// # Returns
+///
+/// * `Result<bool, KafkaError>` - True if the data was compressed, or a KafkaError if compression fails.
+pub fn compress_envelope(
+    envelope: &mut serde_json::Value,
+    threshold: usize,
+) -> Result<bool, KafkaError> {
+    let Some(object) = envelope.as_object_mut() else {
+        return Ok(false);
+    };
+    let Some(data) = object.get("data") else {
+        return Ok(false);
+    };
+
+    let raw = serde_json::to_vec(data).map_err(|e| {
+        KafkaError::SerializationError(format!("Failed to serialize data for compression: {}", e))
+    })?;
+    if raw.len() <= threshold {
+        return Ok(false);
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder
+        .write_all(&raw)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| KafkaError::SerializationError(format!("Failed to compress data: {}", e)))?;
+
+    object.insert(
+        "data".to_string(),
+        serde_json::Value::String(STANDARD.encode(compressed)),
+    );
+    object.insert(
+        "contentEncoding".to_string(),
+        serde_json::Value::String(GZIP_ENCODING.to_string()),
+    );
+
+    Ok(true)
+}
+
+/// Restores `data` compressed by `compress_envelope`.
+///
+/// # Arguments
+///
+/// * `encoding` - The content encoding of the envelope.
+/// * `data` - The encoded data.
+///
+/// # Returns
+///
+/// * `Result<serde_json::Value, KafkaError>` - The original data, or a KafkaError if the encoding
+///   is unsupported or the data is corrupt.
+pub fn decompress_data(
+    encoding: &str,
+    data: &serde_json::Value,
+) -> Result<serde_json::Value, KafkaError> {
+    if encoding != GZIP_ENCODING {
+        return Err(KafkaError::SerializationError(format!(
+            "Unsupported content encoding: {}",
+            encoding
+        )));
+    }
+
+    let encoded = data.as_str().ok_or_else(|| {
+        KafkaError::SerializationError("Compressed data must be a base64 string".to_string())
+    })?;
+    let compressed = STANDARD.decode(encoded).map_err(|e| {
+        KafkaError::SerializationError(format!("Failed to decode compressed data: {}", e))
+    })?;
+
+    let mut raw = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut raw)
+        .map_err(|e| KafkaError::SerializationError(format!("Failed to decompress data: {}", e)))?;
+
+    serde_json::from_slice(&raw).map_err(|e| {
+        KafkaError::SerializationError(format!("Failed to parse decompressed data: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_data_is_not_compressed() {
+        let mut envelope = serde_json::json!({ "uri": "/a", "data": { "id": 1 } });
+        let original = envelope.clone();
+
+        assert!(!compress_envelope(&mut envelope, 1024).unwrap());
+        assert_eq!(envelope, original);
+    }
+
+    #[test]
+    fn test_compress_and_decompress_data() {
+        let data = serde_json::json!({ "items": vec!["repeated value"; 1000] });
+        let mut envelope = serde_json::json!({ "uri": "/a", "data": data });
+
+        assert!(compress_envelope(&mut envelope, 1024).unwrap());
+        assert_eq!(envelope["contentEncoding"], GZIP_ENCODING);
+        assert!(envelope["data"].is_string());
+
+        let restored = decompress_data(GZIP_ENCODING, &envelope["data"]).unwrap();
+        assert_eq!(restored, data);
+    }
+
+    #[test]
+    fn test_decompress_rejects_unknown_encoding() {
+        let result = decompress_data("br", &serde_json::json!("abc"));
+        assert!(matches!(result, Err(KafkaError::SerializationError(_))));
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::kafka::core::ThroughputMeter;
+
+/// ConsumerMetrics tracks how the consumer loop splits its time between waiting for messages
+/// and running handlers.
+///
+/// Idle time is wall-clock time during which no handler is running, i.e. the consumer is
+/// waiting for the next message. Busy time is the cumulative time spent inside handlers, so
+/// with concurrent processing it can exceed wall-clock time. A consumer with high idle time is
+/// starved; one with near-zero idle time and busy time close to `concurrency_limit` times the
+/// wall-clock time is saturated.
+#[derive(Debug)]
+pub struct ConsumerMetrics {
+    state: Mutex<MetricsState>,
+    throughput: ThroughputMeter,
+}
+
+#[derive(Debug)]
+struct MetricsState {
+    in_flight: usize,
+    idle_since: Option<Instant>,
+    idle: Duration,
+    busy: Duration,
+    processed: u64,
+}
+
+/// ConsumerMetricsSnapshot is a point-in-time copy of the consumer loop metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsumerMetricsSnapshot {
+    /// Cumulative time with no handler running
+    pub idle: Duration,
+    /// Cumulative time spent inside handlers
+    pub busy: Duration,
+    /// Number of messages whose handler has completed
+    pub processed: u64,
+    /// Number of handlers currently running
+    pub in_flight: usize,
+}
+
+/// ActivityGuard records the busy time of one handler invocation when dropped.
+pub struct ActivityGuard<'a> {
+    metrics: &'a ConsumerMetrics,
+    started_at: Instant,
+}
+
+impl ConsumerMetrics {
+    /// Creates a new ConsumerMetrics, idle from now on.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of ConsumerMetrics.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(MetricsState {
+                in_flight: 0,
+                idle_since: Some(Instant::now()),
+                idle: Duration::ZERO,
+                busy: Duration::ZERO,
+                processed: 0,
+            }),
+            throughput: ThroughputMeter::default(),
+        }
+    }
+
+    /// Marks the start of a handler invocation.
+    ///
+    /// # Returns
+    ///
+    /// * `ActivityGuard` - A guard that marks the invocation as finished when dropped.
+    pub fn begin(&self) -> ActivityGuard<'_> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(idle_since) = state.idle_since.take() {
+            state.idle += now.duration_since(idle_since);
+        }
+        state.in_flight += 1;
+
+        ActivityGuard {
+            metrics: self,
+            started_at: now,
+        }
+    }
+
+    fn finish(&self, started_at: Instant) {
+        self.throughput.record();
+
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        state.busy += now.duration_since(started_at);
+        state.processed += 1;
+        state.in_flight = state.in_flight.saturating_sub(1);
+        if state.in_flight == 0 {
+            state.idle_since = Some(now);
+        }
+    }
+
+    /// Retrieves the number of completed messages per second over the throughput window.
+    ///
+    /// # Returns
+    ///
+    /// * `f64` - The current throughput.
+    pub fn throughput(&self) -> f64 {
+        self.throughput.rate()
+    }
+
+    /// Retrieves the current metrics, including the idle period in progress.
+    ///
+    /// # Returns
+    ///
+    /// * `ConsumerMetricsSnapshot` - A copy of the current metrics.
+    pub fn snapshot(&self) -> ConsumerMetricsSnapshot {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let ongoing_idle = state
+            .idle_since
+            .map(|idle_since| idle_since.elapsed())
+            .unwrap_or_default();
+
+        ConsumerMetricsSnapshot {
+            idle: state.idle + ongoing_idle,
+            busy: state.busy,
+            processed: state.processed,
+            in_flight: state.in_flight,
+        }
+    }
+}
+
+impl Default for ConsumerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        self.metrics.finish(self.started_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_busy_and_idle_accrue() {
+        let metrics = ConsumerMetrics::new();
+        std::thread::sleep(Duration::from_millis(20));
+
+        {
+            let _guard = metrics.begin();
+            assert_eq!(metrics.snapshot().in_flight, 1);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let snapshot = metrics.snapshot();
+        assert!(snapshot.idle >= Duration::from_millis(20));
+        assert!(snapshot.busy >= Duration::from_millis(20));
+        assert_eq!(snapshot.processed, 1);
+        assert_eq!(snapshot.in_flight, 0);
+    }
+
+    #[test]
+    fn test_overlapping_handlers_do_not_count_as_idle() {
+        let metrics = ConsumerMetrics::new();
+        let first = metrics.begin();
+        let idle_before = metrics.snapshot().idle;
+
+        let second = metrics.begin();
+        drop(first);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(metrics.snapshot().idle, idle_before);
+        drop(second);
+
+        assert_eq!(metrics.snapshot().processed, 2);
+        assert!(metrics.throughput() > 0.0);
+    }
+}
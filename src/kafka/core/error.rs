@@ -1,17 +1,90 @@
+use std::{fmt, str::FromStr};
+
 use crate::kafka::{Response, Status};
 
 pub mod error_codes {
-    pub const INTERNAL_SERVER_ERROR: &str = "INTERNAL_SERVER_ERROR";
-    pub const URI_NOT_FOUND: &str = "URI_NOT_FOUND";
-    pub const INVALID_PARAMETER: &str = "INVALID_PARAMETER";
-    pub const FIELD_REQUIRED: &str = "FIELD_REQUIRED";
-    pub const VALUE_INVALID: &str = "VALUE_INVALID";
-    pub const TIMEOUT_ERROR: &str = "TIMEOUT_ERROR";
-    pub const UNAUTHORIZED: &str = "UNAUTHORIZED";
-    pub const OBJECT_NOT_FOUND: &str = "OBJECT_NOT_FOUND";
-    pub const SECOND_FACTOR_REQUIRED: &str = "SECOND_FACTOR_REQUIRED";
+    use super::ErrorCode;
+
+    pub const INTERNAL_SERVER_ERROR: &str = ErrorCode::InternalServerError.as_str();
+    pub const URI_NOT_FOUND: &str = ErrorCode::UriNotFound.as_str();
+    pub const INVALID_PARAMETER: &str = ErrorCode::InvalidParameter.as_str();
+    pub const FIELD_REQUIRED: &str = ErrorCode::FieldRequired.as_str();
+    pub const VALUE_INVALID: &str = ErrorCode::ValueInvalid.as_str();
+    pub const TIMEOUT_ERROR: &str = ErrorCode::TimeoutError.as_str();
+    pub const UNAUTHORIZED: &str = ErrorCode::Unauthorized.as_str();
+    pub const OBJECT_NOT_FOUND: &str = ErrorCode::ObjectNotFound.as_str();
+    pub const SECOND_FACTOR_REQUIRED: &str = ErrorCode::SecondFactorRequired.as_str();
+}
+
+/// ErrorCode is the machine-readable code of an error `Status`.
+///
+/// Build statuses from it instead of from strings, so a misspelt code does not compile; the
+/// `error_codes` constants are the same codes as strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    InternalServerError,
+    UriNotFound,
+    InvalidParameter,
+    FieldRequired,
+    ValueInvalid,
+    TimeoutError,
+    Unauthorized,
+    ObjectNotFound,
+    SecondFactorRequired,
 }
 
+impl ErrorCode {
+    /// Every error code.
+    pub const ALL: [ErrorCode; 9] = [
+        ErrorCode::InternalServerError,
+        ErrorCode::UriNotFound,
+        ErrorCode::InvalidParameter,
+        ErrorCode::FieldRequired,
+        ErrorCode::ValueInvalid,
+        ErrorCode::TimeoutError,
+        ErrorCode::Unauthorized,
+        ErrorCode::ObjectNotFound,
+        ErrorCode::SecondFactorRequired,
+    ];
+
+    /// Retrieves the code as sent in a `Status`, e.g. `URI_NOT_FOUND`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InternalServerError => "INTERNAL_SERVER_ERROR",
+            ErrorCode::UriNotFound => "URI_NOT_FOUND",
+            ErrorCode::InvalidParameter => "INVALID_PARAMETER",
+            ErrorCode::FieldRequired => "FIELD_REQUIRED",
+            ErrorCode::ValueInvalid => "VALUE_INVALID",
+            ErrorCode::TimeoutError => "TIMEOUT_ERROR",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::ObjectNotFound => "OBJECT_NOT_FOUND",
+            ErrorCode::SecondFactorRequired => "SECOND_FACTOR_REQUIRED",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = UnknownErrorCode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ErrorCode::ALL
+            .into_iter()
+            .find(|code| code.as_str() == s)
+            .ok_or_else(|| UnknownErrorCode(s.to_string()))
+    }
+}
+
+/// UnknownErrorCode is returned when parsing a string that is not an `ErrorCode`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown error code: {0}")]
+pub struct UnknownErrorCode(pub String);
+
 /// KafkaError defines the various errors that can occur within the Kafka module.
 /// It provides structured error messages for different failure scenarios.
 #[derive(thiserror::Error, Debug)]
@@ -39,6 +112,107 @@ pub enum KafkaError {
     /// Represents a configuration error with a detailed message.
     #[error("Configuration Error: {0}")]
     ConfigurationError(String),
+
+    /// Indicates that a client configuration is unusable, listing every problem found.
+    #[error("Configuration Error: {}", ConfigProblem::join(.0))]
+    InvalidConfiguration(Vec<ConfigProblem>),
+
+    /// Indicates that message data does not match the expected type, at the given field path.
+    #[error("Validation Error at {path}: {message}")]
+    ValidationError { path: String, message: String },
+}
+
+/// PayloadError describes why a Kafka message payload could not be read as text.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PayloadError {
+    /// The message carries no payload, or a zero-length one.
+    #[error("message has no payload")]
+    Empty,
+
+    /// The payload is present but is not valid UTF-8.
+    #[error("payload is not valid UTF-8: {0}")]
+    InvalidUtf8(String),
+}
+
+/// SendError describes why a request sent via `RequestSender` produced no response.
+///
+/// Error responses returned by the remote service are not a `SendError`; they arrive as a
+/// regular response message for the caller to inspect.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+    /// The request could not be produced to Kafka.
+    #[error("failed to produce request: {0}")]
+    Produce(String),
+
+    /// No response arrived within the request timeout.
+    #[error("request {0} timeout")]
+    Timeout(String),
+
+    /// The request was cancelled before a response arrived.
+    #[error("request {0} cancelled")]
+    Cancelled(String),
+
+    /// The response channel was closed without a response.
+    #[error("response channel closed unexpectedly: {0}")]
+    ChannelClosed(String),
+
+    /// The request was rejected because the maximum number of pending requests was reached.
+    #[error("too many pending requests (limit {0})")]
+    Overloaded(usize),
+}
+
+/// ConfigProblem is a problem found by `KafkaClientConfig::validate`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigProblem {
+    /// `bootstrap.servers` is missing or blank.
+    #[error("missing bootstrap.servers")]
+    MissingBootstrapServers,
+
+    /// The cluster id is blank.
+    #[error("empty cluster_id")]
+    EmptyClusterId,
+
+    /// A SASL `security.protocol` is set without a `sasl.mechanism`.
+    #[error("security.protocol {protocol} requires sasl.mechanism")]
+    MissingSaslMechanism { protocol: String },
+
+    /// A `sasl.mechanism` is set without a SASL `security.protocol`.
+    #[error("sasl.mechanism {mechanism} requires security.protocol SASL_PLAINTEXT or SASL_SSL")]
+    SaslWithoutSaslProtocol { mechanism: String },
+
+    /// A credential required by the `sasl.mechanism` is missing.
+    #[error("sasl.mechanism {mechanism} requires {key}")]
+    MissingSaslCredential { mechanism: String, key: String },
+
+    /// Config keys that are not common librdkafka properties, with `strict_config` set.
+    #[error("unrecognized config keys: {}", .0.join(", "))]
+    UnrecognizedKeys(Vec<String>),
+}
+
+impl ConfigProblem {
+    /// Joins the descriptions of problems with "; ".
+    fn join(problems: &[ConfigProblem]) -> String {
+        problems
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+impl From<SendError> for KafkaError {
+    /// Maps a request failure to a KafkaError; produce failures and overload stay transient.
+    fn from(error: SendError) -> Self {
+        match error {
+            SendError::Timeout(_) => KafkaError::TimeoutError(error.to_string()),
+            SendError::Produce(_) | SendError::Overloaded(_) => {
+                KafkaError::ConnectionError(error.to_string())
+            }
+            SendError::Cancelled(_) | SendError::ChannelClosed(_) => {
+                KafkaError::InternalServerError(error.to_string())
+            }
+        }
+    }
 }
 
 impl KafkaError {
@@ -48,58 +222,60 @@ impl KafkaError {
     ///
     /// * `Response` - A structured response containing the error code and message.
     pub fn to_response(&self) -> Response {
+        let data = match self {
+            KafkaError::ValidationError { path, .. } => Some(serde_json::json!({ "path": path })),
+            _ => None,
+        };
+
+        Response {
+            status: Some(Status {
+                data,
+                ..Status::new(self.error_code(), self.to_string())
+            }),
+            data: None,
+        }
+    }
+
+    /// Retrieves the `ErrorCode` used when the error is sent as a response.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorCode` - The machine-readable error code.
+    pub fn error_code(&self) -> ErrorCode {
         match self {
-            KafkaError::InternalServerError(_) => Response {
-                status: Some(Status {
-                    code: error_codes::INTERNAL_SERVER_ERROR.to_string(),
-                    message: self.to_string(),
-                    data: None,
-                }),
-                data: None,
-            },
-            KafkaError::UriNotFound(_) => Response {
-                status: Some(Status {
-                    code: error_codes::URI_NOT_FOUND.to_string(),
-                    message: self.to_string(),
-                    data: None,
-                }),
-                data: None,
-            },
-            KafkaError::SerializationError(_) => Response {
-                status: Some(Status {
-                    code: error_codes::VALUE_INVALID.to_string(),
-                    message: self.to_string(),
-                    data: None,
-                }),
-                data: None,
-            },
-            KafkaError::ConnectionError(_) => Response {
-                status: Some(Status {
-                    code: error_codes::TIMEOUT_ERROR.to_string(),
-                    message: self.to_string(),
-                    data: None,
-                }),
-                data: None,
-            },
-            KafkaError::TimeoutError(_) => Response {
-                status: Some(Status {
-                    code: error_codes::TIMEOUT_ERROR.to_string(),
-                    message: self.to_string(),
-                    data: None,
-                }),
-                data: None,
-            },
-            KafkaError::ConfigurationError(_) => Response {
-                status: Some(Status {
-                    code: error_codes::INVALID_PARAMETER.to_string(),
-                    message: self.to_string(),
-                    data: None,
-                }),
-                data: None,
-            },
+            KafkaError::InternalServerError(_) => ErrorCode::InternalServerError,
+            KafkaError::UriNotFound(_) => ErrorCode::UriNotFound,
+            KafkaError::SerializationError(_) => ErrorCode::ValueInvalid,
+            KafkaError::ConnectionError(_) => ErrorCode::TimeoutError,
+            KafkaError::TimeoutError(_) => ErrorCode::TimeoutError,
+            KafkaError::ConfigurationError(_) | KafkaError::InvalidConfiguration(_) => {
+                ErrorCode::InvalidParameter
+            }
+            KafkaError::ValidationError { .. } => ErrorCode::ValueInvalid,
         }
     }
 
+    /// Retrieves the `error_codes` constant used when the error is sent as a response.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The machine-readable error code, e.g. `URI_NOT_FOUND`.
+    pub fn code(&self) -> &'static str {
+        self.error_code().as_str()
+    }
+
+    /// Determines if the error is transient, i.e. the same operation may succeed if retried.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True for connection and timeout errors, false otherwise.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            KafkaError::ConnectionError(_) | KafkaError::TimeoutError(_)
+        )
+    }
+
     /// Converts the KafkaError into a JSON value for response serialization.
     ///
     /// # Returns
@@ -237,6 +413,77 @@ mod tests {
         assert!(response.data.is_none());
     }
 
+    #[test]
+    fn test_validation_error() {
+        let error = KafkaError::ValidationError {
+            path: "user.address".to_string(),
+            message: "missing field `city`".to_string(),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Validation Error at user.address: missing field `city`"
+        );
+
+        let status = error.to_response().status.unwrap();
+        assert_eq!(status.code, error_codes::VALUE_INVALID);
+        assert_eq!(
+            status.data,
+            Some(serde_json::json!({ "path": "user.address" }))
+        );
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(KafkaError::ConnectionError("test".to_string()).is_transient());
+        assert!(KafkaError::TimeoutError("test".to_string()).is_transient());
+        assert!(!KafkaError::InternalServerError("test".to_string()).is_transient());
+        assert!(!KafkaError::SerializationError("test".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_code() {
+        let cases = [
+            (
+                KafkaError::InternalServerError("test".to_string()),
+                "INTERNAL_SERVER_ERROR",
+            ),
+            (KafkaError::UriNotFound("test".to_string()), "URI_NOT_FOUND"),
+            (
+                KafkaError::SerializationError("test".to_string()),
+                "VALUE_INVALID",
+            ),
+            (
+                KafkaError::ConnectionError("test".to_string()),
+                "TIMEOUT_ERROR",
+            ),
+            (
+                KafkaError::TimeoutError("test".to_string()),
+                "TIMEOUT_ERROR",
+            ),
+            (
+                KafkaError::ConfigurationError("test".to_string()),
+                "INVALID_PARAMETER",
+            ),
+            (
+                KafkaError::InvalidConfiguration(vec![ConfigProblem::EmptyClusterId]),
+                "INVALID_PARAMETER",
+            ),
+            (
+                KafkaError::ValidationError {
+                    path: "id".to_string(),
+                    message: "test".to_string(),
+                },
+                "VALUE_INVALID",
+            ),
+        ];
+
+        for (error, code) in cases {
+            assert_eq!(error.code(), code);
+            assert_eq!(error.to_response().status.unwrap().code, code);
+        }
+    }
+
     #[test]
     fn test_error_codes() {
         assert_eq!(error_codes::INTERNAL_SERVER_ERROR, "INTERNAL_SERVER_ERROR");
@@ -253,6 +500,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_error_code_round_trips_through_strings() {
+        let codes = [
+            error_codes::INTERNAL_SERVER_ERROR,
+            error_codes::URI_NOT_FOUND,
+            error_codes::INVALID_PARAMETER,
+            error_codes::FIELD_REQUIRED,
+            error_codes::VALUE_INVALID,
+            error_codes::TIMEOUT_ERROR,
+            error_codes::UNAUTHORIZED,
+            error_codes::OBJECT_NOT_FOUND,
+            error_codes::SECOND_FACTOR_REQUIRED,
+        ];
+
+        for (code, expected) in ErrorCode::ALL.into_iter().zip(codes) {
+            assert_eq!(code.as_str(), expected);
+            assert_eq!(code.to_string(), expected);
+            assert_eq!(expected.parse::<ErrorCode>(), Ok(code));
+        }
+
+        assert_eq!(
+            "uri_not_found".parse::<ErrorCode>(),
+            Err(UnknownErrorCode("uri_not_found".to_string()))
+        );
+    }
+
     #[test]
     fn test_error_debug() {
         let error = KafkaError::InternalServerError("Test error".to_string());
@@ -268,6 +541,31 @@ mod tests {
         assert_eq!(cloned_error, "Timeout Error: Test timeout");
     }
 
+    #[test]
+    fn test_payload_error_display() {
+        assert_eq!(PayloadError::Empty.to_string(), "message has no payload");
+        assert_eq!(
+            PayloadError::InvalidUtf8("bad byte".to_string()).to_string(),
+            "payload is not valid UTF-8: bad byte"
+        );
+    }
+
+    #[test]
+    fn test_send_error_display() {
+        assert_eq!(
+            SendError::Produce("broker down".to_string()).to_string(),
+            "failed to produce request: broker down"
+        );
+        assert_eq!(
+            SendError::Timeout("tx-1".to_string()).to_string(),
+            "request tx-1 timeout"
+        );
+        assert_eq!(
+            SendError::Cancelled("tx-1".to_string()).to_string(),
+            "request tx-1 cancelled"
+        );
+    }
+
     #[test]
     fn test_all_error_variants() {
         let errors = vec![
@@ -277,6 +575,7 @@ mod tests {
             KafkaError::ConnectionError("test".to_string()),
             KafkaError::TimeoutError("test".to_string()),
             KafkaError::ConfigurationError("test".to_string()),
+            KafkaError::InvalidConfiguration(vec![ConfigProblem::EmptyClusterId]),
         ];
 
         for error in errors {
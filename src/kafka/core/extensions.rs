@@ -1,7 +1,11 @@
 use anyhow::Result;
-use rdkafka::{message::OwnedMessage, Message, Timestamp};
+use rdkafka::{
+    message::{BorrowedMessage, OwnedMessage},
+    Message, Timestamp,
+};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     future::Future,
     pin::Pin,
     sync::Arc,
@@ -9,11 +13,13 @@ use std::{
 };
 use tracing::error;
 
-use crate::kafka::KafkaError;
+use crate::kafka::{
+    decompress_data, utils::utils::format_duration, ErrorCode, KafkaError, KafkaProducer,
+};
 
 /// MessageType defines the different types of messages that can be sent through Kafka.
 /// It supports JSON serialization with custom names for each variant.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MessageType {
     /// Request message type - serializes to "REQUEST"
     #[serde(rename = "REQUEST")]
@@ -27,7 +33,7 @@ pub enum MessageType {
 }
 
 /// ResponseDestination holds the topic and URI for message responses.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ResponseDestination {
     pub topic: String,
     pub uri: String,
@@ -61,8 +67,17 @@ pub struct ParsedMessage<T = serde_json::Value> {
     pub uri: String,
     /// Optional response destination configuration
     pub response_destination: Option<ResponseDestination>,
+    /// Encoding of `data` on the wire, e.g. `gzip`; None when sent as plain JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+    /// Optional deadline in milliseconds since the Unix epoch, after which the message is skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline_ms: Option<i64>,
     /// The data payload of the message
     pub data: T,
+    /// Named parameters captured from the route template, e.g. `id` for `/users/{id}`
+    #[serde(skip)]
+    pub path_params: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +87,33 @@ pub struct SendMessage<T = serde_json::Value> {
 }
 
 impl ParsedMessage {
+    /// Retrieves a path parameter captured from the route template.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The parameter name, e.g. `id` for `/users/{id}`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - The parameter value, or None if the route has no such parameter.
+    pub fn path_param(&self, name: &str) -> Option<&str> {
+        self.path_params.get(name).map(String::as_str)
+    }
+
+    /// Checks whether the message deadline has passed.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if `deadline_ms` is set and lies in the past, false otherwise.
+    pub fn is_past_deadline(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+
+        self.deadline_ms.is_some_and(|deadline| deadline < now)
+    }
+
     pub fn should_response(&self) -> bool {
         self.response_destination
             .as_ref()
@@ -80,7 +122,7 @@ impl ParsedMessage {
 
     pub fn parse_from_string(message: &str) -> Option<Self> {
         match serde_json::from_str::<ParsedMessage>(message) {
-            Ok(parsed_message) => Some(parsed_message),
+            Ok(parsed_message) => parsed_message.decode_content(),
             Err(e) => {
                 error!("Failed to parse JSON message: {}", e);
                 None
@@ -88,10 +130,51 @@ impl ParsedMessage {
         }
     }
 
+    /// Restores compressed data so handlers always see plain JSON.
+    fn decode_content(mut self) -> Option<Self> {
+        let Some(encoding) = self.content_encoding.take() else {
+            return Some(self);
+        };
+
+        match decompress_data(&encoding, &self.data) {
+            Ok(data) => {
+                self.data = data;
+                Some(self)
+            }
+            Err(e) => {
+                error!("Failed to decode {} message data: {}", encoding, e);
+                None
+            }
+        }
+    }
+
     pub fn get_response_destination(&self) -> Option<&ResponseDestination> {
         self.response_destination.as_ref()
     }
 
+    /// Clones the message for forwarding as a new request.
+    ///
+    /// The fork gets fresh transaction and message ids and no response destination,
+    /// while the message type, source and data are preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_uri` - Optional URI to route the fork to instead of the original one.
+    ///
+    /// # Returns
+    ///
+    /// * `ParsedMessage` - The forked message.
+    pub fn fork(&self, new_uri: Option<String>) -> ParsedMessage {
+        let mut forked = self.clone();
+        forked.transaction_id = uuid::Uuid::new_v4().to_string();
+        forked.message_id = uuid::Uuid::new_v4().to_string();
+        forked.response_destination = None;
+        if let Some(uri) = new_uri {
+            forked.uri = uri;
+        }
+        forked
+    }
+
     pub fn get_data_as<U>(&self) -> Result<U>
     where
         U: serde::de::DeserializeOwned + std::fmt::Debug,
@@ -103,10 +186,76 @@ impl ParsedMessage {
             )
         })?;
 
-        let result = serde_json::from_value(inner.clone())?;
+        let result = deserialize_value(inner)?;
         tracing::info!("extracted data: {:?}", &result);
         Ok(result)
     }
+
+    /// Deserializes the message data into a typed value.
+    ///
+    /// On failure the error carries the path of the offending field (e.g. `user.address`),
+    /// which is reported to the caller in `status.data` when returned from a handler.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<U, KafkaError>` - The typed data, or `KafkaError::ValidationError` with the field path.
+    pub fn parse_data<U>(&self) -> Result<U, KafkaError>
+    where
+        U: serde::de::DeserializeOwned,
+    {
+        deserialize_value(&self.data)
+    }
+
+    /// Retrieves a nested field of the message data by JSON pointer.
+    ///
+    /// # Arguments
+    ///
+    /// * `pointer` - The JSON pointer of the field, e.g. `/user/id`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&serde_json::Value>` - The field, or None if the pointer does not resolve.
+    pub fn field(&self, pointer: &str) -> Option<&serde_json::Value> {
+        self.data.pointer(pointer)
+    }
+
+    /// Deserializes a nested field of the message data by JSON pointer.
+    ///
+    /// # Arguments
+    ///
+    /// * `pointer` - The JSON pointer of the field, e.g. `/user/id`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<U, KafkaError>` - The typed field, or `KafkaError::ValidationError` with the pointer
+    ///   as path if the field is missing or does not match `U`.
+    pub fn field_as<U>(&self, pointer: &str) -> Result<U, KafkaError>
+    where
+        U: serde::de::DeserializeOwned,
+    {
+        let value = self
+            .field(pointer)
+            .ok_or_else(|| KafkaError::ValidationError {
+                path: pointer.to_string(),
+                message: "missing field".to_string(),
+            })?;
+
+        U::deserialize(value).map_err(|e| KafkaError::ValidationError {
+            path: pointer.to_string(),
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Deserializes a JSON value, reporting the path of the field that failed.
+fn deserialize_value<U>(value: &serde_json::Value) -> Result<U, KafkaError>
+where
+    U: serde::de::DeserializeOwned,
+{
+    serde_path_to_error::deserialize(value).map_err(|e| KafkaError::ValidationError {
+        path: e.path().to_string(),
+        message: e.inner().to_string(),
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,16 +265,172 @@ pub struct Status<T = serde_json::Value> {
     pub data: Option<T>,
 }
 
+impl<T> Status<T> {
+    /// Creates a status without data.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The error code.
+    /// * `message` - The human-readable error message.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of Status.
+    pub fn new<M: Into<String>>(code: ErrorCode, message: M) -> Self {
+        Self {
+            code: code.as_str().to_string(),
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Attaches data describing the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The error data.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated Status instance.
+    pub fn with_data(mut self, data: T) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Parses the code of the status.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<ErrorCode>` - The error code, or None if the code is not a known `ErrorCode`.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        self.code.parse().ok()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Response<S = serde_json::Value, D = serde_json::Value> {
     pub status: Option<Status<S>>,
     pub data: Option<D>,
 }
 
+impl<S, D> Response<S, D> {
+    /// Creates a successful response carrying typed data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The response data.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A response with no status and the given data.
+    pub fn success(data: D) -> Self {
+        Self {
+            status: None,
+            data: Some(data),
+        }
+    }
+
+    /// Creates an error response carrying a typed status.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The error status.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A response with the given status and no data.
+    pub fn error(status: Status<S>) -> Self {
+        Self {
+            status: Some(status),
+            data: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum HandlerResult {
     Response(serde_json::Value),
     Acknowledge,
+    /// Raw bytes sent verbatim as the response payload, without the JSON envelope.
+    ///
+    /// The envelope fields travel as headers instead; see `KafkaProducer::send_bytes_response`.
+    Bytes(Vec<u8>, ContentType),
+    /// Asks for the message to be redelivered later instead of answering it, e.g. when a
+    /// resource it needs is temporarily unavailable. No response is sent.
+    ///
+    /// The consumer hands the message to its handler again after the backoff of its
+    /// `with_redelivery` policy. Until then the message's offset is not stored, so auto-commit
+    /// cannot move the partition's committed offset past it; later messages of the partition keep
+    /// being handled meanwhile. Once the redeliveries are exhausted, the message is given up on.
+    Retry,
+}
+
+/// Header carrying the media type of a binary response.
+pub const CONTENT_TYPE_HEADER: &str = "contentType";
+
+/// ContentType is the media type of a binary handler response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentType {
+    /// `application/octet-stream`
+    OctetStream,
+    /// `application/x-protobuf`
+    Protobuf,
+    /// `image/png`
+    Png,
+    /// `image/jpeg`
+    Jpeg,
+    /// Any other media type
+    Custom(String),
+}
+
+impl ContentType {
+    /// Retrieves the media type string sent in the `contentType` header.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The media type, e.g. `image/png`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ContentType::OctetStream => "application/octet-stream",
+            ContentType::Protobuf => "application/x-protobuf",
+            ContentType::Png => "image/png",
+            ContentType::Jpeg => "image/jpeg",
+            ContentType::Custom(content_type) => content_type,
+        }
+    }
+}
+
+/// HandlerContext gives handlers registered with `RouteRegistry::register_with_ctx` access to
+/// the service's producer, so they can publish events while handling a message.
+#[derive(Clone)]
+pub struct HandlerContext {
+    /// The producer of the StreamHandler running the handler
+    pub producer: Arc<KafkaProducer>,
+    /// The source identifier of the service, taken from the cluster ID
+    pub source_id: String,
+    /// Service metadata set with `StreamHandler::with_handler_meta`
+    pub meta: Arc<HashMap<String, String>>,
+}
+
+impl HandlerContext {
+    /// Publishes an event message to a topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to which the event will be sent.
+    /// * `uri` - The URI of the event.
+    /// * `data` - The event payload.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Ok if the event is sent successfully, or a KafkaError if it fails.
+    pub async fn publish<T>(&self, topic: &str, uri: &str, data: T) -> Result<(), KafkaError>
+    where
+        T: Serialize,
+    {
+        self.producer.send_event(topic, uri, data).await
+    }
 }
 
 pub type MessageHandler = Arc<
@@ -136,6 +441,48 @@ pub type MessageHandler = Arc<
         + Sync,
 >;
 
+/// RouteHandler is a message handler implemented as a type, for handlers holding state or
+/// defined as methods; register it with `RouteRegistry::register_handler`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use rust_common::kafka::{
+///     async_trait, HandlerResult, KafkaError, ParsedMessage, RouteHandler, RouteRegistry,
+/// };
+///
+/// struct Greeter {
+///     greeting: String,
+/// }
+///
+/// #[async_trait]
+/// impl RouteHandler for Greeter {
+///     async fn handle(&self, msg: &ParsedMessage) -> Result<HandlerResult, KafkaError> {
+///         Ok(HandlerResult::Response(serde_json::json!({
+///             "message": format!("{} {}", self.greeting, msg.data["name"]),
+///         })))
+///     }
+/// }
+///
+/// let mut registry = RouteRegistry::new();
+/// registry.register_handler("/api/v1/greet", Arc::new(Greeter { greeting: "hello".to_string() }));
+/// ```
+#[async_trait::async_trait]
+pub trait RouteHandler: Send + Sync {
+    /// Handles a message routed to this handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to handle.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<HandlerResult, KafkaError>` - The handler result, or a KafkaError answered as an error response.
+    async fn handle(&self, msg: &ParsedMessage) -> Result<HandlerResult, KafkaError>;
+}
+
 pub trait MessageLatency {
     fn get_latency(&self) -> i64; // abstract method
 
@@ -144,7 +491,7 @@ pub trait MessageLatency {
         if latency == 0 {
             "N/A".to_string()
         } else {
-            format!("{}ms", latency)
+            format_duration(latency)
         }
     }
 
@@ -154,17 +501,233 @@ pub trait MessageLatency {
     }
 }
 
+/// Computes the milliseconds elapsed since a Kafka message timestamp.
+///
+/// # Arguments
+///
+/// * `ts` - The message timestamp.
+///
+/// # Returns
+///
+/// * `i64` - The latency in milliseconds, or 0 if the timestamp is not available.
+pub fn compute_latency(ts: Timestamp) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+
+    match ts {
+        Timestamp::CreateTime(ts) => now - ts,    // tính từ producer
+        Timestamp::LogAppendTime(ts) => now - ts, // tính từ broker
+        Timestamp::NotAvailable => 0,
+    }
+}
+
 impl MessageLatency for OwnedMessage {
     fn get_latency(&self) -> i64 {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as i64;
+        compute_latency(self.timestamp())
+    }
+}
 
-        match self.timestamp() {
-            Timestamp::CreateTime(ts) => now - ts,    // tính từ producer
-            Timestamp::LogAppendTime(ts) => now - ts, // tính từ broker
-            Timestamp::NotAvailable => 0,
+impl MessageLatency for BorrowedMessage<'_> {
+    fn get_latency(&self) -> i64 {
+        compute_latency(self.timestamp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestMessage;
+
+    fn sample_message() -> ParsedMessage {
+        ParsedMessage {
+            message_type: MessageType::Request,
+            source_id: "source-service".to_string(),
+            transaction_id: "tx-1".to_string(),
+            message_id: "msg-1".to_string(),
+            uri: "/api/v1/users".to_string(),
+            response_destination: Some(ResponseDestination {
+                topic: "source-service".to_string(),
+                uri: "REQUEST_RESPONSE".to_string(),
+            }),
+            content_encoding: None,
+            deadline_ms: None,
+            path_params: HashMap::new(),
+            data: serde_json::json!({ "id": 42 }),
+        }
+    }
+
+    #[test]
+    fn test_field_extracts_nested_value() {
+        let mut message = sample_message();
+        message.data = serde_json::json!({ "user": { "id": 42, "name": "Lan" } });
+
+        assert_eq!(message.field("/user/id"), Some(&serde_json::json!(42)));
+        assert_eq!(message.field_as::<u64>("/user/id").unwrap(), 42);
+        assert_eq!(message.field_as::<String>("/user/name").unwrap(), "Lan");
+    }
+
+    #[test]
+    fn test_field_missing_pointer() {
+        let mut message = sample_message();
+        message.data = serde_json::json!({ "user": { "id": 42 } });
+
+        assert_eq!(message.field("/user/email"), None);
+        assert!(matches!(
+            message.field_as::<String>("/user/email"),
+            Err(KafkaError::ValidationError { ref path, .. }) if path == "/user/email"
+        ));
+        assert!(matches!(
+            message.field_as::<String>("/user/id"),
+            Err(KafkaError::ValidationError { ref path, .. }) if path == "/user/id"
+        ));
+    }
+
+    #[test]
+    fn test_fork_assigns_new_ids() {
+        let original = sample_message();
+        let forked = original.fork(None);
+
+        assert_ne!(forked.transaction_id, original.transaction_id);
+        assert_ne!(forked.message_id, original.message_id);
+        assert_eq!(forked.uri, original.uri);
+        assert_eq!(forked.source_id, original.source_id);
+        assert_eq!(forked.data, original.data);
+        assert!(forked.response_destination.is_none());
+    }
+
+    #[test]
+    fn test_fork_overrides_uri() {
+        let forked = sample_message().fork(Some("/api/v1/audit".to_string()));
+        assert_eq!(forked.uri, "/api/v1/audit");
+        assert_eq!(forked.data, serde_json::json!({ "id": 42 }));
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Address {
+        city: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct User {
+        name: String,
+        address: Address,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct CreateUser {
+        user: User,
+    }
+
+    #[test]
+    fn test_parse_data_reports_missing_nested_field_path() {
+        let mut message = sample_message();
+        message.data = serde_json::json!({
+            "user": { "name": "Alice", "address": { "street": "Main St" } }
+        });
+
+        let error = message.parse_data::<CreateUser>().unwrap_err();
+
+        match &error {
+            KafkaError::ValidationError { path, message } => {
+                assert_eq!(path, "user.address");
+                assert!(message.contains("missing field `city`"));
+            }
+            other => panic!("expected ValidationError, got {:?}", other),
         }
+        assert_eq!(
+            error.to_response().status.unwrap().data,
+            Some(serde_json::json!({ "path": "user.address" }))
+        );
+    }
+
+    #[test]
+    fn test_parse_data_reports_wrong_type_path() {
+        let mut message = sample_message();
+        message.data = serde_json::json!({
+            "user": { "name": 7, "address": { "city": "Hanoi" } }
+        });
+
+        let error = message.parse_data::<CreateUser>().unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Validation Error at user.name:"));
+    }
+
+    #[derive(Debug, Serialize)]
+    struct MyData {
+        id: u64,
+        amount: f64,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_typed_response_serializes_with_field_types() {
+        let response: Response<(), MyData> = Response::success(MyData {
+            id: 42,
+            amount: 9.5,
+            tags: vec!["vip".to_string()],
+        });
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({
+                "status": null,
+                "data": { "id": 42, "amount": 9.5, "tags": ["vip"] }
+            })
+        );
+
+        let error: Response<MyData, ()> = Response::error(
+            Status::new(ErrorCode::ValueInvalid, "amount too large").with_data(MyData {
+                id: 42,
+                amount: 1e9,
+                tags: Vec::new(),
+            }),
+        );
+
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["status"]["code"], "VALUE_INVALID");
+        assert_eq!(value["status"]["data"]["id"], 42);
+        assert!(value["data"].is_null());
+        assert_eq!(
+            error.status.unwrap().error_code(),
+            Some(ErrorCode::ValueInvalid)
+        );
+    }
+
+    fn millis_ago(ms: i64) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64
+            - ms
+    }
+
+    #[test]
+    fn test_compute_latency_per_timestamp_type() {
+        let create_time = compute_latency(Timestamp::CreateTime(millis_ago(5_000)));
+        assert!((5_000..6_000).contains(&create_time));
+
+        let log_append_time = compute_latency(Timestamp::LogAppendTime(millis_ago(2_000)));
+        assert!((2_000..3_000).contains(&log_append_time));
+
+        assert_eq!(compute_latency(Timestamp::NotAvailable), 0);
+    }
+
+    #[test]
+    fn test_owned_message_latency_uses_timestamp() {
+        let message = |timestamp| TestMessage::new("orders").with_timestamp(timestamp).build();
+
+        let expired = message(Timestamp::CreateTime(millis_ago(90_000)));
+        assert!(expired.is_expired(60));
+        assert!(!expired.is_expired(120));
+
+        let unknown = message(Timestamp::NotAvailable);
+        assert_eq!(unknown.get_latency_formatted(), "N/A");
+        assert!(!unknown.is_expired(0));
     }
 }
@@ -0,0 +1,222 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::pin,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use futures::{stream::FuturesUnordered, Stream, StreamExt};
+use rdkafka::{message::OwnedMessage, Message};
+use tracing::error;
+
+/// FairScheduler dispatches messages round-robin across topics so that a busy topic cannot
+/// monopolize the concurrency budget.
+///
+/// Consumed messages are buffered per topic, up to `queue_capacity` each, and handlers are
+/// started by taking one message from each topic in turn. Each topic is additionally capped at
+/// `max_per_topic` running handlers, so a low-volume topic always finds a free slot. Fairness
+/// applies to buffered messages: once a topic's buffer is full, consumption waits for it to drain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FairScheduler {
+    concurrency_limit: usize,
+    max_per_topic: usize,
+    queue_capacity: usize,
+}
+
+#[derive(Default)]
+struct TopicQueues {
+    queues: HashMap<String, VecDeque<OwnedMessage>>,
+    in_flight: HashMap<String, usize>,
+    order: VecDeque<String>,
+}
+
+impl FairScheduler {
+    /// Creates a new FairScheduler.
+    ///
+    /// # Arguments
+    ///
+    /// * `concurrency_limit` - The maximum number of handlers running across all topics.
+    /// * `max_per_topic` - The maximum number of handlers running for a single topic.
+    /// * `queue_capacity` - The number of messages buffered per topic before consumption waits.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of FairScheduler.
+    pub fn new(concurrency_limit: usize, max_per_topic: usize, queue_capacity: usize) -> Self {
+        let concurrency_limit = concurrency_limit.max(1);
+        Self {
+            concurrency_limit,
+            max_per_topic: max_per_topic.clamp(1, concurrency_limit),
+            queue_capacity: queue_capacity.max(1),
+        }
+    }
+
+    /// Retrieves the maximum number of handlers running for a single topic.
+    pub fn max_per_topic(&self) -> usize {
+        self.max_per_topic
+    }
+
+    /// Processes a stream of messages until it ends and every buffered message is handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The messages to process.
+    /// * `handler` - The function that processes each message.
+    pub async fn run<S, T, F>(&self, stream: S, handler: Arc<T>)
+    where
+        S: Stream<Item = Result<OwnedMessage, rdkafka::error::KafkaError>>,
+        T: Fn(OwnedMessage) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut stream = pin!(stream.fuse());
+        let mut running = FuturesUnordered::new();
+        let mut topics = TopicQueues::default();
+        let mut stream_done = false;
+
+        loop {
+            while running.len() < self.concurrency_limit {
+                let Some(message) = topics.next(self.max_per_topic) else {
+                    break;
+                };
+                let topic = message.topic().to_string();
+                let handler = handler.clone();
+                running.push(async move {
+                    if let Err(e) = handler(message).await {
+                        error!("error while processing message: {}", e);
+                    }
+                    topic
+                });
+            }
+
+            let can_read = !stream_done && !topics.any_full(self.queue_capacity);
+            if !can_read && running.is_empty() {
+                break;
+            }
+
+            tokio::select! {
+                next = stream.next(), if can_read => match next {
+                    Some(Ok(message)) => topics.push(message),
+                    Some(Err(e)) => error!("error while processing message: {}", e),
+                    None => stream_done = true,
+                },
+                Some(topic) = running.next(), if !running.is_empty() => topics.finish(&topic),
+            }
+        }
+    }
+}
+
+impl TopicQueues {
+    fn push(&mut self, message: OwnedMessage) {
+        let topic = message.topic();
+        if !self.queues.contains_key(topic) {
+            self.order.push_back(topic.to_string());
+        }
+        self.queues
+            .entry(topic.to_string())
+            .or_default()
+            .push_back(message);
+    }
+
+    /// Takes the next message round-robin from a topic below its in-flight cap.
+    fn next(&mut self, max_per_topic: usize) -> Option<OwnedMessage> {
+        for _ in 0..self.order.len() {
+            let topic = self.order.pop_front()?;
+            self.order.push_back(topic.clone());
+
+            let in_flight = self.in_flight.entry(topic.clone()).or_default();
+            if *in_flight >= max_per_topic {
+                continue;
+            }
+            if let Some(message) = self.queues.get_mut(&topic).and_then(VecDeque::pop_front) {
+                *in_flight += 1;
+                return Some(message);
+            }
+        }
+        None
+    }
+
+    fn finish(&mut self, topic: &str) {
+        if let Some(in_flight) = self.in_flight.get_mut(topic) {
+            *in_flight = in_flight.saturating_sub(1);
+        }
+    }
+
+    fn any_full(&self, queue_capacity: usize) -> bool {
+        self.queues
+            .values()
+            .any(|queue| queue.len() >= queue_capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Mutex, time::Duration};
+
+    use crate::test_support::TestMessage;
+
+    fn message(topic: &str, offset: i64) -> OwnedMessage {
+        TestMessage::new(topic).with_offset(offset).build()
+    }
+
+    #[tokio::test]
+    async fn test_low_volume_topic_not_starved() {
+        let busy = (0..200).map(|offset| Ok(message("busy", offset)));
+        let quiet = (0..5).map(|offset| Ok(message("quiet", offset)));
+        let stream = futures::stream::iter(busy.chain(quiet));
+
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let handler = {
+            let completed = completed.clone();
+            Arc::new(move |message: OwnedMessage| {
+                let completed = completed.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    completed.lock().unwrap().push(message.topic().to_string());
+                    Ok(())
+                }
+            })
+        };
+
+        FairScheduler::new(4, 2, 1000).run(stream, handler).await;
+
+        let completed = completed.lock().unwrap();
+        assert_eq!(completed.len(), 205);
+        let last_quiet = completed
+            .iter()
+            .rposition(|topic| topic == "quiet")
+            .unwrap();
+        assert!(
+            last_quiet < 20,
+            "quiet topic finished at position {}",
+            last_quiet
+        );
+    }
+
+    #[tokio::test]
+    async fn test_per_topic_cap_limits_concurrency() {
+        let stream = futures::stream::iter((0..20).map(|offset| Ok(message("busy", offset))));
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let handler = {
+            let (running, peak) = (running.clone(), peak.clone());
+            Arc::new(move |_: OwnedMessage| {
+                let (running, peak) = (running.clone(), peak.clone());
+                async move {
+                    use std::sync::atomic::Ordering;
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(2)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            })
+        };
+
+        FairScheduler::new(8, 3, 1000).run(stream, handler).await;
+
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+}
@@ -1,8 +1,204 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use rdkafka::{config::RDKafkaLogLevel, ClientConfig};
+use tracing::warn;
 use uuid::Uuid;
 
+use crate::kafka::core::{ConfigProblem, KafkaError, ResponseDestination};
+
+/// Common librdkafka properties recognized by `KafkaClientConfig::unknown_keys`.
+///
+/// Not exhaustive; see the librdkafka CONFIGURATION.md for the full list.
+const KNOWN_CONFIG_KEYS: &[&str] = &[
+    "acks",
+    "allow.auto.create.topics",
+    "api.version.request",
+    "auto.commit.interval.ms",
+    "auto.offset.reset",
+    "batch.num.messages",
+    "batch.size",
+    "bootstrap.servers",
+    "check.crcs",
+    "client.id",
+    "client.rack",
+    "compression.codec",
+    "compression.type",
+    "connections.max.idle.ms",
+    "debug",
+    "delivery.timeout.ms",
+    "enable.auto.commit",
+    "enable.auto.offset.store",
+    "enable.idempotence",
+    "enable.partition.eof",
+    "fetch.max.bytes",
+    "fetch.message.max.bytes",
+    "fetch.min.bytes",
+    "fetch.wait.max.ms",
+    "group.id",
+    "group.instance.id",
+    "heartbeat.interval.ms",
+    "isolation.level",
+    "linger.ms",
+    "max.in.flight.requests.per.connection",
+    "max.partition.fetch.bytes",
+    "max.poll.interval.ms",
+    "message.max.bytes",
+    "message.send.max.retries",
+    "message.timeout.ms",
+    "metadata.max.age.ms",
+    "partition.assignment.strategy",
+    "queue.buffering.max.kbytes",
+    "queue.buffering.max.messages",
+    "queue.buffering.max.ms",
+    "queued.max.messages.kbytes",
+    "queued.min.messages",
+    "receive.message.max.bytes",
+    "reconnect.backoff.max.ms",
+    "reconnect.backoff.ms",
+    "request.timeout.ms",
+    "retries",
+    "retry.backoff.max.ms",
+    "retry.backoff.ms",
+    "sasl.kerberos.service.name",
+    "sasl.mechanism",
+    "sasl.mechanisms",
+    "sasl.oauthbearer.config",
+    "sasl.password",
+    "sasl.username",
+    "security.protocol",
+    "session.timeout.ms",
+    "socket.keepalive.enable",
+    "socket.timeout.ms",
+    "ssl.ca.location",
+    "ssl.certificate.location",
+    "ssl.endpoint.identification.algorithm",
+    "ssl.key.location",
+    "ssl.key.password",
+    "statistics.interval.ms",
+    "topic.metadata.propagation.max.ms",
+    "topic.metadata.refresh.fast.interval.ms",
+    "topic.metadata.refresh.interval.ms",
+    "transaction.timeout.ms",
+    "transactional.id",
+];
+
+/// PartitionAssignmentStrategy selects how a consumer group distributes partitions among its members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionAssignmentStrategy {
+    /// Assigns contiguous ranges of each topic's partitions to consumers.
+    Range,
+    /// Assigns partitions one by one to consumers in turn.
+    RoundRobin,
+    /// Keeps existing assignments and only moves the partitions that must change hands,
+    /// so members keep consuming their other partitions during a rebalance.
+    CooperativeSticky,
+}
+
+impl PartitionAssignmentStrategy {
+    /// Retrieves the librdkafka `partition.assignment.strategy` value.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The strategy name understood by librdkafka.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PartitionAssignmentStrategy::Range => "range",
+            PartitionAssignmentStrategy::RoundRobin => "roundrobin",
+            PartitionAssignmentStrategy::CooperativeSticky => "cooperative-sticky",
+        }
+    }
+}
+
+/// CompressionCodec selects how the producer compresses message batches (`compression.type`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionCodec {
+    /// Sends batches uncompressed.
+    #[default]
+    None,
+    /// Best ratio of the classic codecs, at the highest CPU cost.
+    Gzip,
+    /// Fast with a moderate ratio.
+    Snappy,
+    /// Fastest, with a ratio close to Snappy.
+    Lz4,
+    /// Ratio close to gzip at a much lower CPU cost; requires Kafka 2.1 or later and rdkafka
+    /// built with its `zstd` feature, otherwise creating the producer fails.
+    Zstd,
+}
+
+impl CompressionCodec {
+    /// Retrieves the librdkafka `compression.type` value.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The codec name understood by librdkafka.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressionCodec::None => "none",
+            CompressionCodec::Gzip => "gzip",
+            CompressionCodec::Snappy => "snappy",
+            CompressionCodec::Lz4 => "lz4",
+            CompressionCodec::Zstd => "zstd",
+        }
+    }
+}
+
+/// Acks selects how many broker acknowledgements the producer waits for (`acks`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Acks {
+    /// Fire and forget; a send succeeds once the message is written to the socket, so
+    /// broker-side failures are never reported.
+    None,
+    /// Waits for the partition leader to write the message.
+    #[default]
+    Leader,
+    /// Waits for all in-sync replicas to write the message.
+    All,
+}
+
+impl Acks {
+    /// Retrieves the librdkafka `acks` value.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The acks value understood by librdkafka.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Acks::None => "0",
+            Acks::Leader => "1",
+            Acks::All => "all",
+        }
+    }
+}
+
+/// StartPosition selects where a consumer group starts reading partitions it has no committed
+/// offset for; partitions with a committed offset always resume from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartPosition {
+    /// Reads the whole backlog of partitions without a committed offset.
+    #[default]
+    EarliestIfNew,
+    /// Skips the backlog of partitions without a committed offset, e.g. for a brand-new service.
+    LatestIfNew,
+}
+
+impl StartPosition {
+    /// Retrieves the librdkafka `auto.offset.reset` value.
+    ///
+    /// librdkafka checks the committed offsets at assignment and only applies it to partitions
+    /// without one.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The reset policy understood by librdkafka.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StartPosition::EarliestIfNew => "earliest",
+            StartPosition::LatestIfNew => "latest",
+        }
+    }
+}
+
 /// KafkaClientConfig holds the configuration settings for Kafka clients.
 /// It provides methods to customize settings for producers and consumers.
 #[derive(Debug, Clone)]
@@ -15,6 +211,27 @@ pub struct KafkaClientConfig {
     pub conf_map: HashMap<String, String>,
     /// Log level for rdkafka logging
     pub log_level: RDKafkaLogLevel,
+    /// Optional producer linger time (`linger.ms`), librdkafka default when None
+    pub linger: Option<Duration>,
+    /// Optional maximum number of messages per producer batch (`batch.num.messages`)
+    pub batch_num_messages: Option<usize>,
+    /// Optional consumer auto commit interval (`auto.commit.interval.ms`), librdkafka default when None
+    pub auto_commit_interval: Option<Duration>,
+    /// Optional size in bytes above which the producer gzips the envelope `data` field
+    pub compress_payload_over_bytes: Option<usize>,
+    /// Whether received and sent message payloads are logged; only metadata is logged when false
+    pub log_payloads: bool,
+    /// Whether unrecognized configuration keys fail client creation instead of only warning
+    pub strict_config: bool,
+    /// Optional consumer partition assignment strategy, librdkafka default (range, roundrobin) when None
+    pub partition_assignment_strategy: Option<PartitionAssignmentStrategy>,
+    /// Producer batch compression (`compression.type`), left unset when `CompressionCodec::None`
+    pub compression: CompressionCodec,
+    /// Optional producer acknowledgements (`acks`); `Acks::Leader` when None, except for the
+    /// StreamHandler and RequestSender producers which use `Acks::All`
+    pub acks: Option<Acks>,
+    /// Where the consumer starts on partitions without a committed offset (`auto.offset.reset`)
+    pub start_position: StartPosition,
 }
 
 impl KafkaClientConfig {
@@ -43,9 +260,137 @@ impl KafkaClientConfig {
             topics: None,
             conf_map,
             log_level: RDKafkaLogLevel::Info,
+            linger: None,
+            batch_num_messages: None,
+            auto_commit_interval: None,
+            compress_payload_over_bytes: None,
+            log_payloads: false,
+            strict_config: false,
+            partition_assignment_strategy: None,
+            compression: CompressionCodec::None,
+            acks: None,
+            start_position: StartPosition::EarliestIfNew,
         }
     }
 
+    /// Bootstrap server of a broker running on the local machine with the default port.
+    pub const LOCAL_BOOTSTRAP_SERVERS: &'static str = "localhost:9092";
+
+    /// Creates a KafkaClientConfig for a broker on `localhost:9092`, for tests and examples.
+    ///
+    /// Topics are created on first use and socket, session and metadata timeouts are shortened
+    /// so an unreachable broker or a new topic is noticed within seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster_id` - A unique identifier for the Kafka cluster.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of KafkaClientConfig.
+    pub fn local<S: Into<String>>(cluster_id: S) -> Self {
+        Self::for_test_broker(cluster_id.into(), Self::LOCAL_BOOTSTRAP_SERVERS.to_string())
+    }
+
+    /// Creates a KafkaClientConfig for an ephemeral broker, e.g. one started by testcontainers,
+    /// listening on `localhost:<port>`.
+    ///
+    /// Uses the same settings as `local`. The cluster id is unique per call, so every test gets
+    /// its own consumer group and default topic. The default `StartPosition::EarliestIfNew` makes
+    /// consumption start from the earliest offset, so messages produced before the consumer joins
+    /// are not missed.
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - The port the broker is mapped to on the host.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of KafkaClientConfig.
+    pub fn testcontainers(port: u16) -> Self {
+        Self::for_test_broker(
+            format!("testcontainers-{}", Uuid::new_v4()),
+            format!("localhost:{}", port),
+        )
+    }
+
+    /// Creates a KafkaClientConfig with the settings shared by `local` and `testcontainers`.
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster_id` - A unique identifier for the Kafka cluster.
+    /// * `bootstrap_servers` - A comma-separated list of Kafka brokers.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of KafkaClientConfig.
+    fn for_test_broker(cluster_id: String, bootstrap_servers: String) -> Self {
+        Self::new(cluster_id, bootstrap_servers)
+            .set("allow.auto.create.topics", "true")
+            .set("socket.timeout.ms", "10000")
+            .set("session.timeout.ms", "6000")
+            .set("topic.metadata.refresh.interval.ms", "1000")
+    }
+
+    /// Environment variable holding the cluster identifier.
+    pub const ENV_CLUSTER_ID: &'static str = "KAFKA_CLUSTER_ID";
+    /// Environment variable holding the comma-separated list of brokers.
+    pub const ENV_BOOTSTRAP_SERVERS: &'static str = "KAFKA_BOOTSTRAP_SERVERS";
+    /// Optional environment variable holding a comma-separated list of topics.
+    pub const ENV_TOPICS: &'static str = "KAFKA_TOPICS";
+
+    /// Creates a KafkaClientConfig from environment variables.
+    ///
+    /// Reads `KAFKA_CLUSTER_ID` and `KAFKA_BOOTSTRAP_SERVERS` (required) and
+    /// `KAFKA_TOPICS` (optional, comma-separated).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, KafkaError>` - The configuration, or a ConfigurationError if a required variable is missing.
+    pub fn from_env() -> Result<Self, KafkaError> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Creates a KafkaClientConfig from a key lookup function, using the same keys as `from_env`.
+    ///
+    /// # Arguments
+    ///
+    /// * `lookup` - A function returning the value for a variable name, if set.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, KafkaError>` - The configuration, or a ConfigurationError if a required key is missing.
+    pub fn from_lookup<F>(lookup: F) -> Result<Self, KafkaError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let required = |key: &str| {
+            lookup(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| {
+                    KafkaError::ConfigurationError(format!("missing environment variable {}", key))
+                })
+        };
+
+        let cluster_id = required(Self::ENV_CLUSTER_ID)?;
+        let bootstrap_servers = required(Self::ENV_BOOTSTRAP_SERVERS)?;
+        let mut config = Self::new(cluster_id, bootstrap_servers);
+
+        if let Some(topics) = lookup(Self::ENV_TOPICS) {
+            let topics: Vec<String> = topics
+                .split(',')
+                .map(|topic| topic.trim().to_string())
+                .filter(|topic| !topic.is_empty())
+                .collect();
+            if !topics.is_empty() {
+                config = config.with_topics(topics);
+            }
+        }
+
+        Ok(config)
+    }
+
     /// Sets the topics for the consumer to subscribe to.
     ///
     /// # Arguments
@@ -60,6 +405,187 @@ impl KafkaClientConfig {
         self
     }
 
+    /// Sets how long the producer waits to accumulate messages into a batch (`linger.ms`).
+    ///
+    /// A higher linger trades per-message latency for throughput: every send may wait up to
+    /// this long, but more messages share a single request to the broker. Leaving it unset
+    /// keeps the librdkafka default (5ms), which favors latency.
+    ///
+    /// # Arguments
+    ///
+    /// * `linger` - The maximum time to wait before sending a batch.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_linger(mut self, linger: Duration) -> Self {
+        self.linger = Some(linger);
+        self
+    }
+
+    /// Sets the maximum number of messages the producer puts in one batch (`batch.num.messages`).
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_num_messages` - The maximum number of messages per batch.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_batch_num_messages(mut self, batch_num_messages: usize) -> Self {
+        self.batch_num_messages = Some(batch_num_messages);
+        self
+    }
+
+    /// Sets how often the consumer commits offsets automatically (`auto.commit.interval.ms`).
+    ///
+    /// A shorter interval narrows the window of messages replayed after a crash at the cost of
+    /// more commit requests. Leaving it unset keeps the librdkafka default (5s).
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval between automatic offset commits.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_auto_commit_interval(mut self, interval: Duration) -> Self {
+        self.auto_commit_interval = Some(interval);
+        self
+    }
+
+    /// Sets how the consumer group assigns partitions (`partition.assignment.strategy`).
+    ///
+    /// The eager strategies (`Range`, `RoundRobin`) revoke every partition from every member
+    /// during a rebalance, pausing all consumption. `CooperativeSticky` only moves the partitions
+    /// that change owner, which reduces disruption in large groups. All members of a group must
+    /// use the same protocol, so switching a running group between eager and cooperative
+    /// strategies requires a rolling migration.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - The partition assignment strategy.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_partition_assignment_strategy(
+        mut self,
+        strategy: PartitionAssignmentStrategy,
+    ) -> Self {
+        self.partition_assignment_strategy = Some(strategy);
+        self
+    }
+
+    /// Sets how the producer compresses message batches (`compression.type`).
+    ///
+    /// Compression is applied by librdkafka to whole batches and is transparent to consumers.
+    /// With `CompressionCodec::None`, the default, `compression.type` is left to a value given
+    /// with `set` or to the librdkafka default (none).
+    ///
+    /// # Arguments
+    ///
+    /// * `compression` - The compression codec.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_compression(mut self, compression: CompressionCodec) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets how many broker acknowledgements the producer waits for (`acks`).
+    ///
+    /// With `Acks::Leader` or `Acks::All`, sends wait for the broker and delivery failures are
+    /// returned as `KafkaError::ConnectionError`; with `Acks::None` they go unnoticed.
+    ///
+    /// # Arguments
+    ///
+    /// * `acks` - The acknowledgements to wait for.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_acks(mut self, acks: Acks) -> Self {
+        self.acks = Some(acks);
+        self
+    }
+
+    /// Sets where the consumer starts on partitions its group has no committed offset for.
+    ///
+    /// Partitions with a committed offset resume from it whatever the start position, so
+    /// `StartPosition::LatestIfNew` only skips the backlog the first time a group consumes.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_position` - The start position for partitions without a committed offset.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_start_position(mut self, start_position: StartPosition) -> Self {
+        self.start_position = start_position;
+        self
+    }
+
+    /// Uses `acks` unless acknowledgements were set with `with_acks`.
+    pub(crate) fn with_default_acks(mut self, acks: Acks) -> Self {
+        self.acks.get_or_insert(acks);
+        self
+    }
+
+    /// Compresses the `data` field of sent envelopes larger than `bytes`.
+    ///
+    /// Compressed data is gzipped, base64 encoded and marked with `contentEncoding: "gzip"`.
+    /// `ParsedMessage::parse_from_string` decompresses it transparently, so consumers must run a
+    /// version of this library that understands the encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The serialized size of `data` above which it is compressed.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_compress_payload_over_bytes(mut self, bytes: usize) -> Self {
+        self.compress_payload_over_bytes = Some(bytes);
+        self
+    }
+
+    /// Enables logging the full payload of every received and sent message.
+    ///
+    /// Disabled by default because payloads may contain personal data and logging them at INFO
+    /// produces a lot of output; only topic, partition, offset, size and latency are logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `log_payloads` - Whether to log received and sent payloads.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_log_payloads(mut self, log_payloads: bool) -> Self {
+        self.log_payloads = log_payloads;
+        self
+    }
+
+    /// Makes unrecognized configuration keys fail client creation.
+    ///
+    /// By default unrecognized keys only log a warning; see `unknown_keys`.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict_config` - Whether unrecognized keys are an error.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaClientConfig instance.
+    pub fn with_strict_config(mut self, strict_config: bool) -> Self {
+        self.strict_config = strict_config;
+        self
+    }
+
     /// Sets a custom configuration key-value pair.
     ///
     /// # Arguments
@@ -124,6 +650,24 @@ impl KafkaClientConfig {
         &self.cluster_id
     }
 
+    /// Builds a response destination pointing back to this service.
+    ///
+    /// The topic is the cluster ID, which is the topic the service listens on by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI that should receive the response.
+    ///
+    /// # Returns
+    ///
+    /// * `ResponseDestination` - The response destination for this service.
+    pub fn response_destination<S: Into<String>>(&self, uri: S) -> ResponseDestination {
+        ResponseDestination {
+            topic: self.cluster_id.clone(),
+            uri: uri.into(),
+        }
+    }
+
     /// Retrieves the list of topics.
     ///
     /// # Returns
@@ -133,6 +677,123 @@ impl KafkaClientConfig {
         self.topics.clone()
     }
 
+    /// Retrieves the configuration keys that are not common librdkafka properties.
+    ///
+    /// Such keys are usually typos (e.g. `bootstap.servers`) that librdkafka would otherwise
+    /// reject or silently ignore.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - The unrecognized keys, sorted.
+    pub fn unknown_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .conf_map
+            .keys()
+            .filter(|key| !KNOWN_CONFIG_KEYS.contains(&key.as_str()))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Checks the configuration keys, warning about unrecognized ones.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Ok, or a ConfigurationError listing the unrecognized keys when `strict_config` is set.
+    pub fn validate_keys(&self) -> Result<(), KafkaError> {
+        let unknown = self.unknown_keys();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        if self.strict_config {
+            return Err(KafkaError::ConfigurationError(format!(
+                "unrecognized config keys: {}",
+                unknown.join(", ")
+            )));
+        }
+
+        for key in &unknown {
+            warn!("unrecognized kafka config key: {}", key);
+        }
+        Ok(())
+    }
+
+    /// Lists every problem in the configuration: a missing `bootstrap.servers`, an empty
+    /// `cluster_id` and incomplete SASL settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<ConfigProblem>` - Each problem, empty when the configuration is usable.
+    pub fn configuration_issues(&self) -> Vec<ConfigProblem> {
+        let value = |key: &str| {
+            self.get_conf_with_key(key)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+        let mut issues = Vec::new();
+
+        if value("bootstrap.servers").is_none() {
+            issues.push(ConfigProblem::MissingBootstrapServers);
+        }
+        if self.cluster_id.trim().is_empty() {
+            issues.push(ConfigProblem::EmptyClusterId);
+        }
+
+        let protocol = value("security.protocol").map(str::to_ascii_uppercase);
+        let sasl_protocol = matches!(
+            protocol.as_deref(),
+            Some("SASL_PLAINTEXT") | Some("SASL_SSL")
+        );
+        let mechanism = value("sasl.mechanism")
+            .or_else(|| value("sasl.mechanisms"))
+            .map(str::to_ascii_uppercase);
+
+        match (&mechanism, sasl_protocol) {
+            (None, true) => issues.push(ConfigProblem::MissingSaslMechanism {
+                protocol: protocol.unwrap_or_default(),
+            }),
+            (Some(mechanism), false) => issues.push(ConfigProblem::SaslWithoutSaslProtocol {
+                mechanism: mechanism.clone(),
+            }),
+            _ => {}
+        }
+
+        if let Some(mechanism) = mechanism.filter(|m| m == "PLAIN" || m.starts_with("SCRAM-")) {
+            for key in ["sasl.username", "sasl.password"] {
+                if value(key).is_none() {
+                    issues.push(ConfigProblem::MissingSaslCredential {
+                        mechanism: mechanism.clone(),
+                        key: key.to_string(),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Checks the whole configuration, reporting every problem at once.
+    ///
+    /// Combines `configuration_issues` with `validate_keys`, so unrecognized keys are listed
+    /// too when `strict_config` is set.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Ok, or an InvalidConfiguration listing all the problems found.
+    pub fn validate(&self) -> Result<(), KafkaError> {
+        let mut issues = self.configuration_issues();
+        if self.validate_keys().is_err() {
+            issues.push(ConfigProblem::UnrecognizedKeys(self.unknown_keys()));
+        }
+
+        if issues.is_empty() {
+            return Ok(());
+        }
+        Err(KafkaError::InvalidConfiguration(issues))
+    }
+
     /// Converts the configuration to a rdkafka ClientConfig.
     ///
     /// # Returns
@@ -147,3 +808,181 @@ impl KafkaClientConfig {
         config
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_from_lookup() {
+        let config = KafkaClientConfig::from_lookup(lookup_from(&[
+            ("KAFKA_CLUSTER_ID", "orders-service"),
+            ("KAFKA_BOOTSTRAP_SERVERS", "broker-1:9092,broker-2:9092"),
+            ("KAFKA_TOPICS", "orders, payments,"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.get_cluster_id(), "orders-service");
+        assert_eq!(
+            config.get_conf_with_key("bootstrap.servers"),
+            Some("broker-1:9092,broker-2:9092")
+        );
+        assert_eq!(
+            config.get_topics(),
+            Some(vec!["orders".to_string(), "payments".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_local_config() {
+        let config = KafkaClientConfig::local("orders-service");
+
+        assert_eq!(config.cluster_id, "orders-service");
+        assert_eq!(
+            config.get_conf_with_key("bootstrap.servers"),
+            Some("localhost:9092")
+        );
+        assert_eq!(
+            config.get_conf_with_key("allow.auto.create.topics"),
+            Some("true")
+        );
+        assert_eq!(config.get_conf_with_key("session.timeout.ms"), Some("6000"));
+        assert!(config.unknown_keys().is_empty());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_testcontainers_config() {
+        let config = KafkaClientConfig::testcontainers(49153);
+        let other = KafkaClientConfig::testcontainers(49153);
+
+        assert_eq!(
+            config.get_conf_with_key("bootstrap.servers"),
+            Some("localhost:49153")
+        );
+        assert_eq!(config.get_conf_with_key("auto.offset.reset"), None);
+        assert_eq!(config.start_position, StartPosition::EarliestIfNew);
+        assert!(config.cluster_id.starts_with("testcontainers-"));
+        assert_ne!(config.cluster_id, other.cluster_id);
+    }
+
+    #[test]
+    fn test_from_lookup_without_topics() {
+        let config = KafkaClientConfig::from_lookup(lookup_from(&[
+            ("KAFKA_CLUSTER_ID", "orders-service"),
+            ("KAFKA_BOOTSTRAP_SERVERS", "localhost:9092"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.get_topics(), None);
+    }
+
+    #[test]
+    fn test_from_lookup_missing_variable() {
+        let result =
+            KafkaClientConfig::from_lookup(lookup_from(&[("KAFKA_CLUSTER_ID", "orders-service")]));
+
+        let error = result.unwrap_err();
+        assert!(matches!(error, KafkaError::ConfigurationError(_)));
+        assert!(error.to_string().contains("KAFKA_BOOTSTRAP_SERVERS"));
+    }
+
+    #[test]
+    fn test_response_destination_uses_cluster_id() {
+        let config =
+            KafkaClientConfig::new("orders-service".to_string(), "localhost:9092".to_string());
+        let destination = config.response_destination("/api/v1/orders/reply");
+
+        assert_eq!(destination.topic, config.get_cluster_id());
+        assert_eq!(destination.uri, "/api/v1/orders/reply");
+        assert!(destination.should_response());
+    }
+
+    #[test]
+    fn test_recognized_key_passes_validation() {
+        let config =
+            KafkaClientConfig::new("orders-service".to_string(), "localhost:9092".to_string())
+                .set("session.timeout.ms", "10000")
+                .with_strict_config(true);
+
+        assert!(config.unknown_keys().is_empty());
+        assert!(config.validate_keys().is_ok());
+    }
+
+    #[test]
+    fn test_typo_key_is_reported() {
+        let config =
+            KafkaClientConfig::new("orders-service".to_string(), "localhost:9092".to_string())
+                .set("bootstap.servers", "broker:9092");
+
+        assert_eq!(config.unknown_keys(), vec!["bootstap.servers"]);
+        assert!(config.validate_keys().is_ok());
+
+        let error = config.with_strict_config(true).validate_keys().unwrap_err();
+        assert!(matches!(error, KafkaError::ConfigurationError(_)));
+        assert!(error.to_string().contains("bootstap.servers"));
+    }
+
+    #[test]
+    fn test_validate_accepts_complete_sasl_config() {
+        let config =
+            KafkaClientConfig::new("orders-service".to_string(), "localhost:9092".to_string())
+                .set("security.protocol", "SASL_SSL")
+                .set("sasl.mechanism", "SCRAM-SHA-512")
+                .set("sasl.username", "orders")
+                .set("sasl.password", "secret");
+
+        assert!(config.configuration_issues().is_empty());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems_at_once() {
+        let config = KafkaClientConfig::new(" ".to_string(), String::new())
+            .set("security.protocol", "SASL_PLAINTEXT")
+            .set("sasl.mechanism", "PLAIN")
+            .set("sasl.username", "orders")
+            .set("bootstap.servers", "broker:9092")
+            .with_strict_config(true);
+
+        let password = ConfigProblem::MissingSaslCredential {
+            mechanism: "PLAIN".to_string(),
+            key: "sasl.password".to_string(),
+        };
+        assert_eq!(
+            config.configuration_issues(),
+            vec![
+                ConfigProblem::MissingBootstrapServers,
+                ConfigProblem::EmptyClusterId,
+                password.clone(),
+            ]
+        );
+
+        let error = config.validate().unwrap_err();
+        let KafkaError::InvalidConfiguration(problems) = &error else {
+            panic!("expected InvalidConfiguration, got {:?}", error);
+        };
+        assert_eq!(
+            *problems,
+            vec![
+                ConfigProblem::MissingBootstrapServers,
+                ConfigProblem::EmptyClusterId,
+                password,
+                ConfigProblem::UnrecognizedKeys(vec!["bootstap.servers".to_string()]),
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Configuration Error: missing bootstrap.servers; empty cluster_id; \
+             sasl.mechanism PLAIN requires sasl.password; unrecognized config keys: bootstap.servers"
+        );
+    }
+}
@@ -1,31 +1,82 @@
 use anyhow::{Context, Result};
-use futures::StreamExt;
+use futures::{future, future::BoxFuture, FutureExt, Stream, StreamExt};
 use rdkafka::{
     consumer::{BaseConsumer, Consumer, ConsumerContext, Rebalance, StreamConsumer},
-    message::OwnedMessage,
-    ClientContext,
+    message::{BorrowedMessage, OwnedMessage},
+    ClientConfig, ClientContext, Message, Offset, TopicPartitionList,
 };
-use std::{future::Future, sync::Arc};
-use tracing::{error, info};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
 
-use crate::kafka::core::KafkaClientConfig;
+use crate::kafka::{
+    core::{
+        CompletionDeferred, ConsumerHandle, ConsumerMetrics, ConsumerMetricsSnapshot,
+        FairScheduler, KafkaClientConfig, KafkaError, KeyOrderedDispatcher, OffsetCommitter,
+        OffsetTracker, RedeliveryRequested, Shutdown, TrackedOffsets,
+    },
+    retry::RetryPolicy,
+};
 
-pub struct CustomContext;
+/// CustomContext logs rebalances and resets the tracked offsets of the partitions they move.
+pub struct CustomContext {
+    tracker: Arc<OffsetTracker>,
+}
+
+impl CustomContext {
+    /// Forgets the offsets tracked for the partitions of a rebalance.
+    ///
+    /// # Arguments
+    ///
+    /// * `partitions` - The revoked or assigned partitions.
+    fn reset_partitions(&self, partitions: &TopicPartitionList) {
+        for element in partitions.elements() {
+            self.tracker.reset(element.topic(), element.partition());
+        }
+    }
+}
 
 impl ClientContext for CustomContext {}
 
 impl ConsumerContext for CustomContext {
     fn pre_rebalance(&self, _: &BaseConsumer<Self>, rebalance: &Rebalance) {
         info!("pre rebalance {:?}", rebalance);
+        if let Rebalance::Revoke(partitions) = rebalance {
+            self.reset_partitions(partitions);
+        }
     }
 
     fn post_rebalance(&self, _: &BaseConsumer<Self>, rebalance: &Rebalance) {
         info!("post rebalance {:?}", rebalance);
+        // Consumption of an assigned partition resumes from its committed offset
+        if let Rebalance::Assign(partitions) = rebalance {
+            self.reset_partitions(partitions);
+        }
     }
 }
 
 pub type LoggingConsumer = StreamConsumer<CustomContext>;
 
+/// StreamEnd tells why a processing loop stopped taking messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEnd {
+    /// The loop was stopped by `stop`, `shutdown` or a shutdown trigger.
+    Stopped,
+    /// The message stream closed on its own; the consumer no longer processes messages.
+    Closed,
+}
+
+/// Callback invoked when a processing loop's message stream ends.
+pub type StreamEndCallback = Arc<dyn Fn(StreamEnd) + Send + Sync>;
+
 /// KafkaConsumer is responsible for consuming messages from Kafka topics asynchronously.
 /// It uses a custom context for logging and supports concurrent message processing.
 pub struct KafkaConsumer {
@@ -33,9 +84,53 @@ pub struct KafkaConsumer {
     pub consumer: Arc<LoggingConsumer>,
     /// Maximum number of messages to process concurrently
     pub concurrency_limit: usize,
+    /// Number of serial workers when messages are processed in key order, None for unordered
+    pub key_ordering_workers: Option<usize>,
+    /// Round-robin scheduling across topics, None to dispatch in fetch order
+    pub fair_scheduler: Option<FairScheduler>,
+    /// Idle and busy time of the message processing loop
+    metrics: Arc<ConsumerMetrics>,
+    /// Tells the processing loops to stop taking new messages
+    stop_signal: Arc<watch::Sender<bool>>,
+    /// Number of processing loops started by `start` or `start_borrowed` still running
+    running: Arc<watch::Sender<usize>>,
+    /// The settings the consumer was created with, reused by `consume_range`
+    client_config: ClientConfig,
+    /// Invoked when a processing loop's message stream ends
+    on_stream_end: Option<StreamEndCallback>,
+    /// Backoff and retry bound for reconnecting the stream of `start`, None to not reconnect
+    reconnect: Option<RetryPolicy>,
+    /// Backoff and bound for handling a message again when its handler requests redelivery
+    redelivery: RetryPolicy,
+    /// In-flight offsets of the messages taken by `start` or `start_borrowed`, stored once
+    /// processed in order
+    offsets: TrackedOffsets,
+}
+
+/// Counts a processing loop as running until dropped, including when its task is aborted.
+struct RunningGuard(Arc<watch::Sender<usize>>);
+
+impl RunningGuard {
+    fn new(running: Arc<watch::Sender<usize>>) -> Self {
+        running.send_modify(|count| *count += 1);
+        Self(running)
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.send_modify(|count| *count = count.saturating_sub(1));
+    }
 }
 
 impl KafkaConsumer {
+    const REPLAY_METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+    /// How long `consume_range` waits for the next message before ending the replay
+    const REPLAY_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+    const LAG_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+    /// Consecutive stream errors after which the stream is reconnected, when reconnecting
+    const RECONNECT_AFTER_ERRORS: usize = 10;
+
     /// Creates a new KafkaConsumer with the given configuration and concurrency limit.
     ///
     /// # Arguments
@@ -47,21 +142,15 @@ impl KafkaConsumer {
     ///
     /// * `Result<Self>` - Returns a KafkaConsumer instance or an error if creation fails.
     pub fn new(config: KafkaClientConfig, concurrency_limit: usize) -> Result<Self> {
-        let context = CustomContext;
-
-        let mut consumer_config = config.to_client_config();
+        config.validate()?;
 
-        // Consumer-specific settings
-        consumer_config
-            .set("enable.partition.eof", "false")
-            .set("enable.auto.commit", "true")
-            .set("auto.offset.reset", "earliest")
-            .set("session.timeout.ms", "10000")
-            .set("heartbeat.interval.ms", "500")
-            .set("group.id", config.cluster_id.clone())
-            .set("fetch.message.max.bytes", "1000000000");
+        let tracker = Arc::new(OffsetTracker::new());
+        let context = CustomContext {
+            tracker: tracker.clone(),
+        };
+        let client_config = Self::client_config(&config);
 
-        let consumer: LoggingConsumer = consumer_config
+        let consumer: LoggingConsumer = client_config
             .create_with_context(context)
             .context("Consumer creation failed")?;
 
@@ -78,12 +167,480 @@ impl KafkaConsumer {
 
         info!("consumer subscribed to topic: {:?}", topics);
 
+        let consumer = Arc::new(consumer);
+        let offsets = TrackedOffsets::new(tracker, Self::offset_committer(consumer.clone()));
+
         Ok(Self {
-            consumer: Arc::new(consumer),
+            consumer,
             concurrency_limit,
+            key_ordering_workers: None,
+            fair_scheduler: None,
+            metrics: Arc::new(ConsumerMetrics::new()),
+            stop_signal: Arc::new(watch::channel(false).0),
+            running: Arc::new(watch::channel(0).0),
+            client_config,
+            on_stream_end: None,
+            reconnect: None,
+            redelivery: RetryPolicy::default(),
+            offsets,
         })
     }
 
+    /// Reprocesses the messages of a partition between two offsets, inclusive.
+    ///
+    /// Uses a separate consumer assigned to the partition, so the subscription of this consumer
+    /// and the committed offsets of its group are left untouched. Messages are handled one at a
+    /// time, in offset order; a handler error is logged and does not stop the replay. An
+    /// `end_offset` past the end of the partition is clamped to its last offset.
+    ///
+    /// Some offsets hold no message, such as transaction markers or records removed by
+    /// compaction. The replay ends once the consumer's position passes `end_offset`, or when no
+    /// message arrives for 10 seconds, so a range ending on such offsets still returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to replay.
+    /// * `partition` - The partition to replay.
+    /// * `start_offset` - The first offset to process.
+    /// * `end_offset` - The last offset to process.
+    /// * `handler` - A function that processes each message, returning a future.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, KafkaError>` - The number of messages processed, a ConfigurationError for
+    ///   an invalid range, or a ConnectionError if the partition cannot be read.
+    pub async fn consume_range<T, F>(
+        &self,
+        topic: &str,
+        partition: i32,
+        start_offset: i64,
+        end_offset: i64,
+        handler: T,
+    ) -> Result<usize, KafkaError>
+    where
+        T: Fn(OwnedMessage) -> F,
+        F: Future<Output = Result<()>>,
+    {
+        if start_offset < 0 || end_offset < start_offset {
+            return Err(KafkaError::ConfigurationError(format!(
+                "invalid offset range {}..={}",
+                start_offset, end_offset
+            )));
+        }
+
+        let mut replay_config = self.client_config.clone();
+        replay_config.set("enable.auto.commit", "false");
+        let consumer: Arc<StreamConsumer> = Arc::new(replay_config.create().map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to create replay consumer: {}", e))
+        })?);
+
+        let watermark_consumer = consumer.clone();
+        let watermark_topic = topic.to_string();
+        let (_, high) = tokio::task::spawn_blocking(move || {
+            watermark_consumer.fetch_watermarks(
+                &watermark_topic,
+                partition,
+                Self::REPLAY_METADATA_TIMEOUT,
+            )
+        })
+        .await
+        .map_err(|e| KafkaError::InternalServerError(e.to_string()))?
+        .map_err(|e| {
+            KafkaError::ConnectionError(format!(
+                "failed to fetch watermarks of {} [{}]: {}",
+                topic, partition, e
+            ))
+        })?;
+
+        let end_offset = end_offset.min(high - 1);
+        if start_offset > end_offset {
+            info!(
+                "nothing to replay from {} [{}]: {}..={} is past the end",
+                topic, partition, start_offset, end_offset
+            );
+            return Ok(0);
+        }
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(topic, partition, Offset::Offset(start_offset))
+            .and_then(|_| consumer.assign(&tpl))
+            .map_err(|e| {
+                KafkaError::ConnectionError(format!(
+                    "failed to assign {} [{}]: {}",
+                    topic, partition, e
+                ))
+            })?;
+
+        info!(
+            "replaying {} [{}] offsets {}..={}",
+            topic, partition, start_offset, end_offset
+        );
+
+        let mut processed = 0;
+        loop {
+            let Ok(received) =
+                tokio::time::timeout(Self::REPLAY_IDLE_TIMEOUT, consumer.recv()).await
+            else {
+                let position = Self::replay_position(&consumer, topic, partition);
+                if position.is_none_or(|position| position <= end_offset) {
+                    warn!(
+                        "no message from {} [{}] within {:?} at position {:?}, ending replay before offset {}",
+                        topic, partition, Self::REPLAY_IDLE_TIMEOUT, position, end_offset
+                    );
+                }
+                break;
+            };
+            let message = received
+                .map_err(|e| KafkaError::ConnectionError(e.to_string()))?
+                .detach();
+            let offset = message.offset();
+            if offset > end_offset {
+                break;
+            }
+
+            if let Err(e) = handler(message).await {
+                error!(
+                    "error replaying message from {} [{}@{}]: {}",
+                    topic, partition, offset, e
+                );
+            }
+            processed += 1;
+
+            if offset >= end_offset
+                || Self::replay_position(&consumer, topic, partition)
+                    .is_some_and(|position| position > end_offset)
+            {
+                break;
+            }
+        }
+
+        info!(
+            "replayed {} messages from {} [{}]",
+            processed, topic, partition
+        );
+        Ok(processed)
+    }
+
+    /// Retrieves the next offset a replay consumer reads from a partition.
+    ///
+    /// # Arguments
+    ///
+    /// * `consumer` - The replay consumer.
+    /// * `topic` - The replayed topic.
+    /// * `partition` - The replayed partition.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<i64>` - The position, or None if it is not known yet.
+    fn replay_position(consumer: &StreamConsumer, topic: &str, partition: i32) -> Option<i64> {
+        let position = consumer.position().ok()?;
+        match position.find_partition(topic, partition)?.offset() {
+            Offset::Offset(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// Builds the rdkafka ClientConfig used for the consumer.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - KafkaClientConfig containing the necessary settings for the consumer.
+    ///
+    /// # Returns
+    ///
+    /// * `ClientConfig` - The client config with consumer-specific settings applied.
+    pub fn client_config(config: &KafkaClientConfig) -> ClientConfig {
+        let mut consumer_config = config.to_client_config();
+
+        // Consumer-specific settings
+        consumer_config
+            .set("enable.partition.eof", "false")
+            .set("enable.auto.commit", "true")
+            // Offsets are stored once processed, in order; see `OffsetTracker`
+            .set("enable.auto.offset.store", "false")
+            .set("auto.offset.reset", config.start_position.as_str())
+            .set("session.timeout.ms", "10000")
+            .set("heartbeat.interval.ms", "500")
+            .set("group.id", config.cluster_id.clone())
+            .set("fetch.message.max.bytes", "1000000000");
+
+        if let Some(interval) = config.auto_commit_interval {
+            consumer_config.set("auto.commit.interval.ms", interval.as_millis().to_string());
+        }
+
+        if let Some(strategy) = config.partition_assignment_strategy {
+            consumer_config.set("partition.assignment.strategy", strategy.as_str());
+        }
+
+        consumer_config
+    }
+
+    /// Subscribes to additional topics, keeping the current subscription.
+    ///
+    /// Re-subscribing triggers a rebalance of the consumer group.
+    ///
+    /// # Arguments
+    ///
+    /// * `topics` - The topics to add.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns Ok if the subscription is updated, or a KafkaError if it fails.
+    pub fn add_subscription(&self, topics: &[&str]) -> Result<(), KafkaError> {
+        let current = self.subscribed_topics()?;
+
+        let mut merged: Vec<&str> = current.iter().map(|topic| topic.as_str()).collect();
+        for topic in topics {
+            if !merged.contains(topic) {
+                merged.push(topic);
+            }
+        }
+
+        if merged.len() == current.len() {
+            return Ok(());
+        }
+
+        self.consumer.subscribe(&merged).map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to subscribe to {:?}: {}", merged, e))
+        })?;
+
+        info!("consumer subscribed to topic: {:?}", merged);
+
+        Ok(())
+    }
+
+    /// Retrieves the topics the consumer is subscribed to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<String>, KafkaError>` - The subscribed topics.
+    pub fn subscribed_topics(&self) -> Result<Vec<String>, KafkaError> {
+        let subscription = self.consumer.subscription().map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to fetch subscription: {}", e))
+        })?;
+
+        let mut topics: Vec<String> = Vec::new();
+        for element in subscription.elements() {
+            if !topics.iter().any(|topic| topic == element.topic()) {
+                topics.push(element.topic().to_string());
+            }
+        }
+
+        Ok(topics)
+    }
+
+    /// Retrieves the partitions currently assigned to this consumer.
+    ///
+    /// The assignment is empty until the first rebalance after subscribing has completed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(String, i32)>, KafkaError>` - The assigned (topic, partition) pairs.
+    pub fn assignment(&self) -> Result<Vec<(String, i32)>, KafkaError> {
+        let assignment = self.consumer.assignment().map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to fetch assignment: {}", e))
+        })?;
+
+        Ok(assignment
+            .elements()
+            .iter()
+            .map(|element| (element.topic().to_string(), element.partition()))
+            .collect())
+    }
+
+    /// Retrieves how many messages each assigned partition is behind its high watermark.
+    ///
+    /// The lag is measured from the committed offset; partitions without a commit fall back to
+    /// the current position, and to the low watermark when nothing was consumed yet.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<HashMap<(String, i32), i64>, KafkaError>` - The lag per (topic, partition), or a
+    ///   ConnectionError if the offsets or watermarks cannot be fetched.
+    pub async fn fetch_lag(&self) -> Result<HashMap<(String, i32), i64>, KafkaError> {
+        let consumer = self.consumer.clone();
+        tokio::task::spawn_blocking(move || Self::partition_lag(&consumer))
+            .await
+            .map_err(|e| KafkaError::InternalServerError(e.to_string()))?
+    }
+
+    /// Retrieves the lag summed across all assigned partitions.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<i64, KafkaError>` - The total lag, 0 while no partition is assigned.
+    pub async fn total_lag(&self) -> Result<i64, KafkaError> {
+        Ok(self.fetch_lag().await?.values().sum())
+    }
+
+    /// Computes the lag of each assigned partition with blocking broker queries.
+    fn partition_lag(
+        consumer: &LoggingConsumer,
+    ) -> Result<HashMap<(String, i32), i64>, KafkaError> {
+        let assignment = consumer.assignment().map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to fetch assignment: {}", e))
+        })?;
+        if assignment.count() == 0 {
+            return Ok(HashMap::new());
+        }
+
+        let committed = consumer
+            .committed_offsets(assignment, Self::LAG_QUERY_TIMEOUT)
+            .map_err(|e| {
+                KafkaError::ConnectionError(format!("failed to fetch committed offsets: {}", e))
+            })?;
+        let position = consumer.position().map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to fetch positions: {}", e))
+        })?;
+
+        let mut lag = HashMap::new();
+        for element in committed.elements() {
+            let (topic, partition) = (element.topic(), element.partition());
+            let (low, high) = consumer
+                .fetch_watermarks(topic, partition, Self::LAG_QUERY_TIMEOUT)
+                .map_err(|e| {
+                    KafkaError::ConnectionError(format!(
+                        "failed to fetch watermarks of {} [{}]: {}",
+                        topic, partition, e
+                    ))
+                })?;
+
+            let offset = match element.offset() {
+                Offset::Offset(offset) => offset,
+                _ => match position
+                    .find_partition(topic, partition)
+                    .map(|element| element.offset())
+                {
+                    Some(Offset::Offset(offset)) => offset,
+                    _ => low,
+                },
+            };
+            lag.insert((topic.to_string(), partition), (high - offset).max(0));
+        }
+
+        Ok(lag)
+    }
+
+    /// Retrieves the idle and busy time of the message processing loop.
+    ///
+    /// # Returns
+    ///
+    /// * `ConsumerMetricsSnapshot` - A copy of the current metrics.
+    pub fn metrics(&self) -> ConsumerMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Retrieves the number of messages processed per second over the last 10 seconds.
+    ///
+    /// # Returns
+    ///
+    /// * `f64` - The current throughput.
+    pub fn current_throughput(&self) -> f64 {
+        self.metrics.throughput()
+    }
+
+    /// Processes messages with the same key sequentially.
+    ///
+    /// Messages are routed to `workers` serial queues by key hash, so messages for one entity are
+    /// never handled out of order while different keys still run concurrently. The concurrency
+    /// across keys is bounded by `workers` instead of `concurrency_limit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `workers` - The number of serial queues.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaConsumer instance.
+    pub fn with_key_ordering(mut self, workers: usize) -> Self {
+        self.key_ordering_workers = Some(workers.max(1));
+        self
+    }
+
+    /// Dispatches messages round-robin across topics instead of in fetch order.
+    ///
+    /// When subscribed to several topics, a high-volume topic can otherwise take every
+    /// concurrency slot and starve the others. See `FairScheduler` for details. Key ordering,
+    /// when enabled, takes precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_per_topic` - The maximum number of messages of one topic processed concurrently.
+    /// * `queue_capacity` - The number of messages buffered per topic before consumption waits.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaConsumer instance.
+    pub fn with_fair_scheduling(mut self, max_per_topic: usize, queue_capacity: usize) -> Self {
+        self.fair_scheduler = Some(FairScheduler::new(
+            self.concurrency_limit,
+            max_per_topic,
+            queue_capacity,
+        ));
+        self
+    }
+
+    /// Sets a callback invoked when the message stream of a processing loop ends.
+    ///
+    /// A loop ends with `StreamEnd::Stopped` when asked to stop. It ends with `StreamEnd::Closed`
+    /// when the stream closes on its own, leaving a consumer that no longer processes messages;
+    /// this is also logged as an error, and the callback lets the service restart the consumer
+    /// or report itself unhealthy. Loops aborted by an immediate or timed out stop never reach
+    /// the end of their stream, so the callback is not invoked for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The function invoked with the reason the stream ended.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaConsumer instance.
+    pub fn with_on_stream_end<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(StreamEnd) + Send + Sync + 'static,
+    {
+        self.on_stream_end = Some(Arc::new(callback));
+        self
+    }
+
+    /// Reconnects the message stream of `start` when it ends or keeps failing.
+    ///
+    /// Once the stream ends without `stop` being called, or after 10 consecutive errors, the
+    /// consumer waits with the policy's exponential backoff, re-subscribes to its topics and
+    /// resumes. Receiving a message resets the attempt count; after `max_retries` attempts in a
+    /// row the consumer gives up and the stream ends as `StreamEnd::Closed`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The backoff between attempts and the maximum number of attempts.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaConsumer instance.
+    pub fn with_reconnect(mut self, policy: RetryPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Sets how a message is handled again when its handler fails with `RedeliveryRequested`.
+    ///
+    /// The message is redelivered to its handler after the policy's backoff, without being
+    /// consumed again, while its partition's offset stays uncommitted. After `max_retries`
+    /// redeliveries the message is given up on and its offset committed like a failed message's.
+    /// Defaults to `RetryPolicy::default()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The backoff between redeliveries and the maximum number of redeliveries.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaConsumer instance.
+    pub fn with_redelivery(mut self, policy: RetryPolicy) -> Self {
+        self.redelivery = policy;
+        self
+    }
+
     /// Starts the consumer to process messages using the provided handler function.
     ///
     /// # Arguments
@@ -92,44 +649,1212 @@ impl KafkaConsumer {
     ///
     /// # Returns
     ///
-    /// * `Result<tokio::task::JoinHandle<()>>` - Returns a handle to the spawned task or an error if it fails.
-    pub async fn start<T, F>(&self, handler: T) -> Result<tokio::task::JoinHandle<()>>
+    /// * `Result<ConsumerHandle>` - Returns a handle to the spawned task or an error if it fails.
+    pub async fn start<T, F>(&self, handler: T) -> Result<ConsumerHandle>
     where
         T: Fn(OwnedMessage) -> F + Send + Sync + Clone + 'static,
         F: Future<Output = Result<()>> + Send + 'static,
     {
         let (tx, rx) = tokio::sync::oneshot::channel::<()>();
         let consumer = self.consumer.clone();
-        let handler = Arc::new(handler);
-        let handler_for_spawn = handler.clone();
+        let handler = Self::redelivering(Arc::new(handler), self.redelivery);
         let concurrency_limit = self.concurrency_limit;
+        let key_ordering_workers = self.key_ordering_workers;
+        let fair_scheduler = self.fair_scheduler;
+        let metrics = self.metrics.clone();
+        let offsets = self.offsets.clone();
+        // Offsets left in flight by an aborted loop are never completed
+        offsets.tracker().clear();
+        let stopped = self.stopped().shared();
+        let reconnect = self.reconnect;
+        let max_errors = match reconnect {
+            Some(_) => Self::RECONNECT_AFTER_ERRORS,
+            None => usize::MAX,
+        };
 
-        let consumer_task = tokio::spawn(async move {
+        let consumer_task = self.spawn_processing(async move {
             info!("consumer message processing...");
 
             let _ = tx.send(()); // Signal that consumer is ready to process messages
 
-            consumer
-                .stream()
-                .for_each_concurrent(concurrency_limit, |res| async {
+            let mut attempt = 0;
+            loop {
+                let received = AtomicBool::new(false);
+                let stream = consumer.stream().map(|res| res.map(|m| m.detach()));
+                let stream = Self::until_repeated_errors(stream, max_errors)
+                    .inspect(|res| {
+                        if res.is_ok() {
+                            received.store(true, Ordering::Relaxed);
+                        }
+                    })
+                    .take_until(stopped.clone());
+                Self::run_stream(
+                    stream,
+                    handler.clone(),
+                    concurrency_limit,
+                    key_ordering_workers,
+                    fair_scheduler,
+                    metrics.clone(),
+                    Some(offsets.clone()),
+                )
+                .await;
+
+                let Some(policy) = reconnect else {
+                    break;
+                };
+                if stopped.clone().now_or_never().is_some() {
+                    break;
+                }
+                if received.load(Ordering::Relaxed) {
+                    attempt = 0;
+                }
+                if attempt >= policy.max_retries {
+                    error!("consumer gave up reconnecting after {} attempts", attempt);
+                    break;
+                }
+
+                attempt += 1;
+                let backoff = policy.backoff(attempt);
+                warn!(
+                    "consumer message stream interrupted, reconnecting (attempt {}/{}) in {:?}",
+                    attempt, policy.max_retries, backoff
+                );
+                tokio::select! {
+                    _ = stopped.clone() => break,
+                    _ = tokio::time::sleep(backoff) => {}
+                }
+
+                if let Err(e) = Self::resubscribe(&consumer) {
+                    error!("failed to re-subscribe consumer: {}", e);
+                }
+            }
+        });
+
+        // Wait for consumer to be ready
+        rx.await?;
+        info!("consumer is ready to process messages");
+
+        Ok(consumer_task)
+    }
+
+    /// Starts the consumer with a synchronous handler borrowing each message, avoiding the copy
+    /// made by `start`.
+    ///
+    /// `start` detaches every message into an `OwnedMessage`, copying its payload, so that the
+    /// handler future may outlive the next poll. Here the handler receives a `BorrowedMessage`
+    /// that points into librdkafka's buffer and is only valid until the handler returns, so:
+    ///
+    /// * the handler is synchronous and cannot hold the message across an `.await`;
+    /// * anything kept after returning must be copied out explicitly (e.g. `payload().to_vec()`);
+    /// * messages are handled one at a time, in order, so `concurrency_limit`, key ordering and
+    ///   fair scheduling do not apply.
+    ///
+    /// Use it for very high throughput with short-lived handlers, such as counting or
+    /// forwarding; a slow handler stalls the whole consumer. As with `start`, offsets are stored
+    /// once messages are processed, and a handler failing with `RedeliveryRequested` is called
+    /// again with the message, following the `with_redelivery` policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - A function that processes each borrowed message.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ConsumerHandle>` - Returns a handle to the spawned task or an error if it fails.
+    pub async fn start_borrowed<H>(&self, handler: H) -> Result<ConsumerHandle>
+    where
+        H: for<'a> Fn(&BorrowedMessage<'a>) -> Result<()> + Send + Sync + 'static,
+    {
+        let consumer = self.consumer.clone();
+        let metrics = self.metrics.clone();
+        let offsets = self.offsets.clone();
+        offsets.tracker().clear();
+        let redelivery = self.redelivery;
+        let stopped = self.stopped();
+
+        let consumer_task = self.spawn_processing(async move {
+            info!("consumer borrowed message processing...");
+            tokio::pin!(stopped);
+
+            loop {
+                let received = tokio::select! {
+                    _ = &mut stopped => break,
+                    received = consumer.recv() => received,
+                };
+
+                match received {
+                    Err(e) => {
+                        error!("error while processing message: {}", e);
+                    }
+                    Ok(m) => {
+                        offsets.begin(m.topic(), m.partition(), m.offset());
+                        let mut retry = 0;
+                        while !Self::process_borrowed(&m, &handler, &metrics) {
+                            if retry >= redelivery.max_retries {
+                                error!(
+                                    "gave up redelivering {} [{}@{}] after {} redeliveries",
+                                    m.topic(),
+                                    m.partition(),
+                                    m.offset(),
+                                    retry
+                                );
+                                break;
+                            }
+                            retry += 1;
+                            tokio::time::sleep(redelivery.backoff(retry)).await;
+                        }
+                        offsets.complete(m.topic(), m.partition(), m.offset());
+                    }
+                }
+            }
+        });
+
+        Ok(consumer_task)
+    }
+
+    /// Stops the processing task returned by `start` or `start_borrowed`.
+    ///
+    /// With `ShutdownMode::Graceful`, the consumer stops taking new messages and the task is
+    /// given until the deadline to finish the in-flight ones before being aborted. With
+    /// `ShutdownMode::Immediate`, the task is aborted at once; messages being handled are
+    /// dropped and, since their offsets were not stored, redelivered after a restart.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The handle returned by `start` or `start_borrowed`.
+    /// * `shutdown` - How in-flight work is treated.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all in-flight work completed, false if the task was aborted.
+    pub async fn stop(&self, task: ConsumerHandle, shutdown: Shutdown) -> bool {
+        task.stop(shutdown).await
+    }
+
+    /// Shuts the consumer down gracefully.
+    ///
+    /// Stops every processing loop started by `start` or `start_borrowed` from taking new
+    /// messages, waits for their in-flight handlers to complete, then unsubscribes. Unlike
+    /// `stop`, there is no deadline; use `stop` to bound the wait.
+    pub async fn shutdown(&self) {
+        self.stop_signal.send_replace(true);
+
+        let mut running = self.running.subscribe();
+        let _ = running.wait_for(|count| *count == 0).await;
+
+        self.consumer.unsubscribe();
+        info!("consumer shut down");
+    }
+
+    /// Spawns a processing loop, counting it as running until it ends.
+    ///
+    /// When the loop returns, its end is logged and reported to the `on_stream_end` callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `processing` - The processing loop.
+    ///
+    /// # Returns
+    ///
+    /// * `ConsumerHandle` - The handle to the spawned loop.
+    fn spawn_processing<F>(&self, processing: F) -> ConsumerHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let running = RunningGuard::new(self.running.clone());
+        let stop_signal = self.stop_signal.clone();
+        let on_stream_end = self.on_stream_end.clone();
+        let task = tokio::spawn(async move {
+            let _running = running;
+            processing.await;
+
+            let end = if *stop_signal.borrow() {
+                info!("consumer message stream stopped");
+                StreamEnd::Stopped
+            } else {
+                error!("consumer message stream closed, no more messages will be processed");
+                StreamEnd::Closed
+            };
+            if let Some(on_stream_end) = &on_stream_end {
+                on_stream_end(end);
+            }
+        });
+
+        ConsumerHandle::new(task, self.stop_signal.clone(), self.consumer.clone())
+    }
+
+    /// Resets the stop signal and returns a future completing once `stop` is called.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Future<Output = ()>` - Completes when the processing loop should stop taking messages.
+    fn stopped(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.stop_signal.send_replace(false);
+        let mut stop_signal = self.stop_signal.subscribe();
+
+        async move {
+            // A dropped KafkaConsumer never signals, so processing keeps running
+            if stop_signal.wait_for(|stop| *stop).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Runs a synchronous handler on a message reference, recording busy time.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to process.
+    /// * `handler` - A function that processes the message.
+    /// * `metrics` - The metrics to record into.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the message is done with, false if the handler requested its
+    ///   redelivery with `RedeliveryRequested`.
+    fn process_borrowed<M, H>(message: &M, handler: &H, metrics: &ConsumerMetrics) -> bool
+    where
+        M: Message,
+        H: Fn(&M) -> Result<()>,
+    {
+        let _activity = metrics.begin();
+        match handler(message) {
+            Ok(()) => true,
+            Err(e) if e.is::<RedeliveryRequested>() => {
+                debug!(
+                    "redelivery requested for {} [{}@{}]",
+                    message.topic(),
+                    message.partition(),
+                    message.offset()
+                );
+                false
+            }
+            Err(e) => {
+                error!(
+                    "error handling message from {} [{}@{}]: {}",
+                    message.topic(),
+                    message.partition(),
+                    message.offset(),
+                    e
+                );
+                true
+            }
+        }
+    }
+
+    /// Wraps a handler to call it again with its message when it fails with
+    /// `RedeliveryRequested`.
+    ///
+    /// The message is redelivered after the policy's backoff, so its offset stays in flight,
+    /// holding back its partition's commits, until the handler is done with it. Once
+    /// `max_retries` redeliveries were requested, the handler's error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - A function that processes each message, returning a future.
+    /// * `policy` - The backoff between redeliveries and the maximum number of redeliveries.
+    ///
+    /// # Returns
+    ///
+    /// * `Arc<impl Fn>` - The handler, redelivering messages on request.
+    pub(crate) fn redelivering<T, F>(
+        handler: Arc<T>,
+        policy: RetryPolicy,
+    ) -> Arc<impl Fn(OwnedMessage) -> BoxFuture<'static, Result<()>> + Send + Sync + 'static>
+    where
+        T: Fn(OwnedMessage) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        Arc::new(move |message: OwnedMessage| {
+            let handler = handler.clone();
+            async move {
+                let mut retry = 0;
+                loop {
+                    let result = handler(message.clone()).await;
+                    match result {
+                        Err(e) if e.is::<RedeliveryRequested>() => {
+                            if retry >= policy.max_retries {
+                                error!(
+                                    "gave up redelivering {} [{}@{}] after {} redeliveries",
+                                    message.topic(),
+                                    message.partition(),
+                                    message.offset(),
+                                    retry
+                                );
+                                return Err(e);
+                            }
+                            retry += 1;
+                            let backoff = policy.backoff(retry);
+                            debug!(
+                                "redelivery requested for {} [{}@{}] (redelivery {}/{} in {:?})",
+                                message.topic(),
+                                message.partition(),
+                                message.offset(),
+                                retry,
+                                policy.max_retries,
+                                backoff
+                            );
+                            tokio::time::sleep(backoff).await;
+                        }
+                        result => return result,
+                    }
+                }
+            }
+            .boxed()
+        })
+    }
+
+    /// Ends a message stream at the `max_errors`-th error in a row.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The messages to pass through.
+    /// * `max_errors` - The number of consecutive errors ending the stream.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Stream` - The stream, ending early on repeated errors.
+    fn until_repeated_errors<S, M>(
+        stream: S,
+        max_errors: usize,
+    ) -> impl Stream<Item = Result<M, rdkafka::error::KafkaError>>
+    where
+        S: Stream<Item = Result<M, rdkafka::error::KafkaError>>,
+    {
+        stream.scan(0, move |errors, res| {
+            match &res {
+                Ok(_) => *errors = 0,
+                Err(e) => {
+                    *errors += 1;
+                    if *errors >= max_errors {
+                        error!(
+                            "consumer message stream failed {} times in a row: {}",
+                            errors, e
+                        );
+                        return future::ready(None);
+                    }
+                }
+            }
+            future::ready(Some(res))
+        })
+    }
+
+    /// Subscribes the consumer again to its current topics.
+    ///
+    /// # Arguments
+    ///
+    /// * `consumer` - The consumer to re-subscribe.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns Ok if the consumer is subscribed again, or a
+    ///   ConnectionError if it fails.
+    fn resubscribe(consumer: &LoggingConsumer) -> Result<(), KafkaError> {
+        let subscription = consumer.subscription().map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to fetch subscription: {}", e))
+        })?;
+
+        let mut topics: Vec<String> = Vec::new();
+        for element in subscription.elements() {
+            if !topics.iter().any(|topic| topic == element.topic()) {
+                topics.push(element.topic().to_string());
+            }
+        }
+        let topics: Vec<&str> = topics.iter().map(|topic| topic.as_str()).collect();
+
+        consumer.unsubscribe();
+        consumer.subscribe(&topics).map_err(|e| {
+            KafkaError::ConnectionError(format!("failed to subscribe to {:?}: {}", topics, e))
+        })?;
+
+        info!("consumer re-subscribed to topic: {:?}", topics);
+        Ok(())
+    }
+
+    /// Retrieves the offsets tracked for the messages taken by `start`.
+    ///
+    /// A handler returning `CompletionDeferred` completes its message's offset here once the
+    /// message is processed.
+    pub(crate) fn tracked_offsets(&self) -> TrackedOffsets {
+        self.offsets.clone()
+    }
+
+    /// Builds the committer storing processed offsets for the next auto commit.
+    ///
+    /// # Arguments
+    ///
+    /// * `consumer` - The consumer whose offsets are stored.
+    ///
+    /// # Returns
+    ///
+    /// * `OffsetCommitter` - The committer passed to `run_stream`.
+    fn offset_committer(consumer: Arc<LoggingConsumer>) -> OffsetCommitter {
+        Arc::new(move |topic: &str, partition: i32, next_offset: i64| {
+            let mut tpl = TopicPartitionList::new();
+            if let Err(e) = tpl.add_partition_offset(topic, partition, Offset::Offset(next_offset))
+            {
+                warn!(
+                    "failed to add offset for {} [{}] to store: {}",
+                    topic, partition, e
+                );
+                return;
+            }
+
+            if let Err(e) = consumer.store_offsets(&tpl) {
+                warn!(
+                    "failed to store offset {} for {} [{}]: {}",
+                    next_offset, topic, partition, e
+                );
+            }
+        })
+    }
+
+    /// Processes a stream of messages until it ends, recording idle and busy time.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The messages to process.
+    /// * `handler` - A function that processes each message, returning a future.
+    /// * `concurrency_limit` - The maximum number of messages to process concurrently.
+    /// * `key_ordering_workers` - The number of serial workers when processing in key order.
+    /// * `fair_scheduler` - The scheduler used to dispatch round-robin across topics.
+    /// * `metrics` - The metrics to record into.
+    /// * `offsets` - Tracks the offsets of the messages and commits them as processing completes
+    ///   in order, None to not track offsets. A handler failing with `CompletionDeferred`
+    ///   completes later, through `offsets`; redelivery is left to `redelivering`.
+    pub(crate) async fn run_stream<S, T, F>(
+        stream: S,
+        handler: Arc<T>,
+        concurrency_limit: usize,
+        key_ordering_workers: Option<usize>,
+        fair_scheduler: Option<FairScheduler>,
+        metrics: Arc<ConsumerMetrics>,
+        offsets: Option<TrackedOffsets>,
+    ) where
+        S: Stream<Item = Result<OwnedMessage, rdkafka::error::KafkaError>>,
+        T: Fn(OwnedMessage) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        // Offsets are registered as they are consumed, before any reordering by the dispatchers
+        let begin_offsets = offsets.clone();
+        let stream = stream.inspect(move |res| {
+            if let (Some(offsets), Ok(m)) = (&begin_offsets, res) {
+                offsets.begin(m.topic(), m.partition(), m.offset());
+            }
+        });
+
+        let handler = Arc::new(move |message: OwnedMessage| {
+            let handler = handler.clone();
+            let metrics = metrics.clone();
+            let offsets = offsets.clone();
+            async move {
+                let _activity = metrics.begin();
+                let Some(offsets) = offsets else {
+                    return handler(message).await;
+                };
+
+                let topic = message.topic().to_string();
+                let (partition, offset) = (message.partition(), message.offset());
+                let result = handler(message).await;
+                // Completed by whoever the message was handed over to
+                if matches!(&result, Err(e) if e.is::<CompletionDeferred>()) {
+                    return Ok(());
+                }
+                offsets.complete(&topic, partition, offset);
+                result
+            }
+        });
+
+        if let Some(workers) = key_ordering_workers {
+            let dispatcher = KeyOrderedDispatcher::spawn(workers, concurrency_limit, handler);
+
+            stream
+                .for_each(|res| async {
                     match res {
                         Err(e) => {
                             error!("error while processing message: {}", e);
                         }
-                        Ok(m) => {
-                            let owned_message = m.detach();
-                            let handler = handler_for_spawn.clone();
-                            let _ = handler(owned_message).await;
-                        }
+                        Ok(m) => dispatcher.dispatch(m).await,
                     }
                 })
                 .await;
+
+            dispatcher.close().await;
+            return;
+        }
+
+        if let Some(scheduler) = fair_scheduler {
+            scheduler.run(stream, handler).await;
+            return;
+        }
+
+        stream
+            .for_each_concurrent(concurrency_limit, |res| async {
+                match res {
+                    Err(e) => {
+                        error!("error while processing message: {}", e);
+                    }
+                    Ok(m) => {
+                        let handler = handler.clone();
+                        let _ = handler(m).await;
+                    }
+                }
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::core::{PartitionAssignmentStrategy, StartPosition};
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use crate::test_support::TestMessage;
+
+    fn message(offset: i64) -> OwnedMessage {
+        TestMessage::new("test-topic")
+            .with_payload(Vec::new())
+            .with_offset(offset)
+            .build()
+    }
+
+    fn config() -> KafkaClientConfig {
+        KafkaClientConfig::new("test-service".to_string(), "localhost:9092".to_string())
+    }
+
+    #[test]
+    fn test_client_config_auto_commit_interval() {
+        let client_config = KafkaConsumer::client_config(&config());
+        assert_eq!(client_config.get("enable.auto.commit"), Some("true"));
+        assert_eq!(client_config.get("enable.auto.offset.store"), Some("false"));
+        assert_eq!(client_config.get("auto.commit.interval.ms"), None);
+
+        let config = config().with_auto_commit_interval(Duration::from_millis(1500));
+        let client_config = KafkaConsumer::client_config(&config);
+        assert_eq!(client_config.get("auto.commit.interval.ms"), Some("1500"));
+    }
+
+    #[test]
+    fn test_client_config_partition_assignment_strategy() {
+        let client_config = KafkaConsumer::client_config(&config());
+        assert_eq!(client_config.get("partition.assignment.strategy"), None);
+
+        for (strategy, expected) in [
+            (PartitionAssignmentStrategy::Range, "range"),
+            (PartitionAssignmentStrategy::RoundRobin, "roundrobin"),
+            (
+                PartitionAssignmentStrategy::CooperativeSticky,
+                "cooperative-sticky",
+            ),
+        ] {
+            let config = config().with_partition_assignment_strategy(strategy);
+            let client_config = KafkaConsumer::client_config(&config);
+            assert_eq!(
+                client_config.get("partition.assignment.strategy"),
+                Some(expected)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idle_time_accrues_between_messages() {
+        let metrics = Arc::new(ConsumerMetrics::new());
+        let stream = futures::stream::iter(0..3).then(|offset| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(message(offset))
+        });
+        let handler = Arc::new(|_: OwnedMessage| async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            Ok(())
         });
 
-        // Wait for consumer to be ready
-        rx.await?;
-        info!("consumer is ready to process messages");
+        KafkaConsumer::run_stream(stream, handler, 10, None, None, metrics.clone(), None).await;
 
-        Ok(consumer_task)
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.processed, 3);
+        assert_eq!(snapshot.in_flight, 0);
+        assert!(snapshot.idle >= Duration::from_millis(100));
+        assert!(snapshot.busy >= Duration::from_millis(15));
+        assert!(snapshot.busy < snapshot.idle);
+    }
+
+    /// Spawns the processing loop of `start` over an injected stream that never ends on its own.
+    fn spawn_until_stopped<T, F>(
+        consumer: &KafkaConsumer,
+        messages: Vec<OwnedMessage>,
+        handler: T,
+    ) -> ConsumerHandle
+    where
+        T: Fn(OwnedMessage) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let stream = futures::stream::iter(messages.into_iter().map(Ok))
+            .chain(futures::stream::pending())
+            .take_until(consumer.stopped());
+        let metrics = consumer.metrics.clone();
+
+        consumer.spawn_processing(async move {
+            KafkaConsumer::run_stream(stream, Arc::new(handler), 10, None, None, metrics, None)
+                .await;
+        })
+    }
+
+    #[tokio::test]
+    async fn test_immediate_stop_cancels_in_flight_handler() {
+        let consumer = KafkaConsumer::new(config(), 10).unwrap();
+        let completed = Arc::new(AtomicUsize::new(0));
+        let (started_tx, mut started_rx) = tokio::sync::mpsc::unbounded_channel();
+        let handler_completed = completed.clone();
+        let task = spawn_until_stopped(&consumer, vec![message(0)], move |_| {
+            let completed = handler_completed.clone();
+            let _ = started_tx.send(());
+            async move {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                completed.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        started_rx.recv().await.unwrap();
+
+        let start = std::time::Instant::now();
+        assert!(!consumer.stop(task, Shutdown::immediate()).await);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(completed.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_graceful_stop_waits_for_in_flight_handler() {
+        let consumer = KafkaConsumer::new(config(), 10).unwrap();
+        let completed = Arc::new(AtomicUsize::new(0));
+        let (started_tx, mut started_rx) = tokio::sync::mpsc::unbounded_channel();
+        let handler_completed = completed.clone();
+        let task = spawn_until_stopped(&consumer, vec![message(0)], move |_| {
+            let completed = handler_completed.clone();
+            let _ = started_tx.send(());
+            async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                completed.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        started_rx.recv().await.unwrap();
+
+        assert!(
+            consumer
+                .stop(task, Shutdown::graceful(Duration::from_secs(5)))
+                .await
+        );
+        assert_eq!(completed.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_in_flight_handler_then_unsubscribes() {
+        let consumer = KafkaConsumer::new(config(), 10).unwrap();
+        assert_eq!(consumer.subscribed_topics().unwrap(), vec!["test-service"]);
+
+        let completed = Arc::new(AtomicUsize::new(0));
+        let (started_tx, mut started_rx) = tokio::sync::mpsc::unbounded_channel();
+        let handler_completed = completed.clone();
+        let task = spawn_until_stopped(&consumer, vec![message(0), message(1)], move |_| {
+            let completed = handler_completed.clone();
+            let _ = started_tx.send(());
+            async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                completed.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        started_rx.recv().await.unwrap();
+        started_rx.recv().await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), consumer.shutdown())
+            .await
+            .unwrap();
+
+        assert_eq!(completed.load(Ordering::SeqCst), 2);
+        assert!(task.is_finished());
+        assert_eq!(consumer.consumer.subscription().unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_handle_shutdown_trigger_ends_task() {
+        let consumer = KafkaConsumer::new(config(), 10).unwrap();
+        let task = spawn_until_stopped(&consumer, vec![message(0)], |_| async { Ok(()) });
+
+        task.trigger_shutdown();
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_on_stream_end_fires_when_stream_closes() {
+        let ends = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = ends.clone();
+        let consumer = KafkaConsumer::new(config(), 10)
+            .unwrap()
+            .with_on_stream_end(move |end| recorded.lock().unwrap().push(end));
+
+        let stream = futures::stream::iter((0..2).map(|offset| Ok(message(offset))));
+        let metrics = consumer.metrics.clone();
+        let task = consumer.spawn_processing(async move {
+            let handler = Arc::new(|_: OwnedMessage| async { Ok(()) });
+            KafkaConsumer::run_stream(stream, handler, 10, None, None, metrics, None).await;
+        });
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let task = spawn_until_stopped(&consumer, vec![message(0)], |_| async { Ok(()) });
+        task.trigger_shutdown();
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            *ends.lock().unwrap(),
+            vec![StreamEnd::Closed, StreamEnd::Stopped]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_graceful_stop_aborts_after_deadline() {
+        let consumer = KafkaConsumer::new(config(), 10).unwrap();
+        let (started_tx, mut started_rx) = tokio::sync::mpsc::unbounded_channel();
+        let task = spawn_until_stopped(&consumer, vec![message(0)], move |_| {
+            let _ = started_tx.send(());
+            async move {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok(())
+            }
+        });
+        started_rx.recv().await.unwrap();
+
+        let start = std::time::Instant::now();
+        assert!(
+            !consumer
+                .stop(task, Shutdown::graceful(Duration::from_millis(100)))
+                .await
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_completions_commit_contiguous_offsets() {
+        let stored = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let committed = stored.clone();
+        let committer: OffsetCommitter = Arc::new(move |_: &str, _: i32, next_offset: i64| {
+            committed.lock().unwrap().push(next_offset);
+        });
+
+        // Earlier offsets take longer, so completions arrive in reverse order
+        let stream = futures::stream::iter((0..3).map(|offset| Ok(message(offset))));
+        let handler = Arc::new(|m: OwnedMessage| async move {
+            tokio::time::sleep(Duration::from_millis(30 * (3 - m.offset()) as u64)).await;
+            Ok(())
+        });
+
+        let metrics = Arc::new(ConsumerMetrics::new());
+        let offsets = Some(TrackedOffsets::new(
+            Arc::new(OffsetTracker::new()),
+            committer,
+        ));
+        KafkaConsumer::run_stream(stream, handler, 10, None, None, metrics, offsets).await;
+
+        assert_eq!(*stored.lock().unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_redelivery_request_handles_message_again() {
+        let stored = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let committed = stored.clone();
+        let committer: OffsetCommitter = Arc::new(move |_: &str, _: i32, next_offset: i64| {
+            committed.lock().unwrap().push(next_offset);
+        });
+
+        let handled = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = handled.clone();
+        let stream = futures::stream::iter((0..3).map(|offset| Ok(message(offset))));
+        let handler = Arc::new(move |m: OwnedMessage| {
+            let recorded = recorded.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(10 * m.offset() as u64)).await;
+                let mut handled = recorded.lock().unwrap();
+                let first = !handled.contains(&m.offset());
+                handled.push(m.offset());
+                if m.offset() == 1 && first {
+                    return Err(RedeliveryRequested.into());
+                }
+                Ok(())
+            }
+        });
+        let policy = RetryPolicy::new(3, Duration::from_millis(50), Duration::from_millis(50));
+        let handler = KafkaConsumer::redelivering(handler, policy);
+
+        let metrics = Arc::new(ConsumerMetrics::new());
+        let offsets = Some(TrackedOffsets::new(
+            Arc::new(OffsetTracker::new()),
+            committer,
+        ));
+        KafkaConsumer::run_stream(stream, handler, 10, None, None, metrics, offsets).await;
+
+        // Offset 1 is handled again, holding the commit before it until then
+        let handled = handled.lock().unwrap();
+        assert_eq!(handled.iter().filter(|&&offset| offset == 1).count(), 2);
+        assert_eq!(*stored.lock().unwrap(), vec![1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_redelivery_gives_up_after_max_retries() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let attempts = calls.clone();
+        let handler = Arc::new(move |_: OwnedMessage| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async { Err(RedeliveryRequested.into()) }
+        });
+        let policy = RetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(1));
+        let handler = KafkaConsumer::redelivering(handler, policy);
+
+        let error = handler(message(0)).await.unwrap_err();
+
+        assert!(error.is::<RedeliveryRequested>());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_add_subscription_unions_topics() {
+        let consumer = KafkaConsumer::new(config(), 1).unwrap();
+        assert_eq!(consumer.subscribed_topics().unwrap(), vec!["test-service"]);
+
+        consumer
+            .add_subscription(&["tenant-a", "test-service"])
+            .unwrap();
+
+        let mut topics = consumer.subscribed_topics().unwrap();
+        topics.sort();
+        assert_eq!(topics, vec!["tenant-a", "test-service"]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Kafka broker at KAFKA_BOOTSTRAP_SERVERS"]
+    async fn test_add_subscription_consumes_new_topic() {
+        let bootstrap_servers = std::env::var("KAFKA_BOOTSTRAP_SERVERS").unwrap();
+        let second_topic = format!("add-subscription-{}", uuid::Uuid::new_v4());
+        let config = KafkaClientConfig::new(
+            format!("add-subscription-test-{}", uuid::Uuid::new_v4()),
+            bootstrap_servers,
+        );
+
+        let consumer = KafkaConsumer::new(config.clone(), 1).unwrap();
+        consumer.add_subscription(&[&second_topic]).unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let _task = consumer
+            .start(move |message| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(rdkafka::Message::topic(&message).to_string());
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap();
+
+        let producer = crate::kafka::KafkaProducer::new(config).unwrap();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+        loop {
+            producer
+                .send(serde_json::json!({ "hello": "world" }), &second_topic)
+                .await
+                .unwrap();
+
+            match tokio::time::timeout(Duration::from_secs(2), rx.recv()).await {
+                Ok(Some(topic)) if topic == second_topic => break,
+                _ if tokio::time::Instant::now() > deadline => {
+                    panic!("no message consumed from {}", second_topic)
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_client_config_start_position() {
+        let client_config = KafkaConsumer::client_config(&config());
+        assert_eq!(client_config.get("auto.offset.reset"), Some("earliest"));
+
+        let config = config().with_start_position(StartPosition::LatestIfNew);
+        let client_config = KafkaConsumer::client_config(&config);
+        assert_eq!(client_config.get("auto.offset.reset"), Some("latest"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Kafka broker at KAFKA_BOOTSTRAP_SERVERS"]
+    async fn test_latest_if_new_skips_backlog_only_for_new_group() {
+        let bootstrap_servers = std::env::var("KAFKA_BOOTSTRAP_SERVERS").unwrap();
+        let group = format!("latest-if-new-test-{}", uuid::Uuid::new_v4());
+        let config = KafkaClientConfig::new(group.clone(), bootstrap_servers)
+            .with_start_position(StartPosition::LatestIfNew)
+            .with_auto_commit_interval(Duration::from_millis(100));
+
+        type Received = Arc<std::sync::Mutex<Vec<i64>>>;
+
+        let producer = crate::kafka::KafkaProducer::new(config.clone()).unwrap();
+        let send = |seq: i32| {
+            let producer = producer.clone();
+            let group = group.clone();
+            async move {
+                producer
+                    .send(serde_json::json!({ "seq": seq }), &group)
+                    .await
+                    .unwrap();
+            }
+        };
+        let consume = |config: KafkaClientConfig, received: Received| async move {
+            let consumer = KafkaConsumer::new(config, 1).unwrap();
+            let task = consumer
+                .start(move |message| {
+                    let received = received.clone();
+                    async move {
+                        let payload: serde_json::Value =
+                            serde_json::from_slice(message.payload().unwrap()).unwrap();
+                        received
+                            .lock()
+                            .unwrap()
+                            .push(payload["seq"].as_i64().unwrap());
+                        Ok(())
+                    }
+                })
+                .await
+                .unwrap();
+            (consumer, task)
+        };
+        let wait_for = |received: Received, count: usize| async move {
+            let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+            while received.lock().unwrap().len() < count {
+                assert!(
+                    tokio::time::Instant::now() < deadline,
+                    "messages never received"
+                );
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+        };
+
+        // Fresh group: the backlog is skipped, only messages sent after assignment are handled
+        send(0).await;
+        send(1).await;
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (consumer, _task) = consume(config.clone(), received.clone()).await;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+        while consumer.assignment().unwrap().is_empty() {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "partitions never assigned"
+            );
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        send(2).await;
+        wait_for(received.clone(), 1).await;
+        assert_eq!(*received.lock().unwrap(), vec![2]);
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        consumer.shutdown().await;
+        drop(consumer);
+
+        // Existing group: consumption resumes from the committed offset
+        send(3).await;
+        send(4).await;
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (_consumer, _task) = consume(config, received.clone()).await;
+        wait_for(received.clone(), 2).await;
+        assert_eq!(*received.lock().unwrap(), vec![3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_consume_range_rejects_invalid_range() {
+        let consumer = KafkaConsumer::new(config(), 1).unwrap();
+
+        for (start, end) in [(-1, 5), (5, 4)] {
+            let result = consumer
+                .consume_range("test-topic", 0, start, end, |_| async { Ok(()) })
+                .await;
+            assert!(matches!(result, Err(KafkaError::ConfigurationError(_))));
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Kafka broker at KAFKA_BOOTSTRAP_SERVERS"]
+    async fn test_consume_range_processes_exactly_the_range() {
+        let bootstrap_servers = std::env::var("KAFKA_BOOTSTRAP_SERVERS").unwrap();
+        let topic = format!("consume-range-{}", uuid::Uuid::new_v4());
+        let config = KafkaClientConfig::new(
+            format!("consume-range-test-{}", uuid::Uuid::new_v4()),
+            bootstrap_servers,
+        );
+
+        let producer = crate::kafka::KafkaProducer::new(config.clone()).unwrap();
+        for seq in 0..10 {
+            producer
+                .send(serde_json::json!({ "seq": seq }), &topic)
+                .await
+                .unwrap();
+        }
+
+        let consumer = KafkaConsumer::new(config, 1).unwrap();
+        let offsets = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = offsets.clone();
+        let processed = tokio::time::timeout(
+            Duration::from_secs(60),
+            consumer.consume_range(&topic, 0, 3, 6, move |message| {
+                seen.lock().unwrap().push(message.offset());
+                async { Ok(()) }
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(processed, 4);
+        assert_eq!(*offsets.lock().unwrap(), vec![3, 4, 5, 6]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Kafka broker at KAFKA_BOOTSTRAP_SERVERS"]
+    async fn test_consume_range_returns_when_range_ends_on_transaction_marker() {
+        use rdkafka::producer::{BaseProducer, BaseRecord, Producer};
+
+        let bootstrap_servers = std::env::var("KAFKA_BOOTSTRAP_SERVERS").unwrap();
+        let topic = format!("consume-range-{}", uuid::Uuid::new_v4());
+
+        // Three messages at offsets 0 to 2, followed by the commit marker at offset 3
+        let producer: BaseProducer = ClientConfig::new()
+            .set("bootstrap.servers", &bootstrap_servers)
+            .set("transactional.id", uuid::Uuid::new_v4().to_string())
+            .create()
+            .unwrap();
+        producer.init_transactions(Duration::from_secs(30)).unwrap();
+        producer.begin_transaction().unwrap();
+        for seq in 0..3 {
+            let payload = seq.to_string();
+            producer
+                .send(BaseRecord::<(), _>::to(&topic).payload(&payload))
+                .unwrap();
+        }
+        producer
+            .commit_transaction(Duration::from_secs(30))
+            .unwrap();
+
+        let config = KafkaClientConfig::new(
+            format!("consume-range-test-{}", uuid::Uuid::new_v4()),
+            bootstrap_servers,
+        );
+        let consumer = KafkaConsumer::new(config, 1).unwrap();
+        let processed = tokio::time::timeout(
+            Duration::from_secs(60),
+            consumer.consume_range(&topic, 0, 0, 100, |_| async { Ok(()) }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(processed, 3);
+    }
+
+    #[tokio::test]
+    async fn test_assignment_empty_before_rebalance() {
+        let consumer = KafkaConsumer::new(config(), 1).unwrap();
+        assert!(consumer.assignment().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_at_repeated_errors() {
+        use rdkafka::error::{KafkaError as RdKafkaError, RDKafkaErrorCode};
+
+        let error = || {
+            Err(RdKafkaError::MessageConsumption(
+                RDKafkaErrorCode::AllBrokersDown,
+            ))
+        };
+        let items: Vec<Result<i64, RdKafkaError>> = vec![
+            Ok(0),
+            error(),
+            error(),
+            Ok(1),
+            error(),
+            error(),
+            error(),
+            Ok(2),
+        ];
+
+        let passed: Vec<bool> =
+            KafkaConsumer::until_repeated_errors(futures::stream::iter(items), 3)
+                .map(|res| res.is_ok())
+                .collect()
+                .await;
+
+        // The error count resets on a message, the third error in a row ends the stream
+        assert_eq!(passed, vec![true, false, false, true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn test_lag_empty_without_assignment() {
+        let consumer = KafkaConsumer::new(config(), 1).unwrap();
+        assert!(consumer.fetch_lag().await.unwrap().is_empty());
+        assert_eq!(consumer.total_lag().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Kafka broker at KAFKA_BOOTSTRAP_SERVERS"]
+    async fn test_assignment_after_subscribing_to_multi_partition_topic() {
+        use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+        use rdkafka::client::DefaultClientContext;
+
+        let bootstrap_servers = std::env::var("KAFKA_BOOTSTRAP_SERVERS").unwrap();
+        let topic = format!("assignment-{}", uuid::Uuid::new_v4());
+        let config = KafkaClientConfig::new(
+            format!("assignment-test-{}", uuid::Uuid::new_v4()),
+            bootstrap_servers,
+        );
+
+        let admin: AdminClient<DefaultClientContext> = config.to_client_config().create().unwrap();
+        admin
+            .create_topics(
+                &[NewTopic::new(&topic, 3, TopicReplication::Fixed(1))],
+                &AdminOptions::new(),
+            )
+            .await
+            .unwrap();
+
+        let consumer = KafkaConsumer::new(config, 1).unwrap();
+        consumer.add_subscription(&[&topic]).unwrap();
+        let _task = consumer.start(|_| async { Ok(()) }).await.unwrap();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+        loop {
+            let assigned: Vec<i32> = consumer
+                .assignment()
+                .unwrap()
+                .into_iter()
+                .filter(|(assigned_topic, _)| *assigned_topic == topic)
+                .map(|(_, partition)| partition)
+                .collect();
+            if assigned.len() == 3 {
+                break;
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "partitions of {} never assigned",
+                topic
+            );
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
     }
 }
@@ -1,9 +1,21 @@
 use anyhow::Context;
-use rdkafka::producer::{FutureProducer, FutureRecord};
-use std::sync::Arc;
-use tracing::{error, info};
+use rdkafka::{
+    error::{KafkaError as RdKafkaError, RDKafkaErrorCode},
+    message::{Header, OwnedHeaders},
+    producer::{FutureProducer, FutureRecord},
+    ClientConfig,
+};
+use std::{collections::HashMap, sync::Arc, time::Instant};
+use tracing::{error, info, warn};
 
-use crate::kafka::core::{KafkaClientConfig, KafkaError};
+use crate::kafka::{
+    core::{
+        compress_envelope, CompressionCodec, ContentType, KafkaClientConfig, KafkaError,
+        MessageType, ParsedMessage, SendMessage, CONTENT_TYPE_HEADER,
+    },
+    retry::{retry_transient, RetryPolicy},
+    utils::utils::create_message,
+};
 
 /// KafkaProducer is responsible for sending messages to Kafka topics asynchronously.
 /// It wraps the rdkafka FutureProducer for thread-safe operations.
@@ -11,6 +23,14 @@ use crate::kafka::core::{KafkaClientConfig, KafkaError};
 pub struct KafkaProducer {
     /// The underlying rdkafka producer wrapped in Arc for thread safety
     pub producer: Arc<FutureProducer>,
+    /// The source identifier set on events, taken from the cluster ID
+    source_id: String,
+    /// Size in bytes above which the envelope `data` field is compressed
+    compress_over_bytes: Option<usize>,
+    /// Retry bounds used by `send_with_retry`
+    retry_policy: RetryPolicy,
+    /// Whether sent payloads are logged; only the topic, size and latency are logged when false
+    log_payloads: bool,
 }
 
 impl KafkaProducer {
@@ -24,21 +44,71 @@ impl KafkaProducer {
     ///
     /// * `anyhow::Result<Self>` - Returns a KafkaProducer instance or an error if creation fails.
     pub fn new(config: KafkaClientConfig) -> anyhow::Result<Self> {
-        let mut producer_config = config.to_client_config();
+        config.validate()?;
 
-        producer_config.set("acks", "0");
-        producer_config.set("transaction.timeout.ms", "60000");
-        producer_config.set("message.send.max.retries", "10");
-
-        let producer: FutureProducer = producer_config
+        let producer: FutureProducer = Self::client_config(&config)
             .create()
             .context("Producer creation failed")?;
 
         Ok(Self {
             producer: Arc::new(producer),
+            source_id: config.cluster_id,
+            compress_over_bytes: config.compress_payload_over_bytes,
+            retry_policy: RetryPolicy::default(),
+            log_payloads: config.log_payloads,
         })
     }
 
+    /// Sets the retry bounds used by `send_with_retry`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The retry policy for transient send failures.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated KafkaProducer instance.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Retrieves the retry bounds used by `send_with_retry`.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Builds the rdkafka ClientConfig used for the producer.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - KafkaClientConfig containing the necessary settings for the producer.
+    ///
+    /// # Returns
+    ///
+    /// * `ClientConfig` - The client config with producer-specific settings applied.
+    pub fn client_config(config: &KafkaClientConfig) -> ClientConfig {
+        let mut producer_config = config.to_client_config();
+
+        producer_config.set("acks", config.acks.unwrap_or_default().as_str());
+        producer_config.set("transaction.timeout.ms", "60000");
+        producer_config.set("message.send.max.retries", "10");
+
+        if let Some(linger) = config.linger {
+            producer_config.set("linger.ms", linger.as_millis().to_string());
+        }
+
+        if let Some(batch_num_messages) = config.batch_num_messages {
+            producer_config.set("batch.num.messages", batch_num_messages.to_string());
+        }
+
+        if config.compression != CompressionCodec::None {
+            producer_config.set("compression.type", config.compression.as_str());
+        }
+
+        producer_config
+    }
+
     /// Sends a message to the specified Kafka topic.
     ///
     /// # Arguments
@@ -53,27 +123,855 @@ impl KafkaProducer {
     where
         T: serde::Serialize + std::fmt::Debug,
     {
-        let payload = serde_json::to_string(&message).map_err(|e| {
-            KafkaError::InternalServerError(format!("Failed to serialize response message: {}", e))
-        })?;
+        self.send_with_options(message, topic, None, HashMap::new())
+            .await
+    }
+
+    /// Sends a message to the specified Kafka topic with a partition key and headers.
+    ///
+    /// Messages with the same key land on the same partition, so they are consumed in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to be sent, which must implement `serde::Serialize` and `std::fmt::Debug`.
+    /// * `topic` - The topic to which the message will be sent.
+    /// * `key` - The message key, if any.
+    /// * `headers` - The headers to attach, e.g. `trace-id`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns Ok if the message is sent successfully, or a KafkaError if it fails.
+    pub async fn send_with_options<T>(
+        &self,
+        message: T,
+        topic: &str,
+        key: Option<&str>,
+        headers: HashMap<String, String>,
+    ) -> Result<(), KafkaError>
+    where
+        T: serde::Serialize + std::fmt::Debug,
+    {
+        let payload = self.encode(&message)?;
 
-        let _ = self
+        let mut record = FutureRecord::<str, str>::to(topic).payload(&payload);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        if !headers.is_empty() {
+            record = record.headers(owned_headers(&headers));
+        }
+
+        let started = Instant::now();
+        let result = self
+            .producer
+            .send(record, std::time::Duration::from_secs(5))
+            .await;
+        self.log_send(
+            topic,
+            None,
+            payload.as_bytes(),
+            started,
+            send_error(&result),
+        );
+        result.map_err(|(e, _)| map_send_error(e))?;
+
+        Ok(())
+    }
+
+    /// Sends a message to a specific partition of the topic, bypassing the partitioner.
+    ///
+    /// Useful to co-locate related messages without giving them the same key.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to be sent, which must implement `serde::Serialize` and `std::fmt::Debug`.
+    /// * `topic` - The topic to which the message will be sent.
+    /// * `partition` - The partition to send to, which must exist in the topic.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns Ok if the message is sent successfully, a ConfigurationError
+    ///   for a negative partition, or a KafkaError if sending fails.
+    pub async fn send_to_partition<T>(
+        &self,
+        message: T,
+        topic: &str,
+        partition: i32,
+    ) -> Result<(), KafkaError>
+    where
+        T: serde::Serialize + std::fmt::Debug,
+    {
+        if partition < 0 {
+            return Err(KafkaError::ConfigurationError(format!(
+                "invalid partition {} for topic {}",
+                partition, topic
+            )));
+        }
+
+        let payload = self.encode(&message)?;
+
+        let started = Instant::now();
+        let result = self
             .producer
             .send(
-                FutureRecord::<String, String>::to(&topic).payload(&payload),
+                FutureRecord::<(), str>::to(topic)
+                    .payload(&payload)
+                    .partition(partition),
                 std::time::Duration::from_secs(5),
             )
-            .await
-            .map_err(|(e, _)| {
-                error!(
-                    "sent message: {:?} to topic: {} failed: {}",
-                    message, topic, e
+            .await;
+        self.log_send(
+            topic,
+            Some(partition),
+            payload.as_bytes(),
+            started,
+            send_error(&result),
+        );
+        result.map_err(|(e, _)| map_send_error(e))?;
+
+        Ok(())
+    }
+
+    /// Sends a response message, falling back to an error envelope if it cannot be serialized.
+    ///
+    /// A caller waiting on the response would otherwise never hear back, so if the message fails
+    /// to serialize, a response with the same envelope and a serialization error as data is sent
+    /// instead. Transient failures are retried according to the producer's retry policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The response message to be sent.
+    /// * `topic` - The topic to which the response will be sent.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Ok once the response (or its fallback) is sent, or a KafkaError if it fails.
+    pub async fn send_response<T>(
+        &self,
+        message: &ParsedMessage<T>,
+        topic: &str,
+    ) -> Result<(), KafkaError>
+    where
+        T: serde::Serialize + std::fmt::Debug + Sync,
+    {
+        let payload = self.encode_response(message)?;
+
+        retry_transient(self.retry_policy, || self.produce(&payload, topic)).await
+    }
+
+    /// Produces an already encoded message to the specified Kafka topic.
+    async fn produce(&self, payload: &str, topic: &str) -> Result<(), KafkaError> {
+        let started = Instant::now();
+        let result = self
+            .producer
+            .send(
+                FutureRecord::<(), str>::to(topic).payload(payload),
+                std::time::Duration::from_secs(5),
+            )
+            .await;
+        self.log_send(
+            topic,
+            None,
+            payload.as_bytes(),
+            started,
+            send_error(&result),
+        );
+        result.map_err(|(e, _)| map_send_error(e))?;
+
+        Ok(())
+    }
+
+    /// Sends a message, retrying transient failures according to the producer's retry policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to be sent.
+    /// * `topic` - The topic to which the message will be sent.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Ok once the message is sent, or the last error if retries are exhausted
+    ///   or the failure is not transient.
+    pub async fn send_with_retry<T>(&self, message: &T, topic: &str) -> Result<(), KafkaError>
+    where
+        T: serde::Serialize + std::fmt::Debug,
+    {
+        retry_transient(self.retry_policy, || self.send(message, topic)).await
+    }
+
+    /// Serializes a message, compressing its `data` field above the configured threshold.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to serialize.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, KafkaError>` - The payload as sent on the wire.
+    pub fn encode<T>(&self, message: &T) -> Result<String, KafkaError>
+    where
+        T: serde::Serialize,
+    {
+        let serialize_error = |e: serde_json::Error| {
+            KafkaError::SerializationError(format!("Failed to serialize message: {}", e))
+        };
+
+        let Some(threshold) = self.compress_over_bytes else {
+            return serde_json::to_string(message).map_err(serialize_error);
+        };
+
+        let mut envelope = serde_json::to_value(message).map_err(serialize_error)?;
+        compress_envelope(&mut envelope, threshold)?;
+        serde_json::to_string(&envelope).map_err(serialize_error)
+    }
+
+    /// Serializes a response message, substituting an error envelope if serialization fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The response message to serialize.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, KafkaError>` - The payload as sent on the wire.
+    pub fn encode_response<T>(&self, message: &ParsedMessage<T>) -> Result<String, KafkaError>
+    where
+        T: serde::Serialize,
+    {
+        match self.encode(message) {
+            Ok(payload) => Ok(payload),
+            Err(e) => {
+                warn!(
+                    "failed to encode response {} - {}, sending error fallback: {}",
+                    message.uri, message.transaction_id, e
                 );
-                KafkaError::InternalServerError(format!("Failed to send message to Kafka: {}", e))
-            })?;
+                let fallback = ParsedMessage {
+                    message_type: message.message_type.clone(),
+                    source_id: message.source_id.clone(),
+                    transaction_id: message.transaction_id.clone(),
+                    message_id: message.message_id.clone(),
+                    uri: message.uri.clone(),
+                    response_destination: None,
+                    content_encoding: None,
+                    deadline_ms: None,
+                    path_params: Default::default(),
+                    data: e.to_response_value(),
+                };
+                self.encode(&fallback)
+            }
+        }
+    }
+
+    /// Sends a raw payload to the specified Kafka topic, keeping the key and headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to which the payload will be sent.
+    /// * `key` - The message key, if any.
+    /// * `payload` - The raw payload bytes.
+    /// * `headers` - The message headers.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns Ok if the payload is sent successfully, or a KafkaError if it fails.
+    pub async fn send_payload(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        payload: &[u8],
+        headers: OwnedHeaders,
+    ) -> Result<(), KafkaError> {
+        let mut record = FutureRecord::<[u8], [u8]>::to(topic)
+            .payload(payload)
+            .headers(headers);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
 
-        info!("sent message: {:?} to topic: {} success", message, topic);
+        let started = Instant::now();
+        let result = self
+            .producer
+            .send(record, std::time::Duration::from_secs(5))
+            .await;
+        self.log_send(topic, None, payload, started, send_error(&result));
+        result.map_err(|(e, _)| map_send_error(e))?;
 
         Ok(())
     }
+
+    /// Sends a binary response, retrying transient failures according to the producer's retry policy.
+    ///
+    /// The payload is sent verbatim. Since there is no JSON envelope, its fields travel as
+    /// headers: `contentType`, `messageType`, `sourceId`, `transactionId`, `messageId` and `uri`.
+    /// Consumers must read the raw payload and these headers instead of parsing the payload
+    /// as a `ParsedMessage`; in particular `RequestSender` does not match binary responses to
+    /// pending requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The response envelope; its `data` is ignored.
+    /// * `topic` - The topic to which the response will be sent.
+    /// * `payload` - The raw response bytes.
+    /// * `content_type` - The media type of the payload.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Ok once the response is sent, or a KafkaError if it fails.
+    pub async fn send_bytes_response(
+        &self,
+        message: &ParsedMessage,
+        topic: &str,
+        payload: &[u8],
+        content_type: &ContentType,
+    ) -> Result<(), KafkaError> {
+        retry_transient(self.retry_policy, || async {
+            let started = Instant::now();
+            let result = self
+                .producer
+                .send(
+                    Self::bytes_record(message, topic, payload, content_type),
+                    std::time::Duration::from_secs(5),
+                )
+                .await;
+            self.log_send(topic, None, payload, started, send_error(&result));
+            result.map_err(|(e, _)| map_send_error(e))?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Logs the outcome of a send as structured fields.
+    ///
+    /// The payload itself is only logged when payload logging is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic sent to.
+    /// * `partition` - The partition sent to, None when chosen by the partitioner.
+    /// * `payload` - The payload sent.
+    /// * `started` - When the send started.
+    /// * `error` - The send error, None if the message was delivered.
+    fn log_send(
+        &self,
+        topic: &str,
+        partition: Option<i32>,
+        payload: &[u8],
+        started: Instant,
+        error: Option<&RdKafkaError>,
+    ) {
+        let payload_bytes = payload.len();
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let body = self.log_payloads.then(|| String::from_utf8_lossy(payload));
+        let body = body.as_deref();
+
+        match error {
+            None => info!(
+                topic,
+                partition,
+                payload_bytes,
+                latency_ms,
+                outcome = "success",
+                payload = body,
+                "sent message"
+            ),
+            Some(e) => error!(
+                topic,
+                partition,
+                payload_bytes,
+                latency_ms,
+                outcome = "failure",
+                payload = body,
+                error = %e,
+                "failed to send message"
+            ),
+        }
+    }
+
+    /// Builds the record for a binary response, carrying the envelope fields as headers.
+    fn bytes_record<'a>(
+        message: &ParsedMessage,
+        topic: &'a str,
+        payload: &'a [u8],
+        content_type: &ContentType,
+    ) -> FutureRecord<'a, (), [u8]> {
+        let message_type = match message.message_type {
+            MessageType::Request => "REQUEST",
+            MessageType::Response => "RESPONSE",
+            MessageType::Message => "MESSAGE",
+        };
+
+        let headers = [
+            (CONTENT_TYPE_HEADER, content_type.as_str()),
+            ("messageType", message_type),
+            ("sourceId", message.source_id.as_str()),
+            ("transactionId", message.transaction_id.as_str()),
+            ("messageId", message.message_id.as_str()),
+            ("uri", message.uri.as_str()),
+        ]
+        .into_iter()
+        .fold(OwnedHeaders::new(), |headers, (key, value)| {
+            headers.insert(Header {
+                key,
+                value: Some(value),
+            })
+        });
+
+        FutureRecord::to(topic).payload(payload).headers(headers)
+    }
+
+    /// Wraps event data in a `MessageType::Message` envelope with generated IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to which the event will be sent.
+    /// * `uri` - The URI for message routing.
+    /// * `data` - The event data.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<SendMessage, KafkaError>` - The message ready to be sent, or a KafkaError if the data
+    ///   cannot be serialized.
+    pub fn build_event<T>(&self, topic: &str, uri: &str, data: T) -> Result<SendMessage, KafkaError>
+    where
+        T: serde::Serialize,
+    {
+        let data = serde_json::to_value(data).map_err(|e| {
+            KafkaError::SerializationError(format!("Failed to serialize event data: {}", e))
+        })?;
+
+        Ok(create_message(
+            self.source_id.clone(),
+            uuid::Uuid::new_v4().to_string(),
+            uuid::Uuid::new_v4().to_string(),
+            topic.to_string(),
+            uri.to_string(),
+            data,
+            Some(MessageType::Message),
+            None,
+        ))
+    }
+
+    /// Sends event data to the specified Kafka topic in a `MessageType::Message` envelope.
+    ///
+    /// Unlike `send`, the data carries the standard message metadata, so consumers can route it
+    /// by URI like any other message.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to which the event will be sent.
+    /// * `uri` - The URI for message routing.
+    /// * `data` - The event data.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns Ok if the event is sent successfully, or a KafkaError if it fails.
+    pub async fn send_event<T>(&self, topic: &str, uri: &str, data: T) -> Result<(), KafkaError>
+    where
+        T: serde::Serialize,
+    {
+        let event = self.build_event(topic, uri, data)?;
+        self.send(event.message, &event.topic).await
+    }
+}
+
+/// Converts header pairs into Kafka headers, sorted by name so the order is stable.
+///
+/// # Arguments
+///
+/// * `headers` - The header names and values.
+///
+/// # Returns
+///
+/// * `OwnedHeaders` - The headers to attach to a record.
+fn owned_headers(headers: &HashMap<String, String>) -> OwnedHeaders {
+    let mut pairs: Vec<_> = headers.iter().collect();
+    pairs.sort();
+
+    pairs.into_iter().fold(
+        OwnedHeaders::new_with_capacity(headers.len()),
+        |owned, (key, value)| {
+            owned.insert(Header {
+                key,
+                value: Some(value),
+            })
+        },
+    )
+}
+
+/// Retrieves the error of a send result, if any.
+fn send_error<T, M>(result: &Result<T, (RdKafkaError, M)>) -> Option<&RdKafkaError> {
+    result.as_ref().err().map(|(e, _)| e)
+}
+
+/// Maps an rdkafka send error to a KafkaError, keeping transient failures retryable.
+///
+/// The rdkafka error code, when there is one, is kept in the error message. Broker-side delivery
+/// failures are only reported when the producer waits for acks.
+fn map_send_error(e: RdKafkaError) -> KafkaError {
+    let code = e.rdkafka_error_code();
+    let message = match code {
+        Some(code) => format!("Failed to send message to Kafka ({:?}): {}", code, e),
+        None => format!("Failed to send message to Kafka: {}", e),
+    };
+
+    match code {
+        Some(
+            RDKafkaErrorCode::QueueFull
+            | RDKafkaErrorCode::MessageTimedOut
+            | RDKafkaErrorCode::BrokerTransportFailure
+            | RDKafkaErrorCode::AllBrokersDown
+            | RDKafkaErrorCode::OperationTimedOut
+            | RDKafkaErrorCode::RequestTimedOut
+            | RDKafkaErrorCode::LeaderNotAvailable
+            | RDKafkaErrorCode::NotLeaderForPartition
+            | RDKafkaErrorCode::NetworkException
+            | RDKafkaErrorCode::NotEnoughReplicas
+            | RDKafkaErrorCode::NotEnoughReplicasAfterAppend
+            | RDKafkaErrorCode::KafkaStorageError,
+        ) => KafkaError::ConnectionError(message),
+        _ => KafkaError::InternalServerError(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::core::{error_codes, Acks, Response};
+    use crate::kafka::testing::sample_message;
+    use crate::test_support::capture_logs;
+    use rdkafka::message::Headers;
+    use std::time::Duration;
+
+    fn config() -> KafkaClientConfig {
+        KafkaClientConfig::new("test-service".to_string(), "localhost:9092".to_string())
+    }
+
+    #[test]
+    fn test_owned_headers_sorted_by_name() {
+        let headers = owned_headers(&HashMap::from([
+            ("trace-id".to_string(), "abc".to_string()),
+            ("content-type".to_string(), "application/json".to_string()),
+        ]));
+
+        let pairs: Vec<(&str, &[u8])> = headers
+            .iter()
+            .map(|header| (header.key, header.value.unwrap()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("content-type", b"application/json".as_slice()),
+                ("trace-id", b"abc".as_slice()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_to_negative_partition_is_rejected() {
+        let producer = KafkaProducer::new(config()).unwrap();
+
+        let result = producer
+            .send_to_partition(serde_json::json!({ "hello": "world" }), "test-topic", -1)
+            .await;
+
+        assert!(matches!(result, Err(KafkaError::ConfigurationError(_))));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Kafka broker at KAFKA_BOOTSTRAP_SERVERS"]
+    async fn test_send_to_partition_lands_on_that_partition() {
+        use crate::kafka::KafkaConsumer;
+        use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+        use rdkafka::client::DefaultClientContext;
+
+        let bootstrap_servers = std::env::var("KAFKA_BOOTSTRAP_SERVERS").unwrap();
+        let topic = format!("send-to-partition-{}", uuid::Uuid::new_v4());
+        let config = KafkaClientConfig::new(
+            format!("send-to-partition-test-{}", uuid::Uuid::new_v4()),
+            bootstrap_servers,
+        );
+
+        let admin: AdminClient<DefaultClientContext> = config.to_client_config().create().unwrap();
+        admin
+            .create_topics(
+                &[NewTopic::new(&topic, 3, TopicReplication::Fixed(1))],
+                &AdminOptions::new(),
+            )
+            .await
+            .unwrap();
+
+        let producer = KafkaProducer::new(config.clone()).unwrap();
+        producer
+            .send_to_partition(serde_json::json!({ "seq": 1 }), &topic, 1)
+            .await
+            .unwrap();
+
+        let consumer = KafkaConsumer::new(config, 1).unwrap();
+        let partitions = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = partitions.clone();
+        let processed = tokio::time::timeout(
+            Duration::from_secs(60),
+            consumer.consume_range(&topic, 1, 0, 0, move |message| {
+                seen.lock()
+                    .unwrap()
+                    .push(rdkafka::Message::partition(&message));
+                async { Ok(()) }
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(processed, 1);
+        assert_eq!(*partitions.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_client_config_linger_defaults_to_librdkafka() {
+        let client_config = KafkaProducer::client_config(&config());
+        assert_eq!(client_config.get("linger.ms"), None);
+        assert_eq!(client_config.get("batch.num.messages"), None);
+    }
+
+    #[test]
+    fn test_client_config_with_linger() {
+        let config = config()
+            .with_linger(Duration::from_millis(50))
+            .with_batch_num_messages(5000);
+        let client_config = KafkaProducer::client_config(&config);
+
+        assert_eq!(client_config.get("linger.ms"), Some("50"));
+        assert_eq!(client_config.get("batch.num.messages"), Some("5000"));
+    }
+
+    #[test]
+    fn test_client_config_acks() {
+        let client_config = KafkaProducer::client_config(&config());
+        assert_eq!(client_config.get("acks"), Some("1"));
+
+        let client_config = KafkaProducer::client_config(&config().with_acks(Acks::All));
+        assert_eq!(client_config.get("acks"), Some("all"));
+
+        // An explicit setting wins over the default of the response producers
+        let config = config().with_acks(Acks::None).with_default_acks(Acks::All);
+        let client_config = KafkaProducer::client_config(&config);
+        assert_eq!(client_config.get("acks"), Some("0"));
+    }
+
+    #[test]
+    fn test_client_config_compression() {
+        let client_config = KafkaProducer::client_config(&config());
+        assert_eq!(client_config.get("compression.type"), None);
+
+        for (codec, expected) in [
+            (CompressionCodec::Gzip, "gzip"),
+            (CompressionCodec::Snappy, "snappy"),
+            (CompressionCodec::Lz4, "lz4"),
+            (CompressionCodec::Zstd, "zstd"),
+        ] {
+            let client_config = KafkaProducer::client_config(&config().with_compression(codec));
+            assert_eq!(client_config.get("compression.type"), Some(expected));
+        }
+
+        // The default leaves a codec set through the raw configuration in place
+        let config = config().set("compression.type", "lz4");
+        let client_config = KafkaProducer::client_config(&config);
+        assert_eq!(client_config.get("compression.type"), Some("lz4"));
+    }
+
+    #[test]
+    fn test_build_event_parses_back_as_message() {
+        let producer = KafkaProducer::new(config()).unwrap();
+        let event = producer
+            .build_event(
+                "orders",
+                "/api/v1/order-created",
+                serde_json::json!({ "id": 1 }),
+            )
+            .unwrap();
+
+        let payload = serde_json::to_string(&event.message).unwrap();
+        let parsed = crate::kafka::ParsedMessage::parse_from_string(&payload).unwrap();
+
+        assert_eq!(event.topic, "orders");
+        assert!(matches!(parsed.message_type, MessageType::Message));
+        assert_eq!(parsed.source_id, "test-service");
+        assert_eq!(parsed.uri, "/api/v1/order-created");
+        assert!(!parsed.message_id.is_empty());
+        assert!(!parsed.transaction_id.is_empty());
+        assert_eq!(parsed.data, serde_json::json!({ "id": 1 }));
+    }
+
+    #[test]
+    fn test_large_payload_compressed_on_wire_and_decompressed_on_parse() {
+        let producer = KafkaProducer::new(config().with_compress_payload_over_bytes(1024)).unwrap();
+        let data = serde_json::json!({ "rows": vec!["the same row of data"; 500] });
+        let event = producer
+            .build_event("orders", "/api/v1/bulk", data.clone())
+            .unwrap();
+
+        let wire = producer.encode(&event.message).unwrap();
+        let raw_size = serde_json::to_string(&data).unwrap().len();
+        let on_wire: serde_json::Value = serde_json::from_str(&wire).unwrap();
+        assert_eq!(on_wire["contentEncoding"], "gzip");
+        assert!(on_wire["data"].is_string());
+        assert!(wire.len() < raw_size);
+
+        let parsed = crate::kafka::ParsedMessage::parse_from_string(&wire).unwrap();
+        assert_eq!(parsed.data, data);
+        assert_eq!(parsed.content_encoding, None);
+    }
+
+    #[test]
+    fn test_small_payload_sent_uncompressed() {
+        let producer = KafkaProducer::new(config().with_compress_payload_over_bytes(1024)).unwrap();
+        let event = producer
+            .build_event("orders", "/api/v1/order", serde_json::json!({ "id": 1 }))
+            .unwrap();
+
+        let on_wire: serde_json::Value =
+            serde_json::from_str(&producer.encode(&event.message).unwrap()).unwrap();
+        assert_eq!(on_wire["data"], serde_json::json!({ "id": 1 }));
+        assert!(on_wire.get("contentEncoding").is_none());
+    }
+
+    #[test]
+    fn test_send_log_carries_fields_without_payload() {
+        let payload = br#"{"secret":"s3cr3t"}"#;
+        let sent = |producer: &KafkaProducer| {
+            let (writer, _guard) = capture_logs();
+            producer.log_send("orders", None, payload, Instant::now(), None);
+            writer.output()
+        };
+
+        let event = sent(&KafkaProducer::new(config()).unwrap());
+        assert!(event.contains("topic=\"orders\""));
+        assert!(event.contains("payload_bytes=19"));
+        assert!(event.contains("outcome=\"success\""));
+        assert!(!event.contains("s3cr3t"));
+
+        let event = sent(&KafkaProducer::new(config().with_log_payloads(true)).unwrap());
+        assert!(event.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn test_map_send_error_keeps_transient_errors_retryable() {
+        let queue_full = RdKafkaError::MessageProduction(RDKafkaErrorCode::QueueFull);
+        assert!(map_send_error(queue_full).is_transient());
+
+        let under_replicated =
+            RdKafkaError::MessageProduction(RDKafkaErrorCode::NotEnoughReplicasAfterAppend);
+        assert!(matches!(
+            map_send_error(under_replicated),
+            KafkaError::ConnectionError(_)
+        ));
+
+        let too_large = RdKafkaError::MessageProduction(RDKafkaErrorCode::MessageSizeTooLarge);
+        assert!(!map_send_error(too_large).is_transient());
+    }
+
+    #[test]
+    fn test_map_send_error_carries_rdkafka_code() {
+        let timed_out = RdKafkaError::MessageProduction(RDKafkaErrorCode::MessageTimedOut);
+
+        let error = map_send_error(timed_out);
+
+        assert!(error.is_transient());
+        match error {
+            KafkaError::ConnectionError(message) => {
+                assert!(message.contains("(MessageTimedOut)"), "{}", message)
+            }
+            other => panic!("expected a ConnectionError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unserializable_response_falls_back_to_error_envelope() {
+        let producer = KafkaProducer::new(config()).unwrap();
+        let mut data = std::collections::HashMap::new();
+        data.insert((1, 2), "non-string key");
+        let message = ParsedMessage {
+            message_type: MessageType::Response,
+            source_id: "test-service".to_string(),
+            transaction_id: "tx-1".to_string(),
+            message_id: "msg-1".to_string(),
+            uri: "/api/v1/reply".to_string(),
+            response_destination: None,
+            content_encoding: None,
+            deadline_ms: None,
+            path_params: Default::default(),
+            data,
+        };
+
+        assert!(matches!(
+            producer.encode(&message),
+            Err(KafkaError::SerializationError(_))
+        ));
+
+        let payload = producer.encode_response(&message).unwrap();
+        let parsed = ParsedMessage::parse_from_string(&payload).unwrap();
+        assert_eq!(parsed.message_type, MessageType::Response);
+        assert_eq!(parsed.transaction_id, "tx-1");
+        assert_eq!(parsed.uri, "/api/v1/reply");
+        assert_eq!(parsed.data["status"]["code"], error_codes::VALUE_INVALID);
+        assert_eq!(
+            parsed.data["status"]["message"],
+            "Serialization Error: Failed to serialize message: key must be a string"
+        );
+    }
+
+    #[test]
+    fn test_typed_response_encoded_directly() {
+        #[derive(Debug, serde::Serialize)]
+        struct Balance {
+            account: String,
+            cents: i64,
+        }
+
+        let producer = KafkaProducer::new(config()).unwrap();
+        let message = ParsedMessage {
+            message_type: MessageType::Response,
+            source_id: "test-service".to_string(),
+            transaction_id: "tx-1".to_string(),
+            message_id: "msg-1".to_string(),
+            uri: "/api/v1/balance".to_string(),
+            response_destination: None,
+            content_encoding: None,
+            deadline_ms: None,
+            path_params: Default::default(),
+            data: Response::<(), Balance>::success(Balance {
+                account: "acc-1".to_string(),
+                cents: 1050,
+            }),
+        };
+
+        let payload = producer.encode_response(&message).unwrap();
+        let parsed = ParsedMessage::parse_from_string(&payload).unwrap();
+        assert_eq!(parsed.data["data"]["cents"], 1050);
+        assert_eq!(parsed.data["data"]["account"], "acc-1");
+    }
+
+    #[test]
+    fn test_bytes_response_sent_verbatim_with_content_type() {
+        let mut message = sample_message();
+        message.message_type = MessageType::Response;
+        let payload = [0x89, b'P', b'N', b'G', 0x00, 0xff];
+
+        let record = KafkaProducer::bytes_record(&message, "caller", &payload, &ContentType::Png);
+
+        assert_eq!(record.topic, "caller");
+        assert_eq!(record.payload, Some(&payload[..]));
+
+        let headers: HashMap<&str, &[u8]> = record
+            .headers
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|header| (header.key, header.value.unwrap()))
+            .collect();
+        assert_eq!(headers[CONTENT_TYPE_HEADER], b"image/png");
+        assert_eq!(headers["messageType"], b"RESPONSE");
+        assert_eq!(headers["transactionId"], message.transaction_id.as_bytes());
+    }
 }
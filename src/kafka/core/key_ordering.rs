@@ -0,0 +1,170 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    future::Future,
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Result;
+use rdkafka::{message::OwnedMessage, Message};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::error;
+
+/// KeyOrderedDispatcher processes messages with the same key sequentially while
+/// messages with different keys run concurrently.
+///
+/// Each message is routed to one of `workers` serial queues by hashing its key, so all
+/// messages for a given key are handled in the order they were dispatched. Messages without
+/// a key are spread across the workers round-robin.
+pub struct KeyOrderedDispatcher {
+    senders: Vec<mpsc::Sender<OwnedMessage>>,
+    tasks: Vec<JoinHandle<()>>,
+    next_keyless: AtomicUsize,
+}
+
+impl KeyOrderedDispatcher {
+    /// Spawns the worker tasks.
+    ///
+    /// # Arguments
+    ///
+    /// * `workers` - The number of serial queues, i.e. the maximum concurrency across keys (at least 1).
+    /// * `queue_capacity` - The number of messages buffered per worker before dispatch waits.
+    /// * `handler` - The function that processes each message.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of KeyOrderedDispatcher.
+    pub fn spawn<T, F>(workers: usize, queue_capacity: usize, handler: Arc<T>) -> Self
+    where
+        T: Fn(OwnedMessage) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let workers = workers.max(1);
+        let mut senders = Vec::with_capacity(workers);
+        let mut tasks = Vec::with_capacity(workers);
+
+        for _ in 0..workers {
+            let (sender, mut receiver) = mpsc::channel::<OwnedMessage>(queue_capacity.max(1));
+            let handler = handler.clone();
+            tasks.push(tokio::spawn(async move {
+                while let Some(message) = receiver.recv().await {
+                    if let Err(e) = handler(message).await {
+                        error!("error while processing message: {}", e);
+                    }
+                }
+            }));
+            senders.push(sender);
+        }
+
+        Self {
+            senders,
+            tasks,
+            next_keyless: AtomicUsize::new(0),
+        }
+    }
+
+    /// Retrieves the number of workers.
+    pub fn workers(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Retrieves the worker index a key is routed to.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The message key.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The index of the worker handling this key.
+    pub fn worker_for(&self, key: &[u8]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.senders.len() as u64) as usize
+    }
+
+    /// Queues a message on the worker responsible for its key.
+    ///
+    /// Waits if that worker's queue is full, which applies backpressure to the consumer.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to process.
+    pub async fn dispatch(&self, message: OwnedMessage) {
+        let worker = match message.key() {
+            Some(key) => self.worker_for(key),
+            None => self.next_keyless.fetch_add(1, Ordering::Relaxed) % self.senders.len(),
+        };
+
+        if self.senders[worker].send(message).await.is_err() {
+            error!("key ordered worker {} has stopped", worker);
+        }
+    }
+
+    /// Stops accepting messages and waits for all queued messages to be processed.
+    pub async fn close(self) {
+        drop(self.senders);
+        for task in self.tasks {
+            let _ = task.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Mutex, time::Duration};
+
+    use crate::test_support::TestMessage;
+
+    fn keyed_message(key: &str, offset: i64) -> OwnedMessage {
+        TestMessage::new("test-topic")
+            .with_payload(Vec::new())
+            .with_key(key)
+            .with_offset(offset)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_same_key_in_order_different_keys_overlap() {
+        let events: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let events_for_handler = events.clone();
+
+        let handler = Arc::new(move |message: OwnedMessage| {
+            let events = events_for_handler.clone();
+            async move {
+                let key = String::from_utf8(message.key().unwrap().to_vec()).unwrap();
+                let id = format!("{}{}", key, message.offset());
+                events.lock().unwrap().push(format!("start {}", id));
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                events.lock().unwrap().push(format!("end {}", id));
+                Ok(())
+            }
+        });
+
+        let dispatcher = KeyOrderedDispatcher::spawn(8, 10, handler);
+
+        // Pick two keys that land on different workers
+        let key_a = "a".to_string();
+        let key_b = (0..)
+            .map(|i| format!("b{}", i))
+            .find(|key| dispatcher.worker_for(key.as_bytes()) != dispatcher.worker_for(b"a"))
+            .unwrap();
+
+        dispatcher.dispatch(keyed_message(&key_a, 1)).await;
+        dispatcher.dispatch(keyed_message(&key_a, 2)).await;
+        dispatcher.dispatch(keyed_message(&key_b, 1)).await;
+        dispatcher.close().await;
+
+        let events = events.lock().unwrap().clone();
+        let position = |event: String| events.iter().position(|e| *e == event).unwrap();
+
+        // Same key: the second message starts only after the first one ended
+        assert!(position(format!("end {}1", key_a)) < position(format!("start {}2", key_a)));
+        // Different keys: the other key starts before the first message of key a ended
+        assert!(position(format!("start {}1", key_b)) < position(format!("end {}1", key_a)));
+    }
+}
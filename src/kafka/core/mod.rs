@@ -1,11 +1,25 @@
+pub mod compression;
+pub mod consumer_metrics;
 pub mod error;
 pub mod extensions;
+pub mod fair_scheduling;
 pub mod kafka_config;
 pub mod kafka_consumer;
 pub mod kafka_producer;
+pub mod key_ordering;
+pub mod offset_tracker;
+pub mod shutdown;
+pub mod throughput;
 
+pub use compression::*;
+pub use consumer_metrics::*;
 pub use error::*;
 pub use extensions::*;
+pub use fair_scheduling::*;
 pub use kafka_config::*;
 pub use kafka_consumer::*;
 pub use kafka_producer::*;
+pub use key_ordering::*;
+pub use offset_tracker::*;
+pub use shutdown::*;
+pub use throughput::*;
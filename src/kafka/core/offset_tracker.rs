@@ -0,0 +1,301 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+/// Callback storing the next offset to commit for a topic partition.
+pub type OffsetCommitter = Arc<dyn Fn(&str, i32, i64) + Send + Sync>;
+
+/// RedeliveryRequested is returned by a message handler to leave the message unprocessed, so
+/// its offset is not committed and the message is redelivered.
+///
+/// The consumer hands the message to its handler again after a backoff, following its
+/// redelivery policy; the offset stays in flight, holding back the partition's commits, until
+/// the handler is done with it or the redeliveries are exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("redelivery requested")]
+pub struct RedeliveryRequested;
+
+/// CompletionDeferred is returned by a message handler that handed the message over to be
+/// processed later, e.g. in a batch. Its offset stays in flight until it is completed through
+/// `TrackedOffsets::complete`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("completion deferred")]
+pub struct CompletionDeferred;
+
+/// OffsetTracker computes safe commit offsets when messages complete out of order.
+///
+/// With concurrent processing, a later offset of a partition can finish before an earlier one.
+/// Committing the latest completed offset would then skip the unfinished earlier offset if the
+/// service crashed. The tracker only advances a partition's commit offset past the offsets that
+/// completed contiguously from the start, i.e. up to the lowest offset still in flight.
+#[derive(Default)]
+pub struct OffsetTracker {
+    partitions: Mutex<HashMap<(String, i32), PartitionOffsets>>,
+}
+
+#[derive(Default)]
+struct PartitionOffsets {
+    in_flight: BTreeSet<i64>,
+    lowest_begun: Option<i64>,
+    highest_begun: Option<i64>,
+    committable: Option<i64>,
+}
+
+impl OffsetTracker {
+    /// Creates a new, empty OffsetTracker.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of OffsetTracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that a message was received and its processing started.
+    ///
+    /// Must be called in the order messages are consumed from the partition.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the message.
+    /// * `partition` - The partition of the message.
+    /// * `offset` - The offset of the message.
+    pub fn begin(&self, topic: &str, partition: i32, offset: i64) {
+        let Ok(mut partitions) = self.partitions.lock() else {
+            return;
+        };
+
+        let state = partitions
+            .entry((topic.to_string(), partition))
+            .or_default();
+        state.in_flight.insert(offset);
+        state.lowest_begun = Some(state.lowest_begun.map_or(offset, |l| l.min(offset)));
+        state.highest_begun = Some(state.highest_begun.map_or(offset, |h| h.max(offset)));
+    }
+
+    /// Records that a message finished processing, successfully or not.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the message.
+    /// * `partition` - The partition of the message.
+    /// * `offset` - The offset of the message.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<i64>` - The new next offset to commit if it advanced, None otherwise.
+    pub fn complete(&self, topic: &str, partition: i32, offset: i64) -> Option<i64> {
+        let mut partitions = self.partitions.lock().ok()?;
+        let state = partitions.get_mut(&(topic.to_string(), partition))?;
+
+        if !state.in_flight.remove(&offset) {
+            return None;
+        }
+
+        let next = match state.in_flight.first() {
+            Some(&lowest_in_flight) => lowest_in_flight,
+            None => state.highest_begun? + 1,
+        };
+
+        let current = state.committable.or(state.lowest_begun)?;
+        if next <= current {
+            return None;
+        }
+        state.committable = Some(next);
+        Some(next)
+    }
+
+    /// Retrieves the next offset to commit for a partition.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the partition.
+    /// * `partition` - The partition.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<i64>` - The offset after the last contiguously completed one, or None if no
+    ///   offset can be committed yet.
+    pub fn committable(&self, topic: &str, partition: i32) -> Option<i64> {
+        let partitions = self.partitions.lock().ok()?;
+        partitions
+            .get(&(topic.to_string(), partition))
+            .and_then(|state| state.committable)
+    }
+
+    /// Forgets the offsets tracked for a partition, e.g. when it is revoked or assigned again.
+    ///
+    /// Consumption of a reassigned partition resumes from its committed offset, so offsets left
+    /// in flight by the previous assignment would otherwise hold back its commits forever.
+    /// Completions of the forgotten offsets are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the partition.
+    /// * `partition` - The partition.
+    pub fn reset(&self, topic: &str, partition: i32) {
+        if let Ok(mut partitions) = self.partitions.lock() {
+            partitions.remove(&(topic.to_string(), partition));
+        }
+    }
+
+    /// Forgets the offsets tracked for every partition.
+    pub fn clear(&self) {
+        if let Ok(mut partitions) = self.partitions.lock() {
+            partitions.clear();
+        }
+    }
+}
+
+/// TrackedOffsets pairs an OffsetTracker with the committer receiving the offsets it advances to.
+#[derive(Clone)]
+pub struct TrackedOffsets {
+    tracker: Arc<OffsetTracker>,
+    committer: OffsetCommitter,
+}
+
+impl TrackedOffsets {
+    /// Creates a new TrackedOffsets.
+    ///
+    /// # Arguments
+    ///
+    /// * `tracker` - The tracker recording the in-flight offsets.
+    /// * `committer` - Receives each partition's next offset to commit.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of TrackedOffsets.
+    pub fn new(tracker: Arc<OffsetTracker>, committer: OffsetCommitter) -> Self {
+        Self { tracker, committer }
+    }
+
+    /// Retrieves the underlying tracker.
+    pub fn tracker(&self) -> &Arc<OffsetTracker> {
+        &self.tracker
+    }
+
+    /// Records that a message was received; see `OffsetTracker::begin`.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the message.
+    /// * `partition` - The partition of the message.
+    /// * `offset` - The offset of the message.
+    pub fn begin(&self, topic: &str, partition: i32, offset: i64) {
+        self.tracker.begin(topic, partition, offset);
+    }
+
+    /// Records that a message finished processing, committing its partition's next offset if
+    /// it advanced.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the message.
+    /// * `partition` - The partition of the message.
+    /// * `offset` - The offset of the message.
+    pub fn complete(&self, topic: &str, partition: i32, offset: i64) {
+        if let Some(next_offset) = self.tracker.complete(topic, partition, offset) {
+            (self.committer)(topic, partition, next_offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_advances_only_past_contiguous_completions() {
+        let tracker = OffsetTracker::new();
+        for offset in 0..5 {
+            tracker.begin("orders", 0, offset);
+        }
+
+        assert_eq!(tracker.complete("orders", 0, 2), None);
+        assert_eq!(tracker.complete("orders", 0, 4), None);
+        assert_eq!(tracker.committable("orders", 0), None);
+
+        assert_eq!(tracker.complete("orders", 0, 0), Some(1));
+        assert_eq!(tracker.complete("orders", 0, 1), Some(3));
+        assert_eq!(tracker.complete("orders", 0, 3), Some(5));
+        assert_eq!(tracker.committable("orders", 0), Some(5));
+    }
+
+    #[test]
+    fn test_partitions_are_tracked_independently() {
+        let tracker = OffsetTracker::new();
+        tracker.begin("orders", 0, 10);
+        tracker.begin("orders", 1, 10);
+        tracker.begin("orders", 0, 11);
+
+        assert_eq!(tracker.complete("orders", 0, 11), None);
+        assert_eq!(tracker.complete("orders", 1, 10), Some(11));
+        assert_eq!(tracker.complete("orders", 0, 10), Some(12));
+    }
+
+    #[test]
+    fn test_unknown_offsets_are_ignored() {
+        let tracker = OffsetTracker::new();
+        assert_eq!(tracker.complete("orders", 0, 1), None);
+
+        tracker.begin("orders", 0, 1);
+        assert_eq!(tracker.complete("orders", 0, 1), Some(2));
+        assert_eq!(tracker.complete("orders", 0, 1), None);
+    }
+
+    #[test]
+    fn test_reset_forgets_offsets_left_in_flight() {
+        let tracker = OffsetTracker::new();
+        tracker.begin("orders", 0, 5);
+        tracker.begin("orders", 0, 6);
+        tracker.begin("orders", 1, 5);
+        assert_eq!(tracker.complete("orders", 0, 6), None);
+
+        // Offset 5 never completes; after a reassignment the partition restarts from it
+        tracker.reset("orders", 0);
+        assert_eq!(tracker.committable("orders", 0), None);
+        assert_eq!(tracker.complete("orders", 0, 5), None);
+
+        tracker.begin("orders", 0, 5);
+        assert_eq!(tracker.complete("orders", 0, 5), Some(6));
+
+        // Other partitions are left untouched
+        assert_eq!(tracker.complete("orders", 1, 5), Some(6));
+    }
+
+    #[test]
+    fn test_clear_forgets_every_partition() {
+        let tracker = OffsetTracker::new();
+        tracker.begin("orders", 0, 1);
+        tracker.begin("payments", 3, 1);
+
+        tracker.clear();
+        assert_eq!(tracker.complete("orders", 0, 1), None);
+        assert_eq!(tracker.complete("payments", 3, 1), None);
+    }
+
+    #[test]
+    fn test_tracked_offsets_commit_when_advanced() {
+        let committed = Arc::new(Mutex::new(Vec::new()));
+        let recorded = committed.clone();
+        let committer: OffsetCommitter = Arc::new(move |topic: &str, partition: i32, offset| {
+            recorded
+                .lock()
+                .unwrap()
+                .push((topic.to_string(), partition, offset));
+        });
+        let offsets = TrackedOffsets::new(Arc::new(OffsetTracker::new()), committer);
+
+        offsets.begin("orders", 0, 7);
+        offsets.begin("orders", 0, 8);
+        offsets.complete("orders", 0, 8);
+        assert!(committed.lock().unwrap().is_empty());
+
+        offsets.complete("orders", 0, 7);
+        assert_eq!(
+            *committed.lock().unwrap(),
+            vec![("orders".to_string(), 0, 9)]
+        );
+    }
+}
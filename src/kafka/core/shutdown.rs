@@ -0,0 +1,216 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use rdkafka::consumer::Consumer;
+use tokio::{
+    sync::watch,
+    task::{JoinError, JoinHandle},
+};
+use tracing::{info, warn};
+
+use crate::kafka::core::LoggingConsumer;
+
+/// ShutdownMode selects how a running consumer task is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Stops taking new messages and waits up to the deadline for in-flight handlers to
+    /// complete, then aborts whatever is still running.
+    Graceful(Duration),
+    /// Aborts the task at once, dropping in-flight handlers mid-message.
+    Immediate,
+}
+
+/// Shutdown describes how `KafkaConsumer::stop` and `StreamHandler::stop` end message processing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shutdown {
+    /// How in-flight work is treated
+    pub mode: ShutdownMode,
+}
+
+impl Shutdown {
+    /// Creates a graceful shutdown waiting up to `deadline` for in-flight handlers.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The maximum time to wait before aborting.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of Shutdown.
+    pub fn graceful(deadline: Duration) -> Self {
+        Self {
+            mode: ShutdownMode::Graceful(deadline),
+        }
+    }
+
+    /// Creates an immediate shutdown aborting in-flight handlers.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of Shutdown.
+    pub fn immediate() -> Self {
+        Self {
+            mode: ShutdownMode::Immediate,
+        }
+    }
+
+    /// Ends a task according to the mode.
+    ///
+    /// In graceful mode the task must already have been told to stop taking new work; this
+    /// only waits for it to finish.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task to end.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the task finished by itself, false if it was aborted.
+    pub async fn stop_task(&self, mut task: JoinHandle<()>) -> bool {
+        if let ShutdownMode::Graceful(deadline) = self.mode {
+            if tokio::time::timeout(deadline, &mut task).await.is_ok() {
+                return true;
+            }
+            warn!(
+                "in-flight work did not complete within {}ms, aborting",
+                deadline.as_millis()
+            );
+        }
+
+        task.abort();
+        let _ = task.await;
+        false
+    }
+}
+
+/// ConsumerHandle is the processing task returned by `KafkaConsumer::start`, carrying the
+/// trigger that stops it.
+///
+/// Awaiting the handle waits for the task to end, like a `JoinHandle`.
+pub struct ConsumerHandle {
+    task: JoinHandle<()>,
+    stop_signal: Arc<watch::Sender<bool>>,
+    consumer: Arc<LoggingConsumer>,
+}
+
+impl ConsumerHandle {
+    /// Creates a new ConsumerHandle.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The processing task.
+    /// * `stop_signal` - The signal the processing loop stops taking messages on.
+    /// * `consumer` - The consumer the task reads from.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of ConsumerHandle.
+    pub(crate) fn new(
+        task: JoinHandle<()>,
+        stop_signal: Arc<watch::Sender<bool>>,
+        consumer: Arc<LoggingConsumer>,
+    ) -> Self {
+        Self {
+            task,
+            stop_signal,
+            consumer,
+        }
+    }
+
+    /// Tells the processing loop to stop taking new messages, without waiting.
+    ///
+    /// In-flight handlers keep running; the task ends once they complete.
+    pub fn trigger_shutdown(&self) {
+        self.stop_signal.send_replace(true);
+    }
+
+    /// Stops taking new messages, waits for in-flight handlers to complete, then unsubscribes
+    /// the consumer.
+    pub async fn shutdown(self) {
+        self.trigger_shutdown();
+        let consumer = self.consumer.clone();
+        if let Err(e) = self.await {
+            warn!("consumer task ended abnormally: {}", e);
+        }
+
+        consumer.unsubscribe();
+        info!("consumer shut down");
+    }
+
+    /// Stops the task according to a `Shutdown`.
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown` - How in-flight work is treated.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all in-flight work completed, false if the task was aborted.
+    pub async fn stop(self, shutdown: Shutdown) -> bool {
+        if let ShutdownMode::Graceful(_) = shutdown.mode {
+            self.trigger_shutdown();
+        }
+
+        let completed = shutdown.stop_task(self.task).await;
+        info!("consumer stopped (in-flight work completed: {})", completed);
+        completed
+    }
+
+    /// Aborts the task at once, dropping in-flight handlers.
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    /// Returns true if the task has ended.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Converts the handle into the underlying task handle, dropping the shutdown trigger.
+    pub fn into_join_handle(self) -> JoinHandle<()> {
+        self.task
+    }
+}
+
+impl Future for ConsumerHandle {
+    type Output = Result<(), JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.task).poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_graceful_waits_for_task_to_finish() {
+        let task = tokio::spawn(tokio::time::sleep(Duration::from_millis(50)));
+
+        assert!(
+            Shutdown::graceful(Duration::from_secs(5))
+                .stop_task(task)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_graceful_aborts_after_deadline() {
+        let start = Instant::now();
+        let task = tokio::spawn(tokio::time::sleep(Duration::from_secs(30)));
+
+        assert!(
+            !Shutdown::graceful(Duration::from_millis(50))
+                .stop_task(task)
+                .await
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
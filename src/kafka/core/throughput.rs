@@ -0,0 +1,132 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// ThroughputMeter measures events per second over a sliding window.
+///
+/// Event timestamps are kept in a ring buffer of `capacity` entries, so the highest rate it can
+/// report is `capacity / window` per second; beyond that the oldest timestamps are overwritten
+/// and the rate saturates.
+#[derive(Debug)]
+pub struct ThroughputMeter {
+    window: Duration,
+    capacity: usize,
+    events: Mutex<VecDeque<Instant>>,
+}
+
+impl ThroughputMeter {
+    /// Creates a new ThroughputMeter.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The sliding window the rate is computed over.
+    /// * `capacity` - The maximum number of timestamps kept (at least 1).
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of ThroughputMeter.
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            window,
+            capacity,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Retrieves the sliding window the rate is computed over.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Records one event at the current time.
+    pub fn record(&self) {
+        self.record_at(Instant::now());
+    }
+
+    /// Retrieves the number of events per second within the window ending now.
+    ///
+    /// # Returns
+    ///
+    /// * `f64` - The event rate, 0.0 if the window is empty or zero.
+    pub fn rate(&self) -> f64 {
+        self.rate_at(Instant::now())
+    }
+
+    fn record_at(&self, at: Instant) {
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(at);
+    }
+
+    fn rate_at(&self, now: Instant) -> f64 {
+        if self.window.is_zero() {
+            return 0.0;
+        }
+
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some(oldest) = events.front() {
+            if now.saturating_duration_since(*oldest) < self.window {
+                break;
+            }
+            events.pop_front();
+        }
+
+        events.len() as f64 / self.window.as_secs_f64()
+    }
+}
+
+impl Default for ThroughputMeter {
+    /// A 10 second window able to measure up to 1000 events per second.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10), 10_000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_over_window() {
+        let meter = ThroughputMeter::new(Duration::from_secs(2), 1000);
+        let start = Instant::now();
+
+        // 50 events per second for 4 seconds
+        for i in 0..200 {
+            meter.record_at(start + Duration::from_millis(i * 20));
+        }
+
+        let rate = meter.rate_at(start + Duration::from_secs(4));
+        assert!((rate - 50.0).abs() <= 1.0, "rate was {}", rate);
+    }
+
+    #[test]
+    fn test_old_events_leave_window() {
+        let meter = ThroughputMeter::new(Duration::from_secs(1), 100);
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            meter.record_at(start);
+        }
+
+        assert_eq!(meter.rate_at(start + Duration::from_millis(500)), 10.0);
+        assert_eq!(meter.rate_at(start + Duration::from_secs(2)), 0.0);
+    }
+
+    #[test]
+    fn test_rate_saturates_at_capacity() {
+        let meter = ThroughputMeter::new(Duration::from_secs(1), 5);
+        let start = Instant::now();
+
+        for _ in 0..20 {
+            meter.record_at(start);
+        }
+
+        assert_eq!(meter.rate_at(start), 5.0);
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::Result;
+use rdkafka::{
+    message::{Header, Headers, OwnedHeaders, OwnedMessage},
+    Message,
+};
+use tracing::error;
+
+use crate::kafka::KafkaProducer;
+
+/// Header carrying the reason the message was dead-lettered.
+pub const DLQ_ERROR_HEADER: &str = "x-dlq-error";
+/// Header carrying the topic the message was consumed from.
+pub const DLQ_ORIGINAL_TOPIC_HEADER: &str = "x-dlq-original-topic";
+/// Header carrying the partition the message was consumed from.
+pub const DLQ_ORIGINAL_PARTITION_HEADER: &str = "x-dlq-original-partition";
+/// Header carrying the offset the message was consumed at.
+pub const DLQ_ORIGINAL_OFFSET_HEADER: &str = "x-dlq-original-offset";
+
+/// Headers holding the dead letter metadata.
+pub(crate) const DLQ_HEADERS: [&str; 4] = [
+    DLQ_ERROR_HEADER,
+    DLQ_ORIGINAL_TOPIC_HEADER,
+    DLQ_ORIGINAL_PARTITION_HEADER,
+    DLQ_ORIGINAL_OFFSET_HEADER,
+];
+
+/// DeadLetterQueue forwards messages that fail to parse or whose handler fails to a dead
+/// letter topic, so they can be inspected or replayed instead of being lost.
+///
+/// The original payload and key are kept as-is; the failure reason and the message origin are
+/// added as `x-dlq-*` headers next to the original headers.
+#[derive(Debug, Clone)]
+pub struct DeadLetterQueue {
+    topic: String,
+}
+
+impl DeadLetterQueue {
+    /// Creates a new DeadLetterQueue.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic receiving failed messages.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of DeadLetterQueue.
+    pub fn new<S: Into<String>>(topic: S) -> Self {
+        Self {
+            topic: topic.into(),
+        }
+    }
+
+    /// Retrieves the dead letter topic.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Forwards a failed message to the dead letter topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The KafkaProducer used to produce the message.
+    /// * `message` - The message that failed.
+    /// * `reason` - Why the message failed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the message was produced, or an error if it fails.
+    pub async fn forward(
+        &self,
+        producer: &KafkaProducer,
+        message: &OwnedMessage,
+        reason: &str,
+    ) -> Result<()> {
+        error!(
+            "sending message from topic {} [{}] at offset {} to {}: {}",
+            message.topic(),
+            message.partition(),
+            message.offset(),
+            self.topic,
+            reason
+        );
+
+        producer
+            .send_payload(
+                &self.topic,
+                message.key(),
+                message.payload().unwrap_or_default(),
+                dead_letter_headers(message, reason),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Copies a message's headers and adds the dead letter metadata.
+///
+/// Metadata headers left by an earlier dead-lettering are replaced.
+///
+/// # Arguments
+///
+/// * `message` - The message whose headers are copied.
+/// * `reason` - Why the message failed.
+///
+/// # Returns
+///
+/// * `OwnedHeaders` - The headers for the dead-lettered message.
+pub fn dead_letter_headers(message: &OwnedMessage, reason: &str) -> OwnedHeaders {
+    with_dead_letter_metadata(copy_headers(message, &DLQ_HEADERS), message, reason)
+}
+
+/// Copies a message's headers, leaving out the given keys.
+///
+/// # Arguments
+///
+/// * `message` - The message whose headers are copied.
+/// * `skipped` - The keys of the headers not copied.
+///
+/// # Returns
+///
+/// * `OwnedHeaders` - The copied headers.
+pub(crate) fn copy_headers(message: &OwnedMessage, skipped: &[&str]) -> OwnedHeaders {
+    let mut headers = OwnedHeaders::new();
+
+    if let Some(existing) = message.headers() {
+        for header in existing.iter() {
+            if !skipped.contains(&header.key) {
+                headers = headers.insert(header);
+            }
+        }
+    }
+
+    headers
+}
+
+/// Adds the dead letter metadata of a message to headers.
+///
+/// # Arguments
+///
+/// * `headers` - The headers to add the metadata to.
+/// * `message` - The message that failed.
+/// * `reason` - Why the message failed.
+///
+/// # Returns
+///
+/// * `OwnedHeaders` - The headers with the `x-dlq-*` metadata.
+pub(crate) fn with_dead_letter_metadata(
+    headers: OwnedHeaders,
+    message: &OwnedMessage,
+    reason: &str,
+) -> OwnedHeaders {
+    let partition = message.partition().to_string();
+    let offset = message.offset().to_string();
+    headers
+        .insert(Header {
+            key: DLQ_ERROR_HEADER,
+            value: Some(reason),
+        })
+        .insert(Header {
+            key: DLQ_ORIGINAL_TOPIC_HEADER,
+            value: Some(message.topic()),
+        })
+        .insert(Header {
+            key: DLQ_ORIGINAL_PARTITION_HEADER,
+            value: Some(&partition),
+        })
+        .insert(Header {
+            key: DLQ_ORIGINAL_OFFSET_HEADER,
+            value: Some(&offset),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{header_value, TestMessage};
+
+    #[test]
+    fn test_headers_carry_reason_and_origin() {
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "trace-id",
+                value: Some("abc"),
+            })
+            .insert(Header {
+                key: DLQ_ERROR_HEADER,
+                value: Some("stale"),
+            });
+        let message = TestMessage::new("orders")
+            .with_payload("not json")
+            .with_key("key-1")
+            .with_partition(2)
+            .with_offset(42)
+            .with_headers(headers)
+            .build();
+
+        let headers = dead_letter_headers(&message, "failed to parse message");
+
+        assert_eq!(header_value(&headers, "trace-id"), Some("abc".to_string()));
+        assert_eq!(
+            header_value(&headers, DLQ_ERROR_HEADER),
+            Some("failed to parse message".to_string())
+        );
+        assert_eq!(
+            header_value(&headers, DLQ_ORIGINAL_TOPIC_HEADER),
+            Some("orders".to_string())
+        );
+        assert_eq!(
+            header_value(&headers, DLQ_ORIGINAL_PARTITION_HEADER),
+            Some("2".to_string())
+        );
+        assert_eq!(
+            header_value(&headers, DLQ_ORIGINAL_OFFSET_HEADER),
+            Some("42".to_string())
+        );
+        assert_eq!(headers.count(), 5);
+    }
+}
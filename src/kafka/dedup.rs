@@ -0,0 +1,192 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// DedupCache remembers recently seen message ids so that redelivered messages can be skipped.
+///
+/// Entries expire after `ttl`, and the oldest entries are evicted once `capacity` is reached,
+/// so memory stays bounded regardless of traffic.
+pub struct DedupCache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<DedupState>,
+}
+
+#[derive(Default)]
+struct DedupState {
+    seen: HashMap<String, Instant>,
+    order: VecDeque<(String, Instant)>,
+}
+
+impl DedupCache {
+    /// Creates a new DedupCache.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The maximum number of message ids remembered (at least 1).
+    /// * `ttl` - How long a message id is remembered.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of DedupCache.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            state: Mutex::new(DedupState::default()),
+        }
+    }
+
+    /// Retrieves the maximum number of message ids remembered.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Retrieves how long a message id is remembered.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Records a message id and reports whether it was already seen within the window.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The message id to check.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the id was seen within the TTL (a duplicate), false if it is new.
+    pub fn check_and_insert(&self, message_id: &str) -> bool {
+        self.check_and_insert_at(message_id, Instant::now())
+    }
+
+    /// Reports whether a message id was seen within the window, without recording it.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The message id to check.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the id was seen within the TTL (a duplicate), false otherwise.
+    pub fn contains(&self, message_id: &str) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+
+        state.evict_expired(Instant::now(), self.ttl);
+        state.seen.contains_key(message_id)
+    }
+
+    /// Records a message id as seen, e.g. once its message was handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The message id to record.
+    pub fn insert(&self, message_id: &str) {
+        self.check_and_insert(message_id);
+    }
+
+    /// Retrieves the number of message ids currently remembered.
+    pub fn len(&self) -> usize {
+        self.state.lock().map(|state| state.seen.len()).unwrap_or(0)
+    }
+
+    /// Returns true if no message ids are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn check_and_insert_at(&self, message_id: &str, now: Instant) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+
+        state.evict_expired(now, self.ttl);
+
+        if state.seen.contains_key(message_id) {
+            return true;
+        }
+
+        while state.seen.len() >= self.capacity {
+            if !state.evict_oldest() {
+                break;
+            }
+        }
+
+        state.seen.insert(message_id.to_string(), now);
+        state.order.push_back((message_id.to_string(), now));
+        false
+    }
+}
+
+impl DedupState {
+    fn evict_expired(&mut self, now: Instant, ttl: Duration) {
+        while let Some((_, seen_at)) = self.order.front() {
+            if now.duration_since(*seen_at) < ttl {
+                break;
+            }
+            self.evict_oldest();
+        }
+    }
+
+    fn evict_oldest(&mut self) -> bool {
+        let Some((message_id, seen_at)) = self.order.pop_front() else {
+            return false;
+        };
+        if self.seen.get(&message_id) == Some(&seen_at) {
+            self.seen.remove(&message_id);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_duplicate() {
+        let cache = DedupCache::new(10, Duration::from_secs(60));
+        assert!(!cache.check_and_insert("msg-1"));
+        assert!(cache.check_and_insert("msg-1"));
+        assert!(!cache.check_and_insert("msg-2"));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_contains_does_not_record() {
+        let cache = DedupCache::new(10, Duration::from_secs(60));
+        assert!(!cache.contains("msg-1"));
+        assert!(!cache.contains("msg-1"));
+
+        cache.insert("msg-1");
+        assert!(cache.contains("msg-1"));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_expired_entries_are_forgotten() {
+        let cache = DedupCache::new(10, Duration::from_secs(5));
+        let start = Instant::now();
+
+        assert!(!cache.check_and_insert_at("msg-1", start));
+        assert!(cache.check_and_insert_at("msg-1", start + Duration::from_secs(4)));
+        assert!(!cache.check_and_insert_at("msg-1", start + Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn test_capacity_evicts_oldest() {
+        let cache = DedupCache::new(2, Duration::from_secs(60));
+
+        assert!(!cache.check_and_insert("msg-1"));
+        assert!(!cache.check_and_insert("msg-2"));
+        assert!(!cache.check_and_insert("msg-3"));
+        assert_eq!(cache.len(), 2);
+
+        assert!(!cache.check_and_insert("msg-1"));
+        assert!(cache.check_and_insert("msg-3"));
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::Instant;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+use crate::kafka::DispatchOutcome;
+
+/// PrometheusMetrics records per-route message handling metrics in a `prometheus::Registry`.
+///
+/// Registered metrics, all labelled with `topic` and `uri`:
+///
+/// * `kafka_messages_handled_total` - Messages handled, additionally labelled with `outcome`
+///   (`ok`, `acknowledged`, `retry`, `error`, `rejected`, `not_found` or `failed`).
+/// * `kafka_message_handle_duration_seconds` - Time from receiving a message until its handler
+///   and response completed.
+/// * `kafka_messages_in_flight` - Messages currently being handled.
+#[derive(Clone)]
+pub struct PrometheusMetrics {
+    registry: Registry,
+    handled: IntCounterVec,
+    duration: HistogramVec,
+    in_flight: IntGaugeVec,
+}
+
+/// MessageTimer tracks one message being handled and records it when dropped.
+///
+/// The outcome defaults to `failed`, so a message whose handling bails out early is still counted.
+pub struct MessageTimer<'a> {
+    metrics: &'a PrometheusMetrics,
+    topic: String,
+    uri: String,
+    outcome: &'static str,
+    started_at: Instant,
+}
+
+impl PrometheusMetrics {
+    /// Creates a new PrometheusMetrics with its own registry.
+    ///
+    /// # Returns
+    ///
+    /// * `prometheus::Result<Self>` - The metrics, or an error if registration fails.
+    pub fn new() -> prometheus::Result<Self> {
+        Self::with_registry(Registry::new())
+    }
+
+    /// Creates a new PrometheusMetrics registered in an existing registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - The registry to register the metrics in, e.g. one shared with the host.
+    ///
+    /// # Returns
+    ///
+    /// * `prometheus::Result<Self>` - The metrics, or an error if registration fails.
+    pub fn with_registry(registry: Registry) -> prometheus::Result<Self> {
+        let handled = IntCounterVec::new(
+            Opts::new("kafka_messages_handled_total", "Messages handled by route"),
+            &["topic", "uri", "outcome"],
+        )?;
+        let duration = HistogramVec::new(
+            HistogramOpts::new(
+                "kafka_message_handle_duration_seconds",
+                "Time spent handling a message",
+            ),
+            &["topic", "uri"],
+        )?;
+        let in_flight = IntGaugeVec::new(
+            Opts::new(
+                "kafka_messages_in_flight",
+                "Messages currently being handled",
+            ),
+            &["topic", "uri"],
+        )?;
+
+        registry.register(Box::new(handled.clone()))?;
+        registry.register(Box::new(duration.clone()))?;
+        registry.register(Box::new(in_flight.clone()))?;
+
+        Ok(Self {
+            registry,
+            handled,
+            duration,
+            in_flight,
+        })
+    }
+
+    /// Retrieves the registry the metrics are registered in.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Marks the start of handling a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic the message was consumed from.
+    /// * `uri` - The URI of the message.
+    ///
+    /// # Returns
+    ///
+    /// * `MessageTimer` - A timer that records the message when dropped.
+    pub fn start(&self, topic: &str, uri: &str) -> MessageTimer<'_> {
+        self.in_flight.with_label_values(&[topic, uri]).inc();
+
+        MessageTimer {
+            metrics: self,
+            topic: topic.to_string(),
+            uri: uri.to_string(),
+            outcome: "failed",
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Renders all metrics in the registry in the Prometheus text exposition format.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The exposition text, suitable for serving on `/metrics`.
+    pub fn gather_metrics(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("failed to encode prometheus metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl DispatchOutcome {
+    /// Retrieves the `outcome` label value of the outcome.
+    pub fn as_label(&self) -> &'static str {
+        match self {
+            DispatchOutcome::Ok => "ok",
+            DispatchOutcome::Acknowledged => "acknowledged",
+            DispatchOutcome::Retry => "retry",
+            DispatchOutcome::Error => "error",
+            DispatchOutcome::Rejected => "rejected",
+            DispatchOutcome::NotFound => "not_found",
+        }
+    }
+}
+
+impl MessageTimer<'_> {
+    /// Sets the outcome recorded for the message.
+    pub fn set_outcome(&mut self, outcome: DispatchOutcome) {
+        self.outcome = outcome.as_label();
+    }
+}
+
+impl Drop for MessageTimer<'_> {
+    fn drop(&mut self) {
+        let labels = [self.topic.as_str(), self.uri.as_str()];
+        self.metrics.in_flight.with_label_values(&labels).dec();
+        self.metrics
+            .duration
+            .with_label_values(&labels)
+            .observe(self.started_at.elapsed().as_secs_f64());
+        self.metrics
+            .handled
+            .with_label_values(&[self.topic.as_str(), self.uri.as_str(), self.outcome])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_records_outcome_and_duration() {
+        let metrics = PrometheusMetrics::new().unwrap();
+
+        {
+            let mut timer = metrics.start("orders", "/api/v1/order");
+            timer.set_outcome(DispatchOutcome::Ok);
+        }
+        drop(metrics.start("orders", "/api/v1/order"));
+
+        let text = metrics.gather_metrics();
+        assert!(text.contains(
+            r#"kafka_messages_handled_total{outcome="ok",topic="orders",uri="/api/v1/order"} 1"#
+        ));
+        assert!(text.contains(
+            r#"kafka_messages_handled_total{outcome="failed",topic="orders",uri="/api/v1/order"} 1"#
+        ));
+        assert!(text.contains(
+            r#"kafka_message_handle_duration_seconds_count{topic="orders",uri="/api/v1/order"} 2"#
+        ));
+        assert!(text.contains(r#"kafka_messages_in_flight{topic="orders",uri="/api/v1/order"} 0"#));
+    }
+
+    #[test]
+    fn test_shared_registry_rejects_duplicate_registration() {
+        let registry = Registry::new();
+        assert!(PrometheusMetrics::with_registry(registry.clone()).is_ok());
+        assert!(PrometheusMetrics::with_registry(registry).is_err());
+    }
+}
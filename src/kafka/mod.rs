@@ -1,12 +1,34 @@
+pub mod batch;
 pub mod core;
+pub mod dead_letter;
+pub mod dedup;
+#[cfg(feature = "prometheus")]
+pub mod metrics_exporter;
+pub mod parse_retry;
+pub mod replay;
 pub mod request_sender;
+pub mod retry;
+pub mod route_pattern;
 pub mod routes_register;
 pub mod stream_handler;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod utils;
 
+pub use batch::*;
 pub use core::*;
+pub use dead_letter::*;
+pub use dedup::*;
+#[cfg(feature = "prometheus")]
+pub use metrics_exporter::*;
+pub use parse_retry::*;
+pub use retry::*;
+pub use route_pattern::*;
 pub use routes_register::*;
 pub use stream_handler::*;
 
 // Re-export the routes macro for easier access
 pub use crate::routes;
+
+// Re-exported for implementing `RouteHandler` without a direct dependency
+pub use async_trait::async_trait;
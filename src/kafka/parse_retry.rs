@@ -0,0 +1,302 @@
+use anyhow::Result;
+use rdkafka::{
+    message::{Header, Headers, OwnedHeaders, OwnedMessage},
+    Message,
+};
+use tracing::{error, warn};
+
+use crate::kafka::{
+    dead_letter::{copy_headers, with_dead_letter_metadata, DLQ_HEADERS},
+    KafkaProducer,
+};
+
+/// Header carrying how many times a message has failed to parse.
+pub const PARSE_ATTEMPTS_HEADER: &str = "x-parse-attempts";
+
+/// ParseRetryPolicy decides what happens to a message whose payload cannot be parsed.
+///
+/// A malformed message may be transient (e.g. a partial write), so it is re-produced to its
+/// topic with an incremented `x-parse-attempts` header. Once it has failed `max_retries`
+/// redeliveries, it is routed to the dead letter topic instead, so a poison message never
+/// loops forever. Dead-lettered messages carry the `x-dlq-*` headers of `DeadLetterQueue`
+/// next to their attempt count.
+#[derive(Debug, Clone)]
+pub struct ParseRetryPolicy {
+    max_retries: u32,
+    dlq_topic: String,
+}
+
+/// ParseFailureAction is the outcome of a parse failure under a ParseRetryPolicy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseFailureAction {
+    /// Re-produce the message to its original topic.
+    Redeliver { topic: String, attempts: u32 },
+    /// Send the message to the dead letter topic.
+    DeadLetter { topic: String, attempts: u32 },
+}
+
+impl ParseRetryPolicy {
+    /// Creates a new ParseRetryPolicy.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_retries` - The number of redeliveries before a message is dead-lettered.
+    /// * `dlq_topic` - The topic receiving messages that keep failing to parse.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of ParseRetryPolicy.
+    pub fn new<S: Into<String>>(max_retries: u32, dlq_topic: S) -> Self {
+        Self {
+            max_retries,
+            dlq_topic: dlq_topic.into(),
+        }
+    }
+
+    /// Retrieves the number of redeliveries before a message is dead-lettered.
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Retrieves the dead letter topic.
+    pub fn dlq_topic(&self) -> &str {
+        &self.dlq_topic
+    }
+
+    /// Decides where a message that failed to parse goes next.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message that failed to parse.
+    ///
+    /// # Returns
+    ///
+    /// * `ParseFailureAction` - The destination, with the attempt count including this failure.
+    pub fn action_for(&self, message: &OwnedMessage) -> ParseFailureAction {
+        let attempts = parse_attempts(message).saturating_add(1);
+
+        if attempts > self.max_retries {
+            ParseFailureAction::DeadLetter {
+                topic: self.dlq_topic.clone(),
+                attempts,
+            }
+        } else {
+            ParseFailureAction::Redeliver {
+                topic: message.topic().to_string(),
+                attempts,
+            }
+        }
+    }
+
+    /// Redelivers or dead-letters a message that failed to parse.
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The KafkaProducer used to re-produce the message.
+    /// * `message` - The message that failed to parse.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the message was re-produced, or an error if it fails.
+    pub async fn handle_failure(
+        &self,
+        producer: &KafkaProducer,
+        message: &OwnedMessage,
+    ) -> Result<()> {
+        let (topic, headers) = match self.action_for(message) {
+            ParseFailureAction::Redeliver { topic, attempts } => {
+                warn!(
+                    "failed to parse message from topic {} (attempt {}/{}), redelivering",
+                    message.topic(),
+                    attempts,
+                    self.max_retries
+                );
+                (topic, attempt_headers(message, attempts))
+            }
+            ParseFailureAction::DeadLetter { topic, attempts } => {
+                error!(
+                    "failed to parse message from topic {} after {} attempts, sending to {}",
+                    message.topic(),
+                    attempts,
+                    topic
+                );
+                (topic, dead_letter_attempt_headers(message, attempts))
+            }
+        };
+
+        producer
+            .send_payload(
+                &topic,
+                message.key(),
+                message.payload().unwrap_or_default(),
+                headers,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Reads the parse attempt count from a message's headers, 0 when absent or invalid.
+///
+/// # Arguments
+///
+/// * `message` - The message to read.
+///
+/// # Returns
+///
+/// * `u32` - The number of failed parse attempts so far.
+pub fn parse_attempts(message: &OwnedMessage) -> u32 {
+    message
+        .headers()
+        .and_then(|headers| {
+            headers
+                .iter()
+                .find(|header| header.key == PARSE_ATTEMPTS_HEADER)
+        })
+        .and_then(|header| header.value)
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Copies a message's headers with the parse attempt count set to `attempts`.
+///
+/// # Arguments
+///
+/// * `message` - The message whose headers are copied.
+/// * `attempts` - The attempt count to set.
+///
+/// # Returns
+///
+/// * `OwnedHeaders` - The headers for the re-produced message.
+pub fn attempt_headers(message: &OwnedMessage, attempts: u32) -> OwnedHeaders {
+    let attempts = attempts.to_string();
+    copy_headers(message, &[PARSE_ATTEMPTS_HEADER]).insert(Header {
+        key: PARSE_ATTEMPTS_HEADER,
+        value: Some(&attempts),
+    })
+}
+
+/// Copies a message's headers with the parse attempt count set to `attempts` and the dead
+/// letter metadata added, replacing any left by an earlier dead-lettering.
+///
+/// # Arguments
+///
+/// * `message` - The message whose headers are copied.
+/// * `attempts` - The attempt count to set.
+///
+/// # Returns
+///
+/// * `OwnedHeaders` - The headers for the dead-lettered message.
+pub fn dead_letter_attempt_headers(message: &OwnedMessage, attempts: u32) -> OwnedHeaders {
+    let mut skipped = DLQ_HEADERS.to_vec();
+    skipped.push(PARSE_ATTEMPTS_HEADER);
+
+    let reason = format!("failed to parse message after {} attempts", attempts);
+    let attempts = attempts.to_string();
+    let headers = copy_headers(message, &skipped).insert(Header {
+        key: PARSE_ATTEMPTS_HEADER,
+        value: Some(&attempts),
+    });
+    with_dead_letter_metadata(headers, message, &reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::{
+        DLQ_ERROR_HEADER, DLQ_ORIGINAL_OFFSET_HEADER, DLQ_ORIGINAL_PARTITION_HEADER,
+        DLQ_ORIGINAL_TOPIC_HEADER,
+    };
+    use crate::test_support::{header_value, TestMessage};
+
+    fn malformed_message(attempts: Option<u32>) -> OwnedMessage {
+        let headers = attempts.map(|attempts| {
+            OwnedHeaders::new()
+                .insert(Header {
+                    key: "trace-id",
+                    value: Some("abc"),
+                })
+                .insert(Header {
+                    key: PARSE_ATTEMPTS_HEADER,
+                    value: Some(&attempts.to_string()),
+                })
+        });
+
+        let message = TestMessage::new("orders")
+            .with_payload(b"{\"uri\": \"/api/v1/tr".to_vec())
+            .with_key("key-1");
+
+        match headers {
+            Some(headers) => message.with_headers(headers).build(),
+            None => message.build(),
+        }
+    }
+
+    #[test]
+    fn test_first_failure_is_redelivered() {
+        let policy = ParseRetryPolicy::new(3, "orders.dlq");
+        let message = malformed_message(None);
+
+        assert_eq!(parse_attempts(&message), 0);
+        assert_eq!(
+            policy.action_for(&message),
+            ParseFailureAction::Redeliver {
+                topic: "orders".to_string(),
+                attempts: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_exceeding_retries_lands_in_dlq_with_attempt_header() {
+        let policy = ParseRetryPolicy::new(3, "orders.dlq");
+        let message = malformed_message(Some(3));
+
+        let action = policy.action_for(&message);
+        assert_eq!(
+            action,
+            ParseFailureAction::DeadLetter {
+                topic: "orders.dlq".to_string(),
+                attempts: 4,
+            }
+        );
+
+        let headers = dead_letter_attempt_headers(&message, 4);
+        assert_eq!(
+            header_value(&headers, PARSE_ATTEMPTS_HEADER),
+            Some("4".to_string())
+        );
+        assert_eq!(header_value(&headers, "trace-id"), Some("abc".to_string()));
+        assert_eq!(
+            header_value(&headers, DLQ_ERROR_HEADER),
+            Some("failed to parse message after 4 attempts".to_string())
+        );
+        assert_eq!(
+            header_value(&headers, DLQ_ORIGINAL_TOPIC_HEADER),
+            Some("orders".to_string())
+        );
+        assert_eq!(
+            header_value(&headers, DLQ_ORIGINAL_PARTITION_HEADER),
+            Some("0".to_string())
+        );
+        assert_eq!(
+            header_value(&headers, DLQ_ORIGINAL_OFFSET_HEADER),
+            Some("0".to_string())
+        );
+        assert_eq!(headers.count(), 6);
+    }
+
+    #[test]
+    fn test_redelivery_keeps_headers_with_attempt_count() {
+        let headers = attempt_headers(&malformed_message(Some(1)), 2);
+
+        assert_eq!(
+            header_value(&headers, PARSE_ATTEMPTS_HEADER),
+            Some("2".to_string())
+        );
+        assert_eq!(header_value(&headers, "trace-id"), Some("abc".to_string()));
+        assert_eq!(headers.count(), 2);
+    }
+}
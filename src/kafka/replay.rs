@@ -0,0 +1,221 @@
+use std::{future::Future, path::Path};
+
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, BufReader},
+};
+use tracing::{info, warn};
+
+use crate::kafka::{HandlerResult, KafkaError, ParsedMessage, RouteRegistry};
+
+/// ReplayReport summarizes a replay of a JSON-lines file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Number of messages the handler processed successfully
+    pub processed: usize,
+    /// Number of messages the handler returned an error for
+    pub failed: usize,
+    /// Number of non-empty lines that are not a valid message
+    pub malformed: usize,
+    /// The failed and malformed lines, with their 1-based line number
+    pub errors: Vec<ReplayLineError>,
+}
+
+/// ReplayLineError describes a line that was not processed successfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayLineError {
+    /// The 1-based line number in the file
+    pub line: usize,
+    /// Why the line failed
+    pub error: String,
+}
+
+/// Reads newline-delimited `ParsedMessage` JSON from a file and feeds each message to a handler.
+///
+/// Lines are processed one at a time in file order. Empty lines are ignored, lines that are not
+/// a valid message are skipped and counted as malformed, and handler errors are counted as
+/// failed; neither stops the replay.
+///
+/// # Arguments
+///
+/// * `path` - The JSON-lines file to read.
+/// * `handler` - The function called with each message.
+///
+/// # Returns
+///
+/// * `Result<ReplayReport, KafkaError>` - The per-line outcome, or an error if the file cannot be read.
+pub async fn from_jsonl<P, F, Fut>(path: P, handler: F) -> Result<ReplayReport, KafkaError>
+where
+    P: AsRef<Path>,
+    F: Fn(ParsedMessage) -> Fut,
+    Fut: Future<Output = Result<HandlerResult, KafkaError>>,
+{
+    let path = path.as_ref();
+    let file = File::open(path).await.map_err(|e| {
+        KafkaError::InternalServerError(format!("failed to open {}: {}", path.display(), e))
+    })?;
+
+    let mut lines = BufReader::new(file).lines();
+    let mut report = ReplayReport::default();
+    let mut line_number = 0;
+
+    while let Some(line) = lines.next_line().await.map_err(|e| {
+        KafkaError::InternalServerError(format!("failed to read {}: {}", path.display(), e))
+    })? {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some(message) = ParsedMessage::parse_from_string(&line) else {
+            warn!("replay line {}: malformed message, skipped", line_number);
+            report.malformed += 1;
+            report.errors.push(ReplayLineError {
+                line: line_number,
+                error: "malformed message".to_string(),
+            });
+            continue;
+        };
+
+        match handler(message).await {
+            Ok(_) => report.processed += 1,
+            Err(e) => {
+                warn!("replay line {}: handler failed: {}", line_number, e);
+                report.failed += 1;
+                report.errors.push(ReplayLineError {
+                    line: line_number,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    info!(
+        "replayed {}: processed {}, failed {}, malformed {}",
+        path.display(),
+        report.processed,
+        report.failed,
+        report.malformed
+    );
+
+    Ok(report)
+}
+
+/// Replays a JSON-lines file through the handlers of a route registry.
+///
+/// Each message is dispatched to the handler registered for its URI, or the fallback;
+/// messages matching neither count as failed with `KafkaError::UriNotFound`.
+///
+/// # Arguments
+///
+/// * `path` - The JSON-lines file to read.
+/// * `routes` - The registry to dispatch messages with.
+///
+/// # Returns
+///
+/// * `Result<ReplayReport, KafkaError>` - The per-line outcome, or an error if the file cannot be read.
+pub async fn from_jsonl_with_routes<P>(
+    path: P,
+    routes: &RouteRegistry,
+) -> Result<ReplayReport, KafkaError>
+where
+    P: AsRef<Path>,
+{
+    from_jsonl(path, |mut message| async move {
+        let handler = match routes.resolve(&message.uri)? {
+            Some((handler, path_params)) => {
+                message.path_params = path_params;
+                handler
+            }
+            None => routes
+                .get_fallback()?
+                .ok_or_else(|| KafkaError::UriNotFound(message.uri.clone()))?,
+        };
+        handler(&message).await
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn fixture(contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("replay-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn line(uri: &str, message_id: &str) -> String {
+        serde_json::json!({
+            "messageType": "MESSAGE",
+            "sourceId": "recorder",
+            "transactionId": format!("tx-{}", message_id),
+            "messageId": message_id,
+            "uri": uri,
+            "responseDestination": null,
+            "data": { "id": message_id },
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_valid_lines_processed_and_malformed_counted() {
+        let path = fixture(&format!(
+            "{}\n{{not json\n\n{}\n{{\"uri\": \"/missing-fields\"}}\n{}\n",
+            line("/api/v1/event", "msg-1"),
+            line("/api/v1/event", "msg-2"),
+            line("/api/v1/event", "msg-3"),
+        ));
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let report = from_jsonl(&path, |message| {
+            let seen = seen.clone();
+            async move {
+                seen.lock().unwrap().push(message.message_id);
+                Ok(HandlerResult::Acknowledge)
+            }
+        })
+        .await
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), vec!["msg-1", "msg-2", "msg-3"]);
+        assert_eq!(report.processed, 3);
+        assert_eq!(report.malformed, 2);
+        assert_eq!(report.failed, 0);
+        let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_through_routes_counts_unmatched_as_failed() {
+        let path = fixture(&format!(
+            "{}\n{}\n",
+            line("/api/v1/event", "msg-1"),
+            line("/api/v1/unknown", "msg-2"),
+        ));
+
+        let mut routes = RouteRegistry::new();
+        routes.register("/api/v1/event", |_| async {
+            Ok(HandlerResult::Acknowledge)
+        });
+
+        let report = from_jsonl_with_routes(&path, &routes).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(report.processed, 1);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.errors[0].line, 2);
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_is_an_error() {
+        let result = from_jsonl("/nonexistent/replay.jsonl", |_| async {
+            Ok(HandlerResult::Acknowledge)
+        })
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -1,11 +1,14 @@
 use std::{
     collections::HashMap,
-    sync::Arc,
+    sync::{Arc, Weak},
     time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Context, Result};
-use rdkafka::message::{Message, OwnedMessage};
+use anyhow::{Context, Result};
+use rdkafka::{
+    message::{Message, OwnedMessage},
+    producer::Producer,
+};
 use tokio::{select, sync::oneshot::Sender};
 use tokio::{
     sync::{oneshot, RwLock},
@@ -14,9 +17,9 @@ use tokio::{
 use tracing::{error, info, warn};
 
 use crate::kafka::{
-    utils::utils::{create_message, extract_payload},
-    KafkaClientConfig, KafkaConsumer, KafkaError, KafkaProducer, MessageLatency, MessageType,
-    ParsedMessage, ResponseDestination,
+    utils::utils::{create_message, extract_payload, format_duration, log_received},
+    Acks, KafkaClientConfig, KafkaConsumer, KafkaError, KafkaProducer, MessageLatency, MessageType,
+    ParsedMessage, ResponseDestination, SendError,
 };
 
 /// RequestAsyncParams holds the parameters for sending asynchronous requests via Kafka.
@@ -108,23 +111,57 @@ impl RequestAsyncParams {
     }
 }
 
-struct PendingRequest {
-    sender: Sender<ParsedMessage>,
+/// HealthStatus reports whether a RequestSender can currently serve requests.
+///
+/// It serializes to JSON so it can be returned from a `/healthz` endpoint as is.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthStatus {
+    /// Whether the sender is healthy; false if any entry of `reasons` applies
+    pub healthy: bool,
+    /// Whether the broker answered a metadata request
+    pub broker_connected: bool,
+    /// The number of requests awaiting a response
+    pub pending_requests: usize,
+    /// The age in milliseconds of the oldest request awaiting a response
+    pub oldest_pending_age_ms: Option<u64>,
+    /// Why the sender is unhealthy, empty when healthy
+    pub reasons: Vec<String>,
+}
+
+type PendingResult = Result<ParsedMessage, SendError>;
+
+pub(crate) struct PendingRequest {
+    sender: Sender<PendingResult>,
     created_at: Instant,
+    timeout: Duration,
 }
 
 impl PendingRequest {
-    pub fn new(sender: Sender<ParsedMessage>) -> Self {
+    pub fn new(sender: Sender<PendingResult>, timeout: Duration) -> Self {
         Self {
             sender,
             created_at: Instant::now(),
+            timeout,
         }
     }
 
+    pub fn is_expired(&self) -> bool {
+        self.created_at.elapsed() > self.timeout
+    }
+
     pub fn resolve(self, value: ParsedMessage) -> Result<()> {
-        let _ = self.sender.send(value);
+        let _ = self.sender.send(Ok(value));
         Ok(())
     }
+
+    pub fn cancel(self, transaction_id: String) {
+        let _ = self.sender.send(Err(SendError::Cancelled(transaction_id)));
+    }
+
+    pub fn expire(self, transaction_id: String) {
+        let _ = self.sender.send(Err(SendError::Timeout(transaction_id)));
+    }
 }
 
 /// RequestSender manages the sending of asynchronous requests and handling responses via Kafka.
@@ -136,11 +173,15 @@ pub struct RequestSender {
     pending_requests: Arc<RwLock<HashMap<String, PendingRequest>>>,
     timeout_secs: i64,
     response_topic: String,
+    max_pending: Option<usize>,
+    reap_interval: Duration,
 }
 
 impl RequestSender {
     const DEFAULT_CONCURRENCY_LIMIT: usize = 100;
     const DEFAULT_TIMEOUT_SECS: i64 = 600;
+    const HEALTH_METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+    const DEFAULT_REAP_INTERVAL: Duration = Duration::from_secs(30);
 
     /// Creates a new RequestSender with the given configuration.
     ///
@@ -185,8 +226,8 @@ impl RequestSender {
         let consumer = KafkaConsumer::new(consumer_config, concurrency_limit)
             .context("failed to create Kafka consumer")?;
 
-        let producer =
-            KafkaProducer::new(config.clone()).context("failed to create Kafka producer")?;
+        let producer = KafkaProducer::new(config.clone().with_default_acks(Acks::All))
+            .context("failed to create Kafka producer")?;
 
         Ok(Self {
             config,
@@ -195,9 +236,46 @@ impl RequestSender {
             pending_requests: Arc::new(RwLock::new(HashMap::new())),
             timeout_secs,
             response_topic,
+            max_pending: None,
+            reap_interval: Self::DEFAULT_REAP_INTERVAL,
         })
     }
 
+    /// Limits the number of requests awaiting a response.
+    ///
+    /// Once the limit is reached, `send_request_async` rejects new requests with
+    /// `SendError::Overloaded` instead of queueing them, giving callers explicit backpressure.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_pending` - The maximum number of pending requests.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated RequestSender instance.
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = Some(max_pending);
+        self
+    }
+
+    /// Sets how often `start`'s background sweep removes expired pending requests.
+    ///
+    /// A pending request normally leaves the registry when its response arrives or its wait
+    /// times out, but an aborted caller skips that cleanup. The sweep removes requests pending
+    /// longer than their timeout so such entries cannot accumulate. Defaults to 30 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `reap_interval` - The interval between sweeps.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated RequestSender instance.
+    pub fn with_reap_interval(mut self, reap_interval: Duration) -> Self {
+        self.reap_interval = reap_interval;
+        self
+    }
+
     /// Retrieves the KafkaClientConfig associated with the RequestSender.
     ///
     /// # Returns
@@ -209,11 +287,15 @@ impl RequestSender {
 
     /// Starts the RequestSender to process incoming messages and handle responses.
     ///
+    /// Also spawns the sweep removing expired pending requests (see `with_reap_interval`),
+    /// which stops once the RequestSender is dropped.
+    ///
     /// # Returns
     ///
     /// * `Result<tokio::task::JoinHandle<()>>` - Returns a handle to the spawned task or an error if it fails.
     pub async fn start(&self) -> Result<tokio::task::JoinHandle<()>> {
         let timeout_secs = self.timeout_secs;
+        let log_payloads = self.config.log_payloads;
         let pending_requests = Arc::clone(&self.pending_requests);
 
         let consumer_task = self
@@ -221,11 +303,82 @@ impl RequestSender {
             .start(move |message| {
                 let pending_requests = Arc::clone(&pending_requests);
 
-                async move { Self::handle_message(message, pending_requests, timeout_secs).await }
+                async move {
+                    Self::handle_message(message, pending_requests, timeout_secs, log_payloads)
+                        .await
+                }
             })
             .await?;
 
-        Ok(consumer_task)
+        Self::spawn_reaper(Arc::downgrade(&self.pending_requests), self.reap_interval);
+
+        Ok(consumer_task.into_join_handle())
+    }
+
+    /// Spawns the periodic sweep removing expired pending requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `pending_requests` - The registry of pending requests; the sweep stops once it is dropped.
+    /// * `reap_interval` - The interval between sweeps.
+    fn spawn_reaper(
+        pending_requests: Weak<RwLock<HashMap<String, PendingRequest>>>,
+        reap_interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(reap_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately, when nothing can have expired yet
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                let Some(pending_requests) = pending_requests.upgrade() else {
+                    break;
+                };
+                Self::reap_expired(&pending_requests).await;
+            }
+        })
+    }
+
+    /// Removes the pending requests waiting longer than their timeout.
+    ///
+    /// A caller still waiting on a reaped request receives `SendError::Timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pending_requests` - The registry of pending requests.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of reaped requests.
+    async fn reap_expired(pending_requests: &RwLock<HashMap<String, PendingRequest>>) -> usize {
+        let expired: Vec<(String, PendingRequest)> = {
+            let mut guard = pending_requests.write().await;
+            let expired_ids: Vec<String> = guard
+                .iter()
+                .filter(|(_, request)| request.is_expired())
+                .map(|(transaction_id, _)| transaction_id.clone())
+                .collect();
+            expired_ids
+                .into_iter()
+                .filter_map(|transaction_id| {
+                    guard
+                        .remove(&transaction_id)
+                        .map(|request| (transaction_id, request))
+                })
+                .collect()
+        };
+
+        let reaped = expired.len();
+        for (transaction_id, request) in expired {
+            request.expire(transaction_id);
+        }
+
+        if reaped > 0 {
+            warn!("reaped {} expired pending requests", reaped);
+        }
+        reaped
     }
 
     /// Handles an incoming Kafka message by resolving the corresponding pending request.
@@ -235,30 +388,27 @@ impl RequestSender {
     /// * `message` - The Kafka message to handle.
     /// * `pending_requests` - The registry of pending requests.
     /// * `timeout_secs` - The timeout in seconds for requests.
+    /// * `log_payloads` - Whether to log the payload of the message.
     ///
     /// # Returns
     ///
     /// * `Result<()>` - Returns Ok if the message is handled successfully, or an error if it fails.
-    async fn handle_message(
+    pub(crate) async fn handle_message(
         message: OwnedMessage,
         pending_requests: Arc<RwLock<HashMap<String, PendingRequest>>>,
         timeout_secs: i64,
+        log_payloads: bool,
     ) -> Result<()> {
-        let payload = extract_payload(&message).ok_or_else(|| anyhow!("message has no payload"))?;
+        let payload = extract_payload(&message)?;
 
-        let latency = message.get_latency();
-
-        info!(
-            "received message: '{}' from topic {}, latency: {}ms",
-            payload,
-            message.topic(),
-            latency,
-        );
+        log_received(&message, &payload, log_payloads);
 
         if message.is_expired(timeout_secs) {
             warn!(
-                "ignore this request because it is expired {} _ {}",
-                timeout_secs, payload
+                "ignore this request because it is expired {} _ {}@{}",
+                timeout_secs,
+                message.topic(),
+                message.offset()
             );
             return Ok(());
         }
@@ -269,12 +419,14 @@ impl RequestSender {
         let mut guard = pending_requests.write().await;
 
         if let Some(request) = guard.remove(&parsed_message.transaction_id) {
-            let duration = Instant::now()
-                .duration_since(request.created_at)
-                .as_millis();
+            let duration = format_duration(
+                Instant::now()
+                    .duration_since(request.created_at)
+                    .as_millis() as i64,
+            );
 
             info!(
-                "request {} took {}ms",
+                "request {} took {}",
                 parsed_message.transaction_id, duration
             );
 
@@ -334,64 +486,551 @@ impl RequestSender {
 
     /// Sends an asynchronous request and waits for a response.
     ///
+    /// An error response from the remote service is returned as `Ok`; inspect the response data
+    /// to tell success from failure.
+    ///
     /// # Arguments
     ///
     /// * `params` - The parameters for the request.
     ///
     /// # Returns
     ///
-    /// * `Result<ParsedMessage, KafkaError>` - Returns the parsed response message or a KafkaError if it fails.
+    /// * `Result<ParsedMessage, SendError>` - Returns the parsed response message, or a SendError if
+    ///   no response was received.
     pub async fn send_request_async(
         &self,
         params: RequestAsyncParams,
-    ) -> Result<ParsedMessage, KafkaError> {
+    ) -> Result<ParsedMessage, SendError> {
         let transaction_id = params
             .transaction_id
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
-        let (tx, rx) = oneshot::channel::<ParsedMessage>();
+        let timeout =
+            Duration::from_secs(params.timeout_secs.unwrap_or(Self::DEFAULT_TIMEOUT_SECS) as u64);
+        let rx = self.register_pending(&transaction_id, timeout).await?;
 
+        if let Err(e) = self
+            .send_request_base(
+                params.topic,
+                params.uri,
+                transaction_id.clone(),
+                params.message_id,
+                params.data,
+            )
+            .await
         {
-            let mut guard = self.pending_requests.write().await;
-            let pending_request = PendingRequest::new(tx);
-            guard.insert(transaction_id.clone(), pending_request);
+            self.pending_requests.write().await.remove(&transaction_id);
+            return Err(SendError::Produce(e.to_string()));
         }
 
-        self.send_request_base(
-            params.topic,
-            params.uri,
-            transaction_id.clone(),
-            params.message_id,
-            params.data,
-        )
-        .await?;
+        self.wait_for_response(&transaction_id, rx, timeout).await
+    }
+
+    /// Sends a typed request and waits for its typed response.
+    ///
+    /// The request is serialized as the message data and the `/data` field of the response is
+    /// deserialized into `Res`, like `ParsedMessage::get_data_as` at the receiving end.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to which the request will be sent.
+    /// * `uri` - The URI associated with the request.
+    /// * `request` - The request data.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Res, KafkaError>` - The response data, a SerializationError if the request or
+    ///   response does not match its type, the remote error for an error response, or the
+    ///   `SendError` mapped to a KafkaError if no response was received.
+    pub async fn send_request_typed<Req, Res>(
+        &self,
+        topic: &str,
+        uri: &str,
+        request: Req,
+    ) -> Result<Res, KafkaError>
+    where
+        Req: serde::Serialize,
+        Res: serde::de::DeserializeOwned,
+    {
+        let data = serde_json::to_value(request).map_err(|e| {
+            KafkaError::SerializationError(format!(
+                "failed to serialize request for {}: {}",
+                uri, e
+            ))
+        })?;
+
+        let params = RequestAsyncParams::new(topic.to_string(), uri.to_string(), None, data);
+        let response = self.send_request_async(params).await?;
+
+        Self::typed_response(&response)
+    }
+
+    /// Extracts the typed `/data` field of a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The response message.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Res, KafkaError>` - The response data, an InternalServerError carrying the remote
+    ///   status for an error response, or a SerializationError if the data does not match `Res`.
+    fn typed_response<Res>(response: &ParsedMessage) -> Result<Res, KafkaError>
+    where
+        Res: serde::de::DeserializeOwned,
+    {
+        let data = response
+            .data
+            .pointer("/data")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+
+        if let (serde_json::Value::Null, Some(status)) = (&data, response.data.get("status")) {
+            if let Some(code) = status.get("code").and_then(|code| code.as_str()) {
+                let message = status
+                    .get("message")
+                    .and_then(|message| message.as_str())
+                    .unwrap_or_default();
+                return Err(KafkaError::InternalServerError(format!(
+                    "request {} failed with {}: {}",
+                    response.uri, code, message
+                )));
+            }
+        }
+
+        serde_json::from_value(data).map_err(|e| {
+            KafkaError::SerializationError(format!(
+                "failed to deserialize response for {}: {}",
+                response.uri, e
+            ))
+        })
+    }
+
+    /// Cancels a pending request; its caller receives `SendError::Cancelled`.
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction_id` - The transaction identifier of the request.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns true if the request was pending, false otherwise.
+    pub async fn cancel_request(&self, transaction_id: &str) -> bool {
+        match self.pending_requests.write().await.remove(transaction_id) {
+            Some(request) => {
+                request.cancel(transaction_id.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Registers a pending request awaiting a response, unless `max_pending` is reached.
+    async fn register_pending(
+        &self,
+        transaction_id: &str,
+        timeout: Duration,
+    ) -> Result<oneshot::Receiver<PendingResult>, SendError> {
+        let (tx, rx) = oneshot::channel::<PendingResult>();
+        let mut guard = self.pending_requests.write().await;
+
+        if let Some(max_pending) = self.max_pending {
+            if guard.len() >= max_pending {
+                warn!(
+                    "reject request {}: too many pending requests",
+                    transaction_id
+                );
+                return Err(SendError::Overloaded(max_pending));
+            }
+        }
+
+        guard.insert(transaction_id.to_string(), PendingRequest::new(tx, timeout));
+        Ok(rx)
+    }
 
+    /// Waits for the response of a pending request.
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction_id` - The transaction identifier of the request.
+    /// * `rx` - The receiver resolved when the response arrives.
+    /// * `timeout` - How long to wait for the response.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ParsedMessage, SendError>` - The response, or a SendError if none was received.
+    async fn wait_for_response(
+        &self,
+        transaction_id: &str,
+        rx: oneshot::Receiver<PendingResult>,
+        timeout: Duration,
+    ) -> Result<ParsedMessage, SendError> {
         select! {
             res = rx => {
-                self.pending_requests.write().await.remove(&transaction_id);
+                self.pending_requests.write().await.remove(transaction_id);
                 match res {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(KafkaError::InternalServerError(format!("channel closed unexpectedly: {}", e))),
+                    Ok(result) => result,
+                    Err(e) => Err(SendError::ChannelClosed(e.to_string())),
                 }
             }
-            _ = sleep(Duration::from_secs(params.timeout_secs.unwrap_or(Self::DEFAULT_TIMEOUT_SECS) as u64)) => {
+            _ = sleep(timeout) => {
                 error!("request {} timeout", transaction_id);
-                self.pending_requests.write().await.remove(&transaction_id);
-                Err(KafkaError::TimeoutError(format!("request {} timeout", transaction_id)))
+                self.pending_requests.write().await.remove(transaction_id);
+                Err(SendError::Timeout(transaction_id.to_string()))
+            }
+        }
+    }
+
+    /// Reports broker connectivity and the state of pending requests.
+    ///
+    /// The sender is unhealthy if the broker does not answer a metadata request, if `max_pending`
+    /// requests are pending, so new requests are rejected, or if a request has been pending
+    /// longer than its own timeout, which means it was not cleaned up.
+    ///
+    /// # Returns
+    ///
+    /// * `HealthStatus` - The aggregated health of the sender.
+    pub async fn health(&self) -> HealthStatus {
+        let producer = self.producer.producer.clone();
+        let broker_connected = tokio::task::spawn_blocking(move || {
+            producer
+                .client()
+                .fetch_metadata(None, Self::HEALTH_METADATA_TIMEOUT)
+                .is_ok()
+        })
+        .await
+        .unwrap_or(false);
+
+        let (pending_requests, oldest_pending_age, expired_requests) = {
+            let guard = self.pending_requests.read().await;
+            let oldest = guard
+                .values()
+                .map(|pending| pending.created_at.elapsed())
+                .max();
+            let expired = guard
+                .values()
+                .filter(|pending| pending.is_expired())
+                .count();
+            (guard.len(), oldest, expired)
+        };
+
+        let mut reasons = Vec::new();
+        if !broker_connected {
+            reasons.push("broker metadata request failed".to_string());
+        }
+        if let Some(max_pending) = self.max_pending {
+            if pending_requests >= max_pending {
+                reasons.push(format!(
+                    "{} pending requests reached the limit of {}",
+                    pending_requests, max_pending
+                ));
             }
         }
+        if expired_requests > 0 {
+            reasons.push(format!(
+                "{} pending requests are past their timeout",
+                expired_requests
+            ));
+        }
+
+        HealthStatus {
+            healthy: reasons.is_empty(),
+            broker_connected,
+            pending_requests,
+            oldest_pending_age_ms: oldest_pending_age.map(|age| age.as_millis() as u64),
+            reasons,
+        }
     }
 
     /// Sends a request and waits for an acknowledgment.
     ///
+    /// Any response that is not an error counts as an acknowledgment. A handler that answers with
+    /// `HandlerResult::Acknowledge` only responds when its `StreamHandler` is built with
+    /// `with_acknowledge_response(true)`; otherwise the request times out.
+    ///
     /// # Arguments
     ///
     /// * `params` - The parameters for the request.
     ///
     /// # Returns
     ///
-    /// * `Result<(), KafkaError>` - Returns Ok if the request is acknowledged, or a KafkaError if it fails.
-    pub async fn send_request_acknowledge(&self, _: RequestAsyncParams) -> Result<(), KafkaError> {
-        todo!("send request acknowledge")
+    /// * `Result<(), KafkaError>` - Returns Ok if the request is acknowledged, the remote error for
+    ///   an error response, or the `SendError` mapped to a KafkaError if no response was received.
+    pub async fn send_request_acknowledge(
+        &self,
+        params: RequestAsyncParams,
+    ) -> Result<(), KafkaError> {
+        let response = self.send_request_async(params).await?;
+
+        Self::typed_response::<serde_json::Value>(&response).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sender() -> RequestSender {
+        RequestSender::new(KafkaClientConfig::new(
+            "test-service".to_string(),
+            "localhost:9092".to_string(),
+        ))
+        .unwrap()
+    }
+
+    #[derive(Debug, serde::Deserialize, PartialEq)]
+    struct Account {
+        id: u32,
+        name: String,
+    }
+
+    fn response_with(data: serde_json::Value) -> ParsedMessage {
+        let mut response = crate::kafka::testing::sample_message();
+        response.uri = "/api/v1/accounts".to_string();
+        response.data = data;
+        response
+    }
+
+    #[test]
+    fn test_typed_response_decodes_data() {
+        let response = response_with(serde_json::json!({ "data": { "id": 7, "name": "alice" } }));
+
+        let account: Account = RequestSender::typed_response(&response).unwrap();
+        assert_eq!(
+            account,
+            Account {
+                id: 7,
+                name: "alice".to_string()
+            }
+        );
+
+        let mismatch = RequestSender::typed_response::<Account>(&response_with(
+            serde_json::json!({ "data": { "id": "seven" } }),
+        ));
+        assert!(matches!(mismatch, Err(KafkaError::SerializationError(_))));
+    }
+
+    #[test]
+    fn test_typed_response_surfaces_error_status() {
+        let response = response_with(
+            KafkaError::UriNotFound("/api/v1/accounts".to_string()).to_response_value(),
+        );
+
+        let error = RequestSender::typed_response::<Account>(&response).unwrap_err();
+        assert!(matches!(error, KafkaError::InternalServerError(_)));
+        assert!(error.to_string().contains("URI_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_acknowledge_produce_failure_is_connection_error() {
+        let config =
+            KafkaClientConfig::new("test-service".to_string(), "localhost:9092".to_string())
+                .set("message.max.bytes", "100000");
+        let sender = RequestSender::new(config).unwrap();
+        // Larger than message.max.bytes, rejected before reaching any broker
+        let data = serde_json::json!({ "blob": "x".repeat(200_000) });
+        let params = RequestAsyncParams::new(
+            "test-topic".to_string(),
+            "/api/v1/test".to_string(),
+            None,
+            data,
+        );
+
+        let result = sender.send_request_acknowledge(params).await;
+
+        assert!(matches!(result, Err(KafkaError::ConnectionError(_))));
+        assert!(sender.pending_requests.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unserializable_request_is_serialization_error() {
+        let sender = sender();
+        let request = HashMap::from([((1, 2), "not a string key")]);
+
+        let result = sender
+            .send_request_typed::<_, Account>("test-topic", "/api/v1/accounts", request)
+            .await;
+
+        assert!(matches!(result, Err(KafkaError::SerializationError(_))));
+        assert!(sender.pending_requests.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_produce_failure_is_send_error_produce() {
+        let config =
+            KafkaClientConfig::new("test-service".to_string(), "localhost:9092".to_string())
+                .set("message.max.bytes", "100000");
+        let sender = RequestSender::new(config).unwrap();
+        // Larger than message.max.bytes, rejected before reaching any broker
+        let data = serde_json::json!({ "blob": "x".repeat(200_000) });
+        let params = RequestAsyncParams::new(
+            "test-topic".to_string(),
+            "/api/v1/test".to_string(),
+            None,
+            data,
+        )
+        .with_transaction_id("tx-produce".to_string());
+
+        let result = sender.send_request_async(params).await;
+
+        assert!(matches!(result, Err(SendError::Produce(_))));
+        assert!(sender.pending_requests.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_no_response_is_send_error_timeout() {
+        let sender = sender();
+        let rx = sender
+            .register_pending("tx-timeout", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let result = sender
+            .wait_for_response("tx-timeout", rx, Duration::from_millis(20))
+            .await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            SendError::Timeout("tx-timeout".to_string())
+        );
+        assert!(sender.pending_requests.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_is_send_error_cancelled() {
+        let sender = sender();
+        let rx = sender
+            .register_pending("tx-cancel", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert!(sender.cancel_request("tx-cancel").await);
+        assert!(!sender.cancel_request("tx-cancel").await);
+
+        let result = sender
+            .wait_for_response("tx-cancel", rx, Duration::from_secs(5))
+            .await;
+        assert_eq!(
+            result.unwrap_err(),
+            SendError::Cancelled("tx-cancel".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_rejected_when_max_pending_reached() {
+        let sender = sender().with_max_pending(2);
+        let _first = sender
+            .register_pending("tx-1", Duration::from_secs(60))
+            .await
+            .unwrap();
+        let _second = sender
+            .register_pending("tx-2", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let params = RequestAsyncParams::new(
+            "test-topic".to_string(),
+            "/api/v1/test".to_string(),
+            None,
+            serde_json::json!({}),
+        );
+        let result = sender.send_request_async(params).await;
+
+        assert_eq!(result.unwrap_err(), SendError::Overloaded(2));
+        assert_eq!(sender.pending_requests.read().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_health_unhealthy_with_stale_pending_request() {
+        let config =
+            KafkaClientConfig::new("test-service".to_string(), "localhost:9092".to_string());
+        let sender = RequestSender::with_concurrency_limit(config, 1, 1).unwrap();
+        let (tx, _rx) = oneshot::channel();
+        sender.pending_requests.write().await.insert(
+            "tx-stale".to_string(),
+            PendingRequest {
+                sender: tx,
+                created_at: Instant::now() - Duration::from_secs(5),
+                timeout: Duration::from_secs(1),
+            },
+        );
+
+        let health = sender.health().await;
+
+        assert!(!health.healthy);
+        assert_eq!(health.pending_requests, 1);
+        assert!(health.oldest_pending_age_ms.unwrap() >= 5000);
+        assert!(health
+            .reasons
+            .contains(&"1 pending requests are past their timeout".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_health_unhealthy_when_max_pending_reached() {
+        let sender = sender().with_max_pending(2);
+        for transaction_id in ["tx-1", "tx-2"] {
+            sender
+                .register_pending(transaction_id, Duration::from_secs(60))
+                .await
+                .unwrap();
+        }
+
+        let health = sender.health().await;
+
+        assert!(!health.healthy);
+        assert_eq!(health.pending_requests, 2);
+        assert!(health
+            .reasons
+            .contains(&"2 pending requests reached the limit of 2".to_string()));
+        assert!(!health
+            .reasons
+            .iter()
+            .any(|reason| reason.contains("past their timeout")));
+    }
+
+    #[tokio::test]
+    async fn test_reaper_removes_only_expired_requests() {
+        let sender = sender();
+        let (tx, rx) = oneshot::channel();
+        sender.pending_requests.write().await.insert(
+            "tx-expired".to_string(),
+            PendingRequest {
+                sender: tx,
+                created_at: Instant::now() - Duration::from_secs(5),
+                timeout: Duration::from_secs(1),
+            },
+        );
+        let _fresh = sender
+            .register_pending("tx-fresh", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            RequestSender::reap_expired(&sender.pending_requests).await,
+            1
+        );
+
+        let pending = sender.pending_requests.read().await;
+        assert_eq!(pending.len(), 1);
+        assert!(pending.contains_key("tx-fresh"));
+        assert_eq!(
+            rx.await.unwrap().unwrap_err(),
+            SendError::Timeout("tx-expired".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reaper_stops_when_registry_dropped() {
+        let pending_requests = Arc::new(RwLock::new(HashMap::new()));
+        let reaper = RequestSender::spawn_reaper(
+            Arc::downgrade(&pending_requests),
+            Duration::from_millis(5),
+        );
+        drop(pending_requests);
+
+        tokio::time::timeout(Duration::from_secs(1), reaper)
+            .await
+            .unwrap()
+            .unwrap();
     }
 }
@@ -0,0 +1,182 @@
+use std::{future::Future, time::Duration};
+
+use tracing::warn;
+
+use crate::kafka::KafkaError;
+
+/// RetryPolicy bounds how often and how long a transient failure is retried.
+///
+/// The delay before each retry starts at `initial_backoff` and doubles up to `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of retries after the first attempt
+    pub max_retries: u32,
+    /// The delay before the first retry
+    pub initial_backoff: Duration,
+    /// The upper bound for the delay between retries
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a new RetryPolicy.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_retries` - The maximum number of retries after the first attempt.
+    /// * `initial_backoff` - The delay before the first retry.
+    /// * `max_backoff` - The upper bound for the delay between retries.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of RetryPolicy.
+    pub fn new(max_retries: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::new(0, Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Retrieves the delay before the given retry (1-based).
+    ///
+    /// # Arguments
+    ///
+    /// * `retry` - The retry number, starting at 1.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - The delay before that retry.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three retries starting at 100ms, capped at 2s.
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(100), Duration::from_secs(2))
+    }
+}
+
+/// Runs an operation, retrying it while it fails with a transient error.
+///
+/// Errors for which `KafkaError::is_transient` is false are returned immediately.
+///
+/// # Arguments
+///
+/// * `policy` - The retry bounds.
+/// * `operation` - The operation to run, called once per attempt.
+///
+/// # Returns
+///
+/// * `Result<T, KafkaError>` - The first successful result, or the last error.
+pub async fn retry_transient<T, F, Fut>(
+    policy: RetryPolicy,
+    mut operation: F,
+) -> Result<T, KafkaError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, KafkaError>>,
+{
+    let mut retry = 0;
+
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) if e.is_transient() && retry < policy.max_retries => {
+                retry += 1;
+                let backoff = policy.backoff(retry);
+                warn!(
+                    "transient error (retry {}/{} in {:?}): {}",
+                    retry, policy.max_retries, backoff, e
+                );
+                tokio::time::sleep(backoff).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    };
+
+    fn fast_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::new(
+            max_retries,
+            Duration::from_millis(1),
+            Duration::from_millis(5),
+        )
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(100), Duration::from_millis(350));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+    }
+
+    #[tokio::test]
+    async fn test_fails_once_then_delivers() {
+        let attempts = AtomicU32::new(0);
+        let delivered = Mutex::new(Vec::new());
+
+        let result = retry_transient(fast_policy(3), || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(KafkaError::ConnectionError(
+                    "broker unavailable".to_string(),
+                ));
+            }
+            delivered.lock().unwrap().push("response");
+            Ok(())
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(*delivered.lock().unwrap(), vec!["response"]);
+    }
+
+    #[tokio::test]
+    async fn test_non_transient_error_is_not_retried() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), KafkaError> = retry_transient(fast_policy(3), || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(KafkaError::InternalServerError(
+                "invalid message".to_string(),
+            ))
+        })
+        .await;
+
+        assert!(matches!(result, Err(KafkaError::InternalServerError(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), KafkaError> = retry_transient(fast_policy(2), || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(KafkaError::TimeoutError("queue full".to_string()))
+        })
+        .await;
+
+        assert!(matches!(result, Err(KafkaError::TimeoutError(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+}
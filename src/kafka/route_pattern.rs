@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+/// The path parameter holding the URI segments matched by a trailing `*`.
+pub const CATCH_ALL_PARAM: &str = "*";
+
+/// RoutePattern is a route URI template with named `{param}` or `:param` segments, e.g.
+/// `/users/{id}` or `/users/:id`, optionally ending with a `*` catch-all segment.
+///
+/// A template matches URIs with the same number of `/`-separated segments whose literal
+/// segments are equal; each named segment captures the corresponding URI segment,
+/// percent-decoded, as a path parameter. A trailing `*` matches one or more remaining
+/// segments, captured together under the `*` parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutePattern {
+    template: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    CatchAll,
+}
+
+impl RoutePattern {
+    /// Parses a route template.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The route URI, e.g. `/users/{id}/orders/{order_id}` or `/files/*`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Self>` - The pattern, or None if the template has no parameter or catch-all
+    ///   segment and is therefore matched exactly.
+    pub fn parse(template: &str) -> Option<Self> {
+        let parts: Vec<&str> = template.split('/').collect();
+        let last = parts.len() - 1;
+        let segments: Vec<Segment> = parts
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                if i == last && *segment == "*" {
+                    return Segment::CatchAll;
+                }
+                let name = segment
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.strip_suffix('}'))
+                    .or_else(|| segment.strip_prefix(':'));
+                match name {
+                    Some(name) if !name.is_empty() => Segment::Param(name.to_string()),
+                    _ => Segment::Literal(segment.to_string()),
+                }
+            })
+            .collect();
+
+        if segments.iter().all(|s| matches!(s, Segment::Literal(_))) {
+            return None;
+        }
+
+        Some(Self {
+            template: template.to_string(),
+            segments,
+        })
+    }
+
+    /// Retrieves the template the pattern was parsed from.
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Retrieves the number of literal segments; patterns with more are more specific.
+    pub fn specificity(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|s| matches!(s, Segment::Literal(_)))
+            .count()
+    }
+
+    /// Returns true if the pattern ends with a `*` catch-all segment.
+    pub fn is_catch_all(&self) -> bool {
+        matches!(self.segments.last(), Some(Segment::CatchAll))
+    }
+
+    /// Matches a URI against the pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The message URI.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<HashMap<String, String>>` - The captured path parameters, or None if the URI
+    ///   does not match.
+    pub fn matches(&self, uri: &str) -> Option<HashMap<String, String>> {
+        let parts: Vec<&str> = uri.split('/').collect();
+        let length_matches = if self.is_catch_all() {
+            parts.len() >= self.segments.len()
+        } else {
+            parts.len() == self.segments.len()
+        };
+        if !length_matches {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            let part = parts[i];
+            match segment {
+                Segment::Literal(literal) if literal == part => {}
+                Segment::Literal(_) => return None,
+                Segment::Param(_) if part.is_empty() => return None,
+                Segment::Param(name) => {
+                    params.insert(name.clone(), percent_decode(part));
+                }
+                Segment::CatchAll => {
+                    let rest = parts[i..].join("/");
+                    if rest.is_empty() {
+                        return None;
+                    }
+                    params.insert(CATCH_ALL_PARAM.to_string(), percent_decode(&rest));
+                }
+            }
+        }
+
+        Some(params)
+    }
+}
+
+/// Decodes `%XX` escapes in a path segment; malformed escapes are kept as is.
+pub fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            // from_str_radix accepts a leading sign, so "%+f" would otherwise decode
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+            if let Some(byte) = hex {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_template_is_not_a_pattern() {
+        assert!(RoutePattern::parse("/api/v1/users").is_none());
+    }
+
+    #[test]
+    fn test_named_params_extracted() {
+        let pattern = RoutePattern::parse("/users/{id}/orders/{order_id}").unwrap();
+        let params = pattern.matches("/users/123/orders/A-9").unwrap();
+
+        assert_eq!(params["id"], "123");
+        assert_eq!(params["order_id"], "A-9");
+        assert_eq!(pattern.specificity(), 3);
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let pattern = RoutePattern::parse("/users/{id}").unwrap();
+
+        assert!(pattern.matches("/users").is_none());
+        assert!(pattern.matches("/users/").is_none());
+        assert!(pattern.matches("/users/1/extra").is_none());
+        assert!(pattern.matches("/groups/1").is_none());
+    }
+
+    #[test]
+    fn test_colon_params_extracted() {
+        let pattern = RoutePattern::parse("/api/v1/users/:id/orders/:orderId").unwrap();
+        let params = pattern.matches("/api/v1/users/42/orders/7").unwrap();
+
+        assert_eq!(params["id"], "42");
+        assert_eq!(params["orderId"], "7");
+        assert!(!pattern.is_catch_all());
+    }
+
+    #[test]
+    fn test_trailing_catch_all() {
+        let pattern = RoutePattern::parse("/files/:bucket/*").unwrap();
+        assert!(pattern.is_catch_all());
+        assert_eq!(pattern.specificity(), 2);
+
+        let params = pattern.matches("/files/docs/2024/report.pdf").unwrap();
+        assert_eq!(params["bucket"], "docs");
+        assert_eq!(params[CATCH_ALL_PARAM], "2024/report.pdf");
+
+        assert_eq!(
+            pattern.matches("/files/docs/a").unwrap()[CATCH_ALL_PARAM],
+            "a"
+        );
+        assert!(pattern.matches("/files/docs").is_none());
+        assert!(pattern.matches("/files/docs/").is_none());
+
+        // Only a trailing `*` is a catch-all
+        assert!(RoutePattern::parse("/files/*/meta").is_none());
+    }
+
+    #[test]
+    fn test_params_percent_decoded() {
+        let pattern = RoutePattern::parse("/files/{name}").unwrap();
+        let params = pattern.matches("/files/my%20report%2Fv2").unwrap();
+        assert_eq!(params["name"], "my report/v2");
+
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%41"), "%zzA");
+        assert_eq!(percent_decode("%+f"), "%+f");
+        assert_eq!(percent_decode("%-1"), "%-1");
+    }
+}
@@ -1,9 +1,16 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+};
 
 use std::sync::Mutex;
+use tokio::sync::Semaphore;
 use tracing::{error, info};
 
-use crate::kafka::{HandlerResult, KafkaError, MessageHandler, ParsedMessage};
+use crate::kafka::{
+    BatchConfig, BatchHandler, BatchRoute, HandlerContext, HandlerResult, KafkaError,
+    MessageHandler, ParsedMessage, RouteHandler, RoutePattern,
+};
 
 /// The `routes` macro provides a convenient way to create a `RouteRegistry` with registered routes.
 ///
@@ -41,10 +48,46 @@ macro_rules! routes {
     }};
 }
 
+/// A matched handler together with the path parameters captured by its route template.
+pub type ResolvedRoute = (MessageHandler, HashMap<String, String>);
+
+/// A matched route together with its concurrency limit, if one was registered.
+pub type LimitedRoute = (ResolvedRoute, Option<Arc<Semaphore>>);
+
+/// The difference between the registered URIs and a manifest of expected URIs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteDiff {
+    /// URIs listed in the manifest but not registered.
+    pub missing: BTreeSet<String>,
+    /// URIs registered but not listed in the manifest.
+    pub extra: BTreeSet<String>,
+}
+
+impl RouteDiff {
+    /// Returns true if the registered URIs match the manifest exactly.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
 /// `RouteRegistry` manages the registration and retrieval of message handlers for specific URIs.
+///
+/// A URI may contain named `{param}` or `:param` segments, e.g. `/users/{id}`, and end with a
+/// `*` catch-all segment, e.g. `/files/*`. When looking up a handler, an exact URI match takes
+/// precedence over a template and a template with only named segments over a catch-all; among
+/// matching templates of the same kind the one with the most literal segments wins, then the
+/// one registered first.
+///
+/// A route registered with `register_with_limit` runs at most the given number of handlers at
+/// once; other routes are only bounded by the consumer's concurrency limit.
 #[derive(Clone)]
 pub struct RouteRegistry {
     routes: Arc<Mutex<HashMap<String, MessageHandler>>>,
+    patterns: Arc<Mutex<Vec<(RoutePattern, MessageHandler)>>>,
+    limits: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+    batch_routes: Arc<Mutex<HashMap<String, BatchRoute>>>,
+    fallback: Arc<Mutex<Option<MessageHandler>>>,
+    context: Arc<Mutex<Option<HandlerContext>>>,
 }
 
 impl RouteRegistry {
@@ -56,6 +99,11 @@ impl RouteRegistry {
     pub fn new() -> Self {
         Self {
             routes: Arc::new(Mutex::new(HashMap::new())),
+            patterns: Arc::new(Mutex::new(Vec::new())),
+            limits: Arc::new(Mutex::new(HashMap::new())),
+            batch_routes: Arc::new(Mutex::new(HashMap::new())),
+            fallback: Arc::new(Mutex::new(None)),
+            context: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -74,24 +122,343 @@ impl RouteRegistry {
         F: Fn(ParsedMessage) -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = Result<HandlerResult, KafkaError>> + Send + 'static,
     {
-        let handler = Arc::new(move |msg: &ParsedMessage| {
+        self.insert_handler(uri, Self::into_handler(f))
+    }
+
+    /// Registers a message handler for a specific URI that runs at most `max_concurrent` times
+    /// at once.
+    ///
+    /// Messages for the route beyond the limit wait for a permit before their handler is invoked,
+    /// so a CPU-heavy route can be capped below the consumer's concurrency limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI for which the handler is registered.
+    /// * `max_concurrent` - The maximum number of concurrent handler invocations, at least 1.
+    /// * `f` - The handler function to register.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Self` - The updated `RouteRegistry` instance.
+    pub fn register_with_limit<F, Fut>(
+        &mut self,
+        uri: &str,
+        max_concurrent: usize,
+        f: F,
+    ) -> &mut Self
+    where
+        F: Fn(ParsedMessage) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<HandlerResult, KafkaError>> + Send + 'static,
+    {
+        self.insert_handler(uri, Self::into_handler(f));
+
+        if let Ok(mut limits) = self.limits.lock() {
+            limits.insert(
+                uri.to_string(),
+                Arc::new(Semaphore::new(max_concurrent.max(1))),
+            );
+            info!(
+                "limited uri {} to {} concurrent handlers",
+                uri,
+                max_concurrent.max(1)
+            );
+        } else {
+            error!("Failed to acquire lock for route limits");
+        }
+
+        self
+    }
+
+    /// Registers a `RouteHandler` for a specific URI, alongside the closure based `register`.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI for which the handler is registered.
+    /// * `handler` - The handler to register.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Self` - The updated `RouteRegistry` instance.
+    pub fn register_handler(&mut self, uri: &str, handler: Arc<dyn RouteHandler>) -> &mut Self {
+        let handler: MessageHandler = Arc::new(move |msg: &ParsedMessage| {
+            let handler = handler.clone();
+            let msg = msg.clone();
+            Box::pin(async move { handler.handle(&msg).await })
+        });
+
+        self.insert_handler(uri, handler)
+    }
+
+    /// Stores a handler under a URI or URI template, replacing any previous one and its limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI for which the handler is registered.
+    /// * `handler` - The handler to store.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Self` - The updated `RouteRegistry` instance.
+    fn insert_handler(&mut self, uri: &str, handler: MessageHandler) -> &mut Self {
+        if let Ok(mut limits) = self.limits.lock() {
+            limits.remove(uri);
+        }
+
+        if let Some(pattern) = RoutePattern::parse(uri) {
+            if let Ok(mut patterns) = self.patterns.lock() {
+                patterns.retain(|(existing, _)| existing.template() != uri);
+                patterns.push((pattern, handler));
+                info!("registered handler for uri template: {}", uri);
+            } else {
+                error!("Failed to acquire lock for route patterns");
+            }
+            return self;
+        }
+
+        if let Ok(mut routes) = self.routes.lock() {
+            routes.insert(uri.to_string(), handler);
+            info!("registered handler for uri: {}", uri);
+        } else {
+            error!("Failed to acquire lock for routes");
+        }
+
+        self
+    }
+
+    /// Registers a handler receiving the message data deserialized as `T`.
+    ///
+    /// If the data does not match `T`, the handler is not called and the caller receives a
+    /// `VALUE_INVALID` response naming the offending field path in `status.data`.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI for which the handler is registered.
+    /// * `f` - The handler function receiving the message and its typed data.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Self` - The updated `RouteRegistry` instance.
+    pub fn register_typed<T, F, Fut>(&mut self, uri: &str, f: F) -> &mut Self
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+        F: Fn(ParsedMessage, T) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<HandlerResult, KafkaError>> + Send + 'static,
+    {
+        let f = Arc::new(f);
+        self.register(uri, move |message: ParsedMessage| {
+            let f = f.clone();
+            async move {
+                let data = message.parse_data::<T>()?;
+                f(message, data).await
+            }
+        })
+    }
+
+    /// Registers a handler receiving a `HandlerContext` alongside the message.
+    ///
+    /// The context is bound when the owning `StreamHandler` starts; until then the handler
+    /// fails with an internal error.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI for which the handler is registered.
+    /// * `f` - The handler function receiving the message and the handler context.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Self` - The updated `RouteRegistry` instance.
+    pub fn register_with_ctx<F, Fut>(&mut self, uri: &str, f: F) -> &mut Self
+    where
+        F: Fn(ParsedMessage, HandlerContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<HandlerResult, KafkaError>> + Send + 'static,
+    {
+        let f = Arc::new(f);
+        let context = self.context.clone();
+        self.register(uri, move |message: ParsedMessage| {
+            let f = f.clone();
+            let context = context.lock().ok().and_then(|context| context.clone());
+            async move {
+                let context = context.ok_or_else(|| {
+                    KafkaError::InternalServerError("handler context is not bound".to_string())
+                })?;
+                f(message, context).await
+            }
+        })
+    }
+
+    /// Binds the context passed to handlers registered with `register_with_ctx`.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The handler context to bind.
+    pub fn bind_context(&self, context: HandlerContext) {
+        if let Ok(mut bound) = self.context.lock() {
+            *bound = Some(context);
+        } else {
+            error!("Failed to acquire lock for handler context");
+        }
+    }
+
+    /// Removes the handler registered for a URI or URI template, together with its limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI or URI template, exactly as registered.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, KafkaError>` - Returns true if a handler was removed, false if none was
+    ///   registered for `uri`.
+    pub fn unregister(&mut self, uri: &str) -> Result<bool, KafkaError> {
+        let removed = if RoutePattern::parse(uri).is_some() {
+            let mut patterns = self.patterns.lock().map_err(|_| {
+                KafkaError::InternalServerError("Failed to acquire lock".to_string())
+            })?;
+            let count = patterns.len();
+            patterns.retain(|(existing, _)| existing.template() != uri);
+            patterns.len() != count
+        } else {
+            let mut routes = self.routes.lock().map_err(|_| {
+                KafkaError::InternalServerError("Failed to acquire lock".to_string())
+            })?;
+            routes.remove(uri).is_some()
+        };
+
+        self.limits
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?
+            .remove(uri);
+
+        if removed {
+            info!("unregistered handler for uri: {}", uri);
+        }
+        Ok(removed)
+    }
+
+    /// Removes every registered handler and limit; the fallback and batch routes are kept.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Returns Ok once the routes are removed, or an error if a lock
+    ///   cannot be acquired.
+    pub fn clear(&mut self) -> Result<(), KafkaError> {
+        let mut routes = self
+            .routes
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
+        let mut patterns = self
+            .patterns
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
+        let count = routes.len() + patterns.len();
+        routes.clear();
+        patterns.clear();
+        self.limits
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?
+            .clear();
+
+        info!("cleared {} registered handlers", count);
+        Ok(())
+    }
+
+    /// Sets a fallback handler invoked for messages whose URI has no registered handler.
+    ///
+    /// Without a fallback, such messages are answered with a `UriNotFound` response.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The handler function to use as fallback.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Self` - The updated `RouteRegistry` instance.
+    pub fn set_fallback<F, Fut>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(ParsedMessage) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<HandlerResult, KafkaError>> + Send + 'static,
+    {
+        let handler = Self::into_handler(f);
+
+        if let Ok(mut fallback) = self.fallback.lock() {
+            *fallback = Some(handler);
+            info!("registered fallback handler");
+        } else {
+            error!("Failed to acquire lock for fallback");
+        }
+
+        self
+    }
+
+    /// Wraps a handler function into a `MessageHandler`.
+    fn into_handler<F, Fut>(f: F) -> MessageHandler
+    where
+        F: Fn(ParsedMessage) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<HandlerResult, KafkaError>> + Send + 'static,
+    {
+        Arc::new(move |msg: &ParsedMessage| {
             let fut = f(msg.clone());
             Box::pin(fut)
                 as std::pin::Pin<
                     Box<dyn std::future::Future<Output = Result<HandlerResult, KafkaError>> + Send>,
                 >
+        })
+    }
+
+    /// Registers a batch handler for a specific URI.
+    ///
+    /// Messages for the URI are accumulated until `config.batch_size` messages are collected
+    /// or `config.batch_timeout` elapses, then passed to the handler together. The offsets of
+    /// the whole batch are committed once the handler succeeds, or once `config.failure_policy`
+    /// gives up on the batch. Batch routes never send responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI for which the batch handler is registered.
+    /// * `config` - The batch size and timeout.
+    /// * `f` - The batch handler function to register.
+    ///
+    /// # Returns
+    ///
+    /// * `&mut Self` - The updated `RouteRegistry` instance.
+    pub fn register_batch<F, Fut>(&mut self, uri: &str, config: BatchConfig, f: F) -> &mut Self
+    where
+        F: Fn(Vec<ParsedMessage>) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<(), KafkaError>> + Send + 'static,
+    {
+        let handler: BatchHandler = Arc::new(move |messages: Vec<ParsedMessage>| {
+            Box::pin(f(messages))
+                as std::pin::Pin<
+                    Box<dyn std::future::Future<Output = Result<(), KafkaError>> + Send>,
+                >
         });
 
-        if let Ok(mut routes) = self.routes.lock() {
-            routes.insert(uri.to_string(), handler);
-            info!("registered handler for uri: {}", uri);
+        if let Ok(mut batch_routes) = self.batch_routes.lock() {
+            batch_routes.insert(uri.to_string(), BatchRoute { config, handler });
+            info!(
+                "registered batch handler for uri: {} (size: {}, timeout: {:?})",
+                uri, config.batch_size, config.batch_timeout
+            );
         } else {
-            error!("Failed to acquire lock for routes");
+            error!("Failed to acquire lock for batch routes");
         }
 
         self
     }
 
+    /// Retrieves all registered batch routes.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<HashMap<String, BatchRoute>, KafkaError>` - The batch routes keyed by URI.
+    pub fn get_batch_routes(&self) -> Result<HashMap<String, BatchRoute>, KafkaError> {
+        let batch_routes = self
+            .batch_routes
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
+        Ok(batch_routes.clone())
+    }
+
     /// Checks if a handler is registered for a specific URI.
     ///
     /// # Arguments
@@ -106,7 +473,12 @@ impl RouteRegistry {
             .routes
             .lock()
             .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
-        Ok(routes.contains_key(uri))
+        if routes.contains_key(uri) {
+            return Ok(true);
+        }
+        drop(routes);
+
+        Ok(self.resolve(uri)?.is_some())
     }
 
     /// Retrieves all registered URIs.
@@ -119,7 +491,86 @@ impl RouteRegistry {
             .routes
             .lock()
             .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
-        Ok(routes.keys().cloned().collect())
+        let mut uris: Vec<String> = routes.keys().cloned().collect();
+        drop(routes);
+
+        let patterns = self
+            .patterns
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
+        uris.extend(
+            patterns
+                .iter()
+                .map(|(pattern, _)| pattern.template().to_string()),
+        );
+        Ok(uris)
+    }
+
+    /// Exports the registered URIs, sorted and deduplicated, for comparison against a manifest.
+    ///
+    /// Templates are exported as registered, e.g. `/users/{id}`; batch routes are not included.
+    ///
+    /// # Returns
+    ///
+    /// * `BTreeSet<String>` - The registered URIs, or an empty set if the registry lock is poisoned.
+    pub fn export_uris(&self) -> BTreeSet<String> {
+        self.get_registered_uris()
+            .unwrap_or_default()
+            .into_iter()
+            .collect()
+    }
+
+    /// Compares the registered URIs against a manifest of expected URIs.
+    ///
+    /// URIs are compared literally, so a template only matches the same template in the manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - The URIs the service is expected to handle.
+    ///
+    /// # Returns
+    ///
+    /// * `RouteDiff` - The manifest URIs that are not registered and the registered URIs that
+    ///   are not in the manifest.
+    pub fn diff_against(&self, manifest: &BTreeSet<String>) -> RouteDiff {
+        let registered = self.export_uris();
+
+        RouteDiff {
+            missing: manifest.difference(&registered).cloned().collect(),
+            extra: registered.difference(manifest).cloned().collect(),
+        }
+    }
+
+    /// Formats the registered URIs as a sorted tree grouped by path segment.
+    ///
+    /// Segments shared by several URIs become a parent line and chains of segments with a single
+    /// child are joined, so `/api/v1/login` and `/api/v1/register` are listed as:
+    ///
+    /// ```text
+    /// /api/v1
+    ///   /login
+    ///   /register
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The tree, one URI segment group per line, or an empty string if no routes are registered.
+    pub fn format_tree(&self) -> String {
+        let mut root = RouteTreeNode::default();
+        for uri in self.get_registered_uris().unwrap_or_default() {
+            let mut node = &mut root;
+            for segment in uri.split('/').filter(|segment| !segment.is_empty()) {
+                node = node.children.entry(segment.to_string()).or_default();
+            }
+            node.is_route = true;
+        }
+
+        let mut lines = Vec::new();
+        if root.is_route {
+            lines.push("/".to_string());
+        }
+        root.format_children(0, &mut lines);
+        lines.join("\n")
     }
 
     /// Retrieves the handler for a specific URI, if it exists.
@@ -132,11 +583,136 @@ impl RouteRegistry {
     ///
     /// * `Result<Option<MessageHandler>, KafkaError>` - The handler if it exists, or None.
     pub fn get_handler(&self, uri: &str) -> Result<Option<MessageHandler>, KafkaError> {
+        Ok(self.resolve(uri)?.map(|(handler, _)| handler))
+    }
+
+    /// Finds the handler for a URI together with the path parameters captured by its template.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The message URI.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<ResolvedRoute>, KafkaError>` - The handler and path parameters (empty
+    ///   for an exact match), or None if no route matches.
+    pub fn resolve(&self, uri: &str) -> Result<Option<ResolvedRoute>, KafkaError> {
+        Ok(self.resolve_key(uri)?.map(|(_, route)| route))
+    }
+
+    /// Finds the handler for a URI together with its path parameters and concurrency limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The message URI.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<LimitedRoute>, KafkaError>` - The matched route and the semaphore
+    ///   limiting it (None for a route registered without a limit), or None if no route matches.
+    pub fn resolve_with_limit(&self, uri: &str) -> Result<Option<LimitedRoute>, KafkaError> {
+        let Some((key, route)) = self.resolve_key(uri)? else {
+            return Ok(None);
+        };
+
+        let limits = self
+            .limits
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
+        Ok(Some((route, limits.get(&key).cloned())))
+    }
+
+    /// Finds the route matching a URI together with the URI or template it was registered under.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The message URI.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<(String, ResolvedRoute)>, KafkaError>` - The registered URI or template
+    ///   and the matched route, or None if no route matches.
+    fn resolve_key(&self, uri: &str) -> Result<Option<(String, ResolvedRoute)>, KafkaError> {
         let routes = self
             .routes
             .lock()
             .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
-        Ok(routes.get(uri).cloned())
+        if let Some(handler) = routes.get(uri) {
+            return Ok(Some((uri.to_string(), (handler.clone(), HashMap::new()))));
+        }
+        drop(routes);
+
+        let patterns = self
+            .patterns
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
+
+        let mut best: Option<((bool, usize), &RoutePattern, ResolvedRoute)> = None;
+        for (pattern, handler) in patterns.iter() {
+            let Some(params) = pattern.matches(uri) else {
+                continue;
+            };
+            let rank = (!pattern.is_catch_all(), pattern.specificity());
+            if best.as_ref().is_none_or(|(current, _, _)| rank > *current) {
+                best = Some((rank, pattern, (handler.clone(), params)));
+            }
+        }
+
+        Ok(best.map(|(_, pattern, route)| (pattern.template().to_string(), route)))
+    }
+
+    /// Retrieves the handler for a URI together with the captured path parameters.
+    ///
+    /// Same as `resolve`.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The message URI.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<ResolvedRoute>, KafkaError>` - The handler and path parameters (empty
+    ///   for an exact match), or None if no route matches.
+    pub fn get_handler_with_params(&self, uri: &str) -> Result<Option<ResolvedRoute>, KafkaError> {
+        self.resolve(uri)
+    }
+
+    /// Retrieves the fallback handler, if one is set.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<MessageHandler>, KafkaError>` - The fallback handler if it exists, or None.
+    pub fn get_fallback(&self) -> Result<Option<MessageHandler>, KafkaError> {
+        let fallback = self
+            .fallback
+            .lock()
+            .map_err(|_| KafkaError::InternalServerError("Failed to acquire lock".to_string()))?;
+        Ok(fallback.clone())
+    }
+}
+
+/// A path segment of the route tree built by `RouteRegistry::format_tree`.
+#[derive(Default)]
+struct RouteTreeNode {
+    is_route: bool,
+    children: BTreeMap<String, RouteTreeNode>,
+}
+
+impl RouteTreeNode {
+    fn format_children(&self, depth: usize, lines: &mut Vec<String>) {
+        for (segment, child) in &self.children {
+            let mut label = format!("/{}", segment);
+            let mut node = child;
+            while !node.is_route && node.children.len() == 1 {
+                let (segment, next) = node.children.iter().next().unwrap();
+                label.push('/');
+                label.push_str(segment);
+                node = next;
+            }
+
+            lines.push(format!("{}{}", "  ".repeat(depth), label));
+            node.format_children(depth + 1, lines);
+        }
     }
 }
 
@@ -150,3 +726,321 @@ impl Default for RouteRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::{testing::sample_message, CATCH_ALL_PARAM};
+
+    #[derive(serde::Deserialize)]
+    struct Order {
+        id: u64,
+    }
+
+    #[tokio::test]
+    async fn test_register_typed_passes_typed_data() {
+        let mut registry = RouteRegistry::new();
+        registry.register_typed("/api/v1/orders", |_, order: Order| async move {
+            Ok(HandlerResult::Response(
+                serde_json::json!({ "id": order.id }),
+            ))
+        });
+
+        let handler = registry.get_handler("/api/v1/orders").unwrap().unwrap();
+        let mut message = sample_message();
+        message.data = serde_json::json!({ "id": 7 });
+
+        match handler(&message).await.unwrap() {
+            HandlerResult::Response(response) => {
+                assert_eq!(response, serde_json::json!({ "id": 7 }))
+            }
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_typed_reports_field_path() {
+        let mut registry = RouteRegistry::new();
+        registry.register_typed("/api/v1/orders", |_, _: Order| async move {
+            Ok(HandlerResult::Acknowledge)
+        });
+
+        let handler = registry.get_handler("/api/v1/orders").unwrap().unwrap();
+        let mut message = sample_message();
+        message.data = serde_json::json!({ "id": "seven" });
+
+        let error = handler(&message).await.err().unwrap();
+        assert!(matches!(
+            error,
+            KafkaError::ValidationError { ref path, .. } if path == "id"
+        ));
+    }
+
+    struct CountingHandler {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl RouteHandler for CountingHandler {
+        async fn handle(&self, msg: &ParsedMessage) -> Result<HandlerResult, KafkaError> {
+            let calls = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Ok(HandlerResult::Response(serde_json::json!({
+                "uri": msg.uri,
+                "calls": calls,
+            })))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_handler_invokes_trait_object() {
+        let counting = Arc::new(CountingHandler {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let mut registry = RouteRegistry::new();
+        registry.register_handler("/api/v1/orders", counting.clone());
+
+        let handler = registry.get_handler("/api/v1/orders").unwrap().unwrap();
+        let mut message = sample_message();
+        message.uri = "/api/v1/orders".to_string();
+        handler(&message).await.unwrap();
+        let HandlerResult::Response(response) = handler(&message).await.unwrap() else {
+            panic!("expected a response");
+        };
+
+        assert_eq!(
+            response,
+            serde_json::json!({ "uri": "/api/v1/orders", "calls": 2 })
+        );
+        assert_eq!(counting.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_named_param_route_extracts_path_params() {
+        let mut registry = RouteRegistry::new();
+        registry.register("/users/{id}", |message: ParsedMessage| async move {
+            Ok(HandlerResult::Response(serde_json::json!({
+                "id": message.path_param("id"),
+            })))
+        });
+
+        let (handler, params) = registry.resolve("/users/123").unwrap().unwrap();
+        assert_eq!(params["id"], "123");
+
+        let mut message = crate::kafka::testing::sample_message();
+        message.uri = "/users/123".to_string();
+        message.path_params = params;
+        let HandlerResult::Response(response) = handler(&message).await.unwrap() else {
+            panic!("expected a response");
+        };
+        assert_eq!(response["id"], "123");
+
+        assert!(registry.resolve("/users").unwrap().is_none());
+        assert!(registry.has_handler("/users/42").unwrap());
+        assert_eq!(registry.get_registered_uris().unwrap(), vec!["/users/{id}"]);
+    }
+
+    #[test]
+    fn test_route_precedence() {
+        let mut registry = RouteRegistry::new();
+        let respond = |name: &'static str| {
+            move |_: ParsedMessage| async move { Ok(HandlerResult::Response(serde_json::json!(name))) }
+        };
+        registry.register("/users/{id}/{action}", respond("two-params"));
+        registry.register("/users/{id}/profile", respond("one-param"));
+        registry.register("/users/me/profile", respond("exact"));
+
+        let (_, params) = registry.resolve("/users/me/profile").unwrap().unwrap();
+        assert!(params.is_empty());
+
+        let (_, params) = registry.resolve("/users/7/profile").unwrap().unwrap();
+        assert_eq!(params.len(), 1);
+        assert_eq!(params["id"], "7");
+
+        let (_, params) = registry.resolve("/users/7/delete").unwrap().unwrap();
+        assert_eq!(params["action"], "delete");
+    }
+
+    #[test]
+    fn test_colon_params_and_catch_all_precedence() {
+        let mut registry = RouteRegistry::new();
+        let respond = |name: &'static str| {
+            move |_: ParsedMessage| async move { Ok(HandlerResult::Response(serde_json::json!(name))) }
+        };
+        registry.register("/api/v1/*", respond("catch-all"));
+        registry.register("/api/v1/users/*", respond("users-catch-all"));
+        registry.register("/api/v1/users/:id/orders/:orderId", respond("named"));
+        registry.register("/api/v1/users/me/orders/latest", respond("exact"));
+
+        let (_, params) = registry
+            .get_handler_with_params("/api/v1/users/42/orders/7")
+            .unwrap()
+            .unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params["id"], "42");
+        assert_eq!(params["orderId"], "7");
+
+        let (_, params) = registry
+            .get_handler_with_params("/api/v1/users/me/orders/latest")
+            .unwrap()
+            .unwrap();
+        assert!(params.is_empty());
+
+        let (_, params) = registry
+            .get_handler_with_params("/api/v1/users/42/invoices")
+            .unwrap()
+            .unwrap();
+        assert_eq!(params[CATCH_ALL_PARAM], "42/invoices");
+
+        let (_, params) = registry
+            .get_handler_with_params("/api/v1/groups/3")
+            .unwrap()
+            .unwrap();
+        assert_eq!(params[CATCH_ALL_PARAM], "groups/3");
+
+        assert!(registry
+            .get_handler_with_params("/api/v2/users")
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_register_with_ctx_receives_producer() {
+        let published = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = RouteRegistry::new();
+        let sink = published.clone();
+        registry.register_with_ctx("/api/v1/orders", move |_, ctx: HandlerContext| {
+            let sink = sink.clone();
+            async move {
+                let event = ctx.producer.build_event(
+                    "audit",
+                    "/api/v1/order-created",
+                    serde_json::json!({ "region": ctx.meta["region"] }),
+                )?;
+                sink.lock().unwrap().push(event);
+                Ok(HandlerResult::Acknowledge)
+            }
+        });
+
+        let handler = registry.get_handler("/api/v1/orders").unwrap().unwrap();
+        assert!(handler(&sample_message()).await.is_err());
+
+        let config = crate::kafka::KafkaClientConfig::new(
+            "order-service".to_string(),
+            "localhost:9092".to_string(),
+        );
+        registry.bind_context(HandlerContext {
+            producer: Arc::new(crate::kafka::KafkaProducer::new(config).unwrap()),
+            source_id: "order-service".to_string(),
+            meta: Arc::new(HashMap::from([("region".to_string(), "eu".to_string())])),
+        });
+        handler(&sample_message()).await.unwrap();
+
+        let published = published.lock().unwrap();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].topic, "audit");
+        assert_eq!(published[0].message.source_id, "order-service");
+        assert_eq!(published[0].message.data["region"], "eu");
+    }
+
+    #[test]
+    fn test_format_tree_groups_shared_prefix() {
+        let mut registry = RouteRegistry::new();
+        let ack = |_: ParsedMessage| async move { Ok(HandlerResult::Acknowledge) };
+        registry.register("/api/v1/register", ack);
+        registry.register("/health", ack);
+        registry.register("/api/v1/login", ack);
+
+        assert_eq!(
+            registry.format_tree(),
+            ["/api/v1", "  /login", "  /register", "/health"].join("\n")
+        );
+
+        registry.register("/api/v2/users/{id}", ack);
+        assert_eq!(
+            registry.format_tree(),
+            [
+                "/api",
+                "  /v1",
+                "    /login",
+                "    /register",
+                "  /v2/users/{id}",
+                "/health",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_register_with_limit_attaches_semaphore_to_route() {
+        let mut registry = RouteRegistry::new();
+        let ack = |_: ParsedMessage| async move { Ok(HandlerResult::Acknowledge) };
+        registry.register_with_limit("/api/v1/report/{id}", 4, ack);
+        registry.register("/api/v1/event", ack);
+
+        let (_, limit) = registry
+            .resolve_with_limit("/api/v1/report/42")
+            .unwrap()
+            .unwrap();
+        assert_eq!(limit.unwrap().available_permits(), 4);
+
+        let (_, limit) = registry
+            .resolve_with_limit("/api/v1/event")
+            .unwrap()
+            .unwrap();
+        assert!(limit.is_none());
+
+        registry.register("/api/v1/report/{id}", ack);
+        let (_, limit) = registry
+            .resolve_with_limit("/api/v1/report/42")
+            .unwrap()
+            .unwrap();
+        assert!(limit.is_none());
+    }
+
+    #[test]
+    fn test_unregister_and_clear_remove_routes() {
+        let mut registry = RouteRegistry::new();
+        let ack = |_: ParsedMessage| async move { Ok(HandlerResult::Acknowledge) };
+        registry.register("/api/v1/beta", ack);
+        registry.register("/api/v1/users/{id}", ack);
+        registry.register("/health", ack);
+
+        assert!(registry.unregister("/api/v1/beta").unwrap());
+        assert!(!registry.has_handler("/api/v1/beta").unwrap());
+        assert!(!registry.unregister("/api/v1/beta").unwrap());
+
+        assert!(registry.unregister("/api/v1/users/{id}").unwrap());
+        assert!(!registry.has_handler("/api/v1/users/42").unwrap());
+        assert!(registry.has_handler("/health").unwrap());
+
+        registry.clear().unwrap();
+        assert!(!registry.has_handler("/health").unwrap());
+        assert!(registry.get_registered_uris().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_against_reports_missing_and_extra() {
+        let mut registry = RouteRegistry::new();
+        let ack = |_: ParsedMessage| async move { Ok(HandlerResult::Acknowledge) };
+        registry.register("/api/v1/login", ack);
+        registry.register("/api/v1/users/{id}", ack);
+        registry.register("/health", ack);
+
+        assert_eq!(
+            registry.export_uris().into_iter().collect::<Vec<_>>(),
+            ["/api/v1/login", "/api/v1/users/{id}", "/health"]
+        );
+
+        let manifest: BTreeSet<String> = ["/api/v1/login", "/api/v1/users/{id}", "/api/v1/logout"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let diff = registry.diff_against(&manifest);
+
+        assert_eq!(diff.missing, BTreeSet::from(["/api/v1/logout".to_string()]));
+        assert_eq!(diff.extra, BTreeSet::from(["/health".to_string()]));
+        assert!(!diff.is_empty());
+
+        assert!(registry.diff_against(&registry.export_uris()).is_empty());
+    }
+}
@@ -1,15 +1,36 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
 
 use crate::kafka::{
-    utils::utils::{create_message, extract_payload},
+    utils::utils::{create_message, extract_payload, format_duration, log_received, validate_json},
+    Acks, BatchCommitter, BatchEntry, Batcher, CompletionDeferred, ConsumerHandle,
+    ConsumerMetricsSnapshot, ContentType, DeadLetterQueue, DedupCache, HandlerContext,
     HandlerResult, KafkaClientConfig, KafkaConsumer, KafkaError, KafkaProducer, MessageType,
-    ParsedMessage, RouteRegistry,
+    ParseRetryPolicy, ParsedMessage, RedeliveryRequested, ResponseDestination, RetryPolicy,
+    RouteRegistry, SendMessage, Shutdown, StreamEnd, TrackedOffsets,
 };
-use anyhow::{anyhow, Context, Result};
-use rdkafka::{message::OwnedMessage, Message};
-use tracing::{error, info, warn};
+use anyhow::{Context, Result};
+use rdkafka::{message::OwnedMessage, Message, Timestamp};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
+
+#[cfg(feature = "prometheus")]
+use crate::kafka::PrometheusMetrics;
+
+/// Callback invoked with messages whose URI matches no route.
+pub type UnhandledCallback = Arc<dyn Fn(&ParsedMessage) + Send + Sync>;
+
+/// Hook rewriting a raw payload into the standard message envelope before it is parsed.
+pub type PreParser = Arc<dyn Fn(String) -> Result<String, KafkaError> + Send + Sync>;
 
-use crate::kafka::extensions::MessageLatency;
+/// Interceptor run on every dispatched message before its route handler; an error rejects the
+/// message.
+pub type Middleware = Arc<dyn Fn(&ParsedMessage) -> Result<(), KafkaError> + Send + Sync>;
 
 /// StreamHandler is responsible for processing Kafka messages using a route-based system.
 /// It manages the consumer and producer, and handles message routing and response.
@@ -17,7 +38,92 @@ pub struct StreamHandler {
     config: KafkaClientConfig,
     consumer: KafkaConsumer,
     producer: Arc<KafkaProducer>,
-    route_registry: RouteRegistry,
+    route_registry: Arc<RwLock<RouteRegistry>>,
+    dedup: Option<Arc<DedupCache>>,
+    parse_retry: Option<Arc<ParseRetryPolicy>>,
+    dead_letter: Option<Arc<DeadLetterQueue>>,
+    pre_parser: Option<PreParser>,
+    payload_limits: Option<(usize, usize)>,
+    on_unhandled: Option<UnhandledCallback>,
+    middleware: Vec<Middleware>,
+    unhandled_count: Arc<AtomicU64>,
+    in_flight: Arc<AtomicUsize>,
+    handler_meta: HashMap<String, String>,
+    skip_historical: bool,
+    correlation_spans: bool,
+    handler_timeout: Option<Duration>,
+    acknowledge_response: bool,
+    #[cfg(feature = "prometheus")]
+    prometheus: Option<Arc<PrometheusMetrics>>,
+}
+
+/// MessageContext holds the shared state needed to process each consumed message.
+#[derive(Clone)]
+struct MessageContext {
+    source_id: String,
+    log_payloads: bool,
+    route_registry: Arc<RwLock<RouteRegistry>>,
+    producer: Arc<KafkaProducer>,
+    batchers: Arc<HashMap<String, Batcher>>,
+    dedup: Option<Arc<DedupCache>>,
+    parse_retry: Option<Arc<ParseRetryPolicy>>,
+    dead_letter: Option<Arc<DeadLetterQueue>>,
+    pre_parser: Option<PreParser>,
+    payload_limits: Option<(usize, usize)>,
+    on_unhandled: Option<UnhandledCallback>,
+    middleware: Arc<Vec<Middleware>>,
+    unhandled_count: Arc<AtomicU64>,
+    in_flight: Arc<AtomicUsize>,
+    skip_before_ms: Option<i64>,
+    correlation_spans: bool,
+    handler_timeout: Option<Duration>,
+    acknowledge_response: bool,
+    #[cfg(feature = "prometheus")]
+    prometheus: Option<Arc<PrometheusMetrics>>,
+}
+
+/// DispatchOutcome is how a dispatched message was handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchOutcome {
+    /// The handler succeeded and its response was sent.
+    Ok,
+    /// The handler acknowledged the message.
+    Acknowledged,
+    /// The handler asked for the message to be redelivered.
+    Retry,
+    /// The handler failed; an error response was sent.
+    Error,
+    /// A middleware rejected the message before its handler ran.
+    Rejected,
+    /// No route matched the message's URI.
+    NotFound,
+}
+
+/// DispatchOptions holds the per-handler settings applied by `StreamHandler::dispatch`.
+#[derive(Clone, Copy)]
+struct DispatchOptions<'a> {
+    handler_timeout: Option<Duration>,
+    acknowledge_response: bool,
+    dead_letter: Option<(&'a DeadLetterQueue, &'a OwnedMessage)>,
+    middleware: &'a [Middleware],
+    in_flight: &'a AtomicUsize,
+}
+
+/// InFlightGuard counts a handler invocation as in flight until it is dropped, so the count is
+/// decremented even if the handler fails, times out or panics.
+struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl<'a> InFlightGuard<'a> {
+    fn new(in_flight: &'a AtomicUsize) -> Self {
+        in_flight.fetch_add(1, Ordering::Relaxed);
+        Self(in_flight)
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl StreamHandler {
@@ -56,314 +162,2006 @@ impl StreamHandler {
         let consumer = KafkaConsumer::new(config.clone(), concurrency_limit)
             .context("failed to create Kafka consumer")?;
 
-        let producer =
-            KafkaProducer::new(config.clone()).context("failed to create Kafka producer")?;
+        let producer = KafkaProducer::new(config.clone().with_default_acks(Acks::All))
+            .context("failed to create Kafka producer")?;
 
         Ok(Self {
             config,
             consumer,
             producer: Arc::new(producer),
-            route_registry,
+            route_registry: Arc::new(RwLock::new(route_registry)),
+            dedup: None,
+            parse_retry: None,
+            dead_letter: None,
+            pre_parser: None,
+            payload_limits: None,
+            on_unhandled: None,
+            middleware: Vec::new(),
+            unhandled_count: Arc::new(AtomicU64::new(0)),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            handler_meta: HashMap::new(),
+            skip_historical: false,
+            correlation_spans: true,
+            handler_timeout: None,
+            acknowledge_response: false,
+            #[cfg(feature = "prometheus")]
+            prometheus: None,
         })
     }
 
-    /// Retrieves the KafkaClientConfig associated with the StreamHandler.
+    /// Processes messages with the same Kafka key sequentially.
+    ///
+    /// See `KafkaConsumer::with_key_ordering` for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `workers` - The number of serial queues, i.e. the maximum concurrency across keys.
     ///
     /// # Returns
     ///
-    /// * `&KafkaClientConfig` - A reference to the configuration.
-    pub fn get_config(&self) -> &KafkaClientConfig {
-        &self.config
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_key_ordering(mut self, workers: usize) -> Self {
+        self.consumer = self.consumer.with_key_ordering(workers);
+        self
     }
 
-    /// Starts the StreamHandler to process messages using the registered routes.
+    /// Sets a callback invoked when the consumer's message stream ends.
+    ///
+    /// See `KafkaConsumer::with_on_stream_end` for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The function invoked with the reason the stream ended.
     ///
     /// # Returns
     ///
-    /// * `Result<tokio::task::JoinHandle<()>>` - Returns a handle to the spawned task or an error if it fails.
-    pub async fn start(&self) -> Result<tokio::task::JoinHandle<()>> {
-        let route_registry = self.route_registry.clone();
-        let producer = self.producer.clone();
-        let source_id = self.config.cluster_id.clone();
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_on_stream_end<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(StreamEnd) + Send + Sync + 'static,
+    {
+        self.consumer = self.consumer.with_on_stream_end(callback);
+        self
+    }
 
-        let consumer_task =
-            self.consumer
-                .start(move |message| {
-                    let route_registry = route_registry.clone();
-                    let producer = producer.clone();
-                    let source_id = source_id.clone();
-                    async move {
-                        Self::handle_message(message, source_id, route_registry, producer).await
-                    }
-                })
-                .await?;
+    /// Reconnects the consumer's message stream when it ends or keeps failing.
+    ///
+    /// See `KafkaConsumer::with_reconnect` for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The backoff between attempts and the maximum number of attempts.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_reconnect(mut self, policy: RetryPolicy) -> Self {
+        self.consumer = self.consumer.with_reconnect(policy);
+        self
+    }
 
-        Ok(consumer_task)
+    /// Dispatches messages round-robin across subscribed topics.
+    ///
+    /// See `KafkaConsumer::with_fair_scheduling` for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_per_topic` - The maximum number of messages of one topic processed concurrently.
+    /// * `queue_capacity` - The number of messages buffered per topic before consumption waits.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_fair_scheduling(mut self, max_per_topic: usize, queue_capacity: usize) -> Self {
+        self.consumer = self
+            .consumer
+            .with_fair_scheduling(max_per_topic, queue_capacity);
+        self
+    }
+
+    /// Retrieves the idle and busy time of the consumer loop.
+    ///
+    /// # Returns
+    ///
+    /// * `ConsumerMetricsSnapshot` - A copy of the current metrics.
+    pub fn consumer_metrics(&self) -> ConsumerMetricsSnapshot {
+        self.consumer.metrics()
     }
 
-    /// Sends a response message using the producer.
+    /// Enables deduplication of messages by `message_id`.
+    ///
+    /// Kafka delivers at least once, so a message can be received again after a rebalance.
+    /// With deduplication enabled, a message whose id was already handled within `ttl` is skipped
+    /// without invoking its handler. An id is remembered once its handler succeeds or
+    /// acknowledges, or its batch is handled, so a message that failed or asked to be retried
+    /// is handled again when redelivered. Messages with an empty `message_id` are never
+    /// deduplicated.
     ///
     /// # Arguments
     ///
-    /// * `producer` - The KafkaProducer to use for sending the message.
-    /// * `source_id` - The source identifier for the message.
-    /// * `message_id` - The unique message identifier.
-    /// * `transaction_id` - The transaction identifier for tracking.
-    /// * `topic` - The topic to which the message will be sent.
-    /// * `uri` - The URI for message routing.
-    /// * `data` - The data payload of the message.
+    /// * `capacity` - The maximum number of message ids remembered.
+    /// * `ttl` - How long a message id is remembered.
     ///
     /// # Returns
     ///
-    /// * `Result<(), KafkaError>` - Returns Ok if the message is sent successfully, or a KafkaError if it fails.
-    async fn send_response(
-        producer: Arc<KafkaProducer>,
-        source_id: String,
-        message_id: String,
-        transaction_id: String,
-        topic: String,
-        uri: String,
-        data: serde_json::Value,
-    ) -> Result<(), KafkaError> {
-        let send_message = create_message(
-            source_id,
-            message_id,
-            transaction_id,
-            topic,
-            uri,
-            data,
-            Some(MessageType::Response),
-            None,
-        );
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_deduplication(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.dedup = Some(Arc::new(DedupCache::new(capacity, ttl)));
+        self
+    }
 
-        producer
-            .send(send_message.message, &send_message.topic)
-            .await
-            .map_err(|e| {
-                KafkaError::InternalServerError(format!("failed to send response: {}", e))
-            })?;
+    /// Retries messages that fail to parse, then routes them to a dead letter topic.
+    ///
+    /// A message whose payload cannot be parsed is re-produced to its topic up to `max_retries`
+    /// times, tracked by the `x-parse-attempts` header, and then sent to `dlq_topic`. Without
+    /// this option such messages are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_retries` - The number of redeliveries before a message is dead-lettered.
+    /// * `dlq_topic` - The topic receiving messages that keep failing to parse.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_parse_retry<S: Into<String>>(mut self, max_retries: u32, dlq_topic: S) -> Self {
+        self.parse_retry = Some(Arc::new(ParseRetryPolicy::new(max_retries, dlq_topic)));
+        self
+    }
 
-        Ok(())
+    /// Forwards messages that fail to parse, are rejected by the payload limits, or whose
+    /// handler returns an error to `topic`.
+    ///
+    /// The original key and payload are kept; the error and the source topic, partition and
+    /// offset are added as `x-dlq-*` headers. Handler errors still get their error response.
+    /// When `with_parse_retry` is also set, parse failures follow the retry policy instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic receiving failed messages.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_dead_letter_topic<S: Into<String>>(mut self, topic: S) -> Self {
+        self.dead_letter = Some(Arc::new(DeadLetterQueue::new(topic)));
+        self
     }
 
-    /// Handles the response for a parsed message.
+    /// Rewrites each raw payload before it is parsed as a `ParsedMessage`.
+    ///
+    /// Lets a service consume upstreams that wrap the standard envelope, e.g. base64 encoded or
+    /// nested in a foreign envelope. The hook runs after the payload is logged and before the
+    /// payload limits are checked. A payload the hook rejects is forwarded to the dead letter
+    /// topic if one is set, otherwise skipped with an error log.
     ///
     /// # Arguments
     ///
-    /// * `producer` - The KafkaProducer to use for sending the response.
-    /// * `source_id` - The source identifier for the message.
-    /// * `parsed_message` - The parsed message to handle.
-    /// * `start_time` - The time when the message processing started.
-    /// * `response_data` - The data to include in the response.
-    /// * `log_prefix` - A prefix for logging purposes.
+    /// * `pre_parser` - The function returning the standard envelope for a raw payload.
     ///
     /// # Returns
     ///
-    /// * `Result<()>` - Returns Ok if the response is handled successfully, or an error if it fails.
-    async fn handle_response(
-        producer: Arc<KafkaProducer>,
-        source_id: String,
-        parsed_message: &ParsedMessage,
-        start_time: Instant,
-        response_data: serde_json::Value,
-        log_prefix: &str,
-    ) -> Result<()> {
-        if parsed_message.should_response() {
-            let response_destination = parsed_message.get_response_destination().unwrap();
-
-            Self::send_response(
-                producer,
-                source_id,
-                parsed_message.message_id.clone(),
-                parsed_message.transaction_id.clone(),
-                response_destination.topic.clone(),
-                response_destination.uri.clone(),
-                response_data,
-            )
-            .await?;
-        }
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_pre_parser<F>(mut self, pre_parser: F) -> Self
+    where
+        F: Fn(String) -> Result<String, KafkaError> + Send + Sync + 'static,
+    {
+        self.pre_parser = Some(Arc::new(pre_parser));
+        self
+    }
 
-        let duration = start_time.elapsed().as_millis();
-        info!(
-            "{} handle request {} - {} took: {}ms",
-            log_prefix, parsed_message.uri, parsed_message.transaction_id, duration
-        );
+    /// Rejects payloads larger than `max_bytes` or nested deeper than `max_depth` before parsing.
+    ///
+    /// Rejected messages are skipped with an error log; no response is sent since the envelope
+    /// was never parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - The maximum nesting depth of objects and arrays.
+    /// * `max_bytes` - The maximum payload size in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_payload_limits(mut self, max_depth: usize, max_bytes: usize) -> Self {
+        self.payload_limits = Some((max_depth, max_bytes));
+        self
+    }
 
-        Ok(())
+    /// Sets a callback invoked for every message whose URI matches no route.
+    ///
+    /// The not-found response is still sent; the callback is meant for monitoring misrouted
+    /// traffic, such as clients calling deprecated or misspelled URIs. It is not invoked when
+    /// a fallback route handles the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The function called with each unmatched message.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_on_unhandled<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ParsedMessage) + Send + Sync + 'static,
+    {
+        self.on_unhandled = Some(Arc::new(callback));
+        self
     }
 
-    /// Sends a response indicating that the requested URI was not found.
+    /// Adds a middleware run on every message before its route handler.
+    ///
+    /// Middleware runs in registration order, e.g. for authentication or auditing. If one
+    /// returns an error, the remaining middleware and the handler are skipped and the error is
+    /// sent back as the response. Batched messages are not passed through middleware.
     ///
     /// # Arguments
     ///
-    /// * `producer` - The KafkaProducer to use for sending the response.
-    /// * `parsed_message` - The parsed message to handle.
-    /// * `source_id` - The source identifier for the message.
-    /// * `start_time` - The time when the message processing started.
+    /// * `middleware` - The interceptor to add.
     ///
     /// # Returns
     ///
-    /// * `Result<()>` - Returns Ok if the response is sent successfully, or an error if it fails.
-    async fn send_not_found_uri_response(
-        producer: Arc<KafkaProducer>,
-        parsed_message: &ParsedMessage,
-        source_id: String,
-        start_time: Instant,
-    ) -> Result<()> {
-        warn!("no handler found for uri: {}", parsed_message.uri);
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_middleware<F>(mut self, middleware: F) -> Self
+    where
+        F: Fn(&ParsedMessage) -> Result<(), KafkaError> + Send + Sync + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
 
-        Self::handle_response(
-            producer,
-            source_id,
-            parsed_message,
-            start_time,
-            KafkaError::UriNotFound(parsed_message.uri.clone()).to_response_value(),
-            "1.",
-        )
-        .await
+    /// Retrieves the number of messages whose URI matched no route.
+    pub fn unhandled_count(&self) -> u64 {
+        self.unhandled_count.load(Ordering::Relaxed)
     }
 
-    /// Handles an error response for a parsed message.
+    /// Retrieves the number of handlers currently executing.
+    ///
+    /// Messages waiting for a route's concurrency limit are not counted, which makes the value
+    /// usable as an autoscaling signal next to the consumer lag.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Skips messages created before the handler was started.
+    ///
+    /// The startup time is recorded in `start()`; messages whose `CreateTime` timestamp precedes
+    /// it are committed without being handled. This gives "process only new messages" semantics
+    /// without changing `auto.offset.reset`. Messages without a `CreateTime` are always handled.
     ///
     /// # Arguments
     ///
-    /// * `producer` - The KafkaProducer to use for sending the response.
-    /// * `parsed_message` - The parsed message to handle.
-    /// * `source_id` - The source identifier for the message.
-    /// * `start_time` - The time when the message processing started.
-    /// * `error` - The error to include in the response.
+    /// * `skip_historical` - Whether to skip messages created before startup.
     ///
     /// # Returns
     ///
-    /// * `Result<()>` - Returns Ok if the response is handled successfully, or an error if it fails.
-    async fn handle_response_error(
-        producer: Arc<KafkaProducer>,
-        parsed_message: &ParsedMessage,
-        source_id: String,
-        start_time: Instant,
-        error: KafkaError,
-    ) -> Result<()> {
-        Self::handle_response(
-            producer,
-            source_id,
-            parsed_message,
-            start_time,
-            error.to_response_value(),
-            "3.",
-        )
-        .await
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_skip_historical(mut self, skip_historical: bool) -> Self {
+        self.skip_historical = skip_historical;
+        self
     }
 
-    /// Handles a successful response for a parsed message.
+    /// Bounds how long a handler may run before the message is answered with a timeout error.
+    ///
+    /// A handler awaiting a dead downstream would otherwise hold its concurrency slot forever.
+    /// On timeout the handler future is dropped, and a `KafkaError::TimeoutError` response is
+    /// logged and sent. Handlers run without a timeout by default.
     ///
     /// # Arguments
     ///
-    /// * `producer` - The KafkaProducer to use for sending the response.
-    /// * `parsed_message` - The parsed message to handle.
-    /// * `source_id` - The source identifier for the message.
-    /// * `start_time` - The time when the message processing started.
-    /// * `response` - The response data to include.
+    /// * `timeout` - The maximum time a handler may run.
     ///
     /// # Returns
     ///
-    /// * `Result<()>` - Returns Ok if the response is handled successfully, or an error if it fails.
-    async fn handle_response_ok(
-        producer: Arc<KafkaProducer>,
-        parsed_message: &ParsedMessage,
-        source_id: String,
-        start_time: Instant,
-        response: serde_json::Value,
-    ) -> Result<()> {
-        Self::handle_response(
-            producer,
-            source_id,
-            parsed_message,
-            start_time,
-            serde_json::json!({
-                "data": response
-            }),
-            "4.",
-        )
-        .await
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_handler_timeout(mut self, timeout: Duration) -> Self {
+        self.handler_timeout = Some(timeout);
+        self
     }
 
-    /// Handles an incoming Kafka message by routing it to the appropriate handler.
+    /// Sets whether `HandlerResult::Acknowledge` is answered when the message has a response destination.
+    ///
+    /// A caller using `RequestSender::send_request_async` waits for a response, so without one it
+    /// would time out against an acknowledging handler. When enabled, such callers receive a
+    /// lightweight response with `{"acknowledged": true}` as data. Disabled by default, so an
+    /// acknowledging handler sends nothing, as before. Messages without a response destination are
+    /// never answered.
     ///
     /// # Arguments
     ///
-    /// * `message` - The Kafka message to handle.
-    /// * `source_id` - The source identifier for the message.
-    /// * `route_registry` - The registry of routes for message handling.
-    /// * `producer` - The KafkaProducer to use for sending responses.
+    /// * `enabled` - Whether to send acknowledge responses.
     ///
     /// # Returns
     ///
-    /// * `Result<()>` - Returns Ok if the message is handled successfully, or an error if it fails.
-    async fn handle_message(
-        message: OwnedMessage,
-        source_id: String,
-        route_registry: RouteRegistry,
-        producer: Arc<KafkaProducer>,
-    ) -> Result<()> {
-        let start_time = Instant::now();
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_acknowledge_response(mut self, enabled: bool) -> Self {
+        self.acknowledge_response = enabled;
+        self
+    }
 
-        let payload = extract_payload(&message).ok_or_else(|| anyhow!("message has no payload"))?;
+    /// Sets whether messages are handled inside a span carrying their `transaction_id` and `uri`.
+    ///
+    /// Enabled by default, so every log emitted while handling a message, by the consumer or by
+    /// its handler, is correlated with the message without the handler creating spans itself.
+    /// The fields are recorded once the message is parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to enter the span while handling each message.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_correlation_spans(mut self, enabled: bool) -> Self {
+        self.correlation_spans = enabled;
+        self
+    }
 
-        let latency = message.get_latency();
+    /// Adds a metadata entry exposed to context handlers through `HandlerContext::meta`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The metadata key.
+    /// * `value` - The metadata value.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_handler_meta<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.handler_meta.insert(key.into(), value.into());
+        self
+    }
 
-        info!(
-            "received message: '{}' from topic {}, latency: {}ms",
-            payload,
-            message.topic(),
-            latency,
-        );
+    /// Sets the retry bounds for sending responses.
+    ///
+    /// Transient producer failures (broker unavailable, queue full, timeouts) are retried with
+    /// exponential backoff before the response is given up on. Defaults to `RetryPolicy::default()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The retry policy for response sends.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    pub fn with_response_retry(mut self, policy: RetryPolicy) -> Self {
+        self.producer = Arc::new((*self.producer).clone().with_retry_policy(policy));
+        self
+    }
 
-        let parsed_message = ParsedMessage::parse_from_string(&payload)
-            .context("failed to parse message from kafka payload")?;
+    /// Records per-route Prometheus metrics for every handled message.
+    ///
+    /// See `PrometheusMetrics` for the registered metrics; serve `metrics.gather_metrics()` on
+    /// `/metrics` to expose them.
+    ///
+    /// # Arguments
+    ///
+    /// * `metrics` - The metrics to record into.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The updated StreamHandler instance.
+    #[cfg(feature = "prometheus")]
+    pub fn with_prometheus(mut self, metrics: Arc<PrometheusMetrics>) -> Self {
+        self.prometheus = Some(metrics);
+        self
+    }
 
-        let handler = route_registry.get_handler(&parsed_message.uri)?;
+    /// Retrieves the KafkaClientConfig associated with the StreamHandler.
+    ///
+    /// # Returns
+    ///
+    /// * `&KafkaClientConfig` - A reference to the configuration.
+    pub fn get_config(&self) -> &KafkaClientConfig {
+        &self.config
+    }
 
-        if let Some(handler) = handler {
-            match handler(&parsed_message).await {
+    /// Starts the StreamHandler to process messages using the registered routes.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ConsumerHandle>` - Returns a handle to the spawned task or an error if it fails.
+    pub async fn start(&self) -> Result<ConsumerHandle> {
+        let context = self.message_context()?;
+
+        let consumer_task = self
+            .consumer
+            .start(move |message| {
+                let context = context.clone();
+                async move { Self::handle_message(message, context).await }
+            })
+            .await?;
+
+        Ok(consumer_task)
+    }
+
+    /// Stops the processing task returned by `start`.
+    ///
+    /// See `KafkaConsumer::stop` for how each `ShutdownMode` treats in-flight messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The handle returned by `start`.
+    /// * `shutdown` - How in-flight work is treated.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all in-flight work completed, false if the task was aborted.
+    pub async fn stop(&self, task: ConsumerHandle, shutdown: Shutdown) -> bool {
+        self.consumer.stop(task, shutdown).await
+    }
+
+    /// Shuts the handler down gracefully: stops taking new messages, waits for in-flight
+    /// handlers to complete, then unsubscribes the consumer.
+    pub async fn shutdown(&self) {
+        self.consumer.shutdown().await
+    }
+
+    /// Replaces the route registry used for messages received from now on.
+    ///
+    /// Messages already being dispatched finish with the previous registry. Batch routes are
+    /// bound when the handler starts, so batch routes of the new registry are not batched.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_registry` - The registry replacing the active one.
+    pub fn swap_routes(&self, new_registry: RouteRegistry) {
+        new_registry.bind_context(self.handler_context());
+
+        match self.route_registry.write() {
+            Ok(mut route_registry) => {
+                *route_registry = new_registry;
+                info!("swapped route registry");
+            }
+            Err(_) => error!("Failed to acquire lock for route registry"),
+        }
+    }
+
+    /// Builds the context passed to handlers registered with `register_with_ctx`.
+    fn handler_context(&self) -> HandlerContext {
+        HandlerContext {
+            producer: self.producer.clone(),
+            source_id: self.config.cluster_id.clone(),
+            meta: Arc::new(self.handler_meta.clone()),
+        }
+    }
+
+    /// Binds the handler context and builds the state shared by all consumed messages.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<MessageContext>` - The message context, or an error if the routes cannot be read.
+    fn message_context(&self) -> Result<MessageContext> {
+        self.registry()?.bind_context(self.handler_context());
+
+        Ok(MessageContext {
+            source_id: self.config.cluster_id.clone(),
+            log_payloads: self.config.log_payloads,
+            route_registry: self.route_registry.clone(),
+            producer: self.producer.clone(),
+            batchers: Arc::new(Self::spawn_batchers(
+                &self.registry()?,
+                &self.consumer.tracked_offsets(),
+                self.dedup.clone(),
+            )?),
+            dedup: self.dedup.clone(),
+            parse_retry: self.parse_retry.clone(),
+            dead_letter: self.dead_letter.clone(),
+            pre_parser: self.pre_parser.clone(),
+            payload_limits: self.payload_limits,
+            on_unhandled: self.on_unhandled.clone(),
+            middleware: Arc::new(self.middleware.clone()),
+            unhandled_count: self.unhandled_count.clone(),
+            in_flight: self.in_flight.clone(),
+            skip_before_ms: self
+                .skip_historical
+                .then(|| chrono::Utc::now().timestamp_millis()),
+            correlation_spans: self.correlation_spans,
+            handler_timeout: self.handler_timeout,
+            acknowledge_response: self.acknowledge_response,
+            #[cfg(feature = "prometheus")]
+            prometheus: self.prometheus.clone(),
+        })
+    }
+
+    /// Retrieves the active route registry.
+    fn registry(&self) -> Result<RouteRegistry> {
+        Self::read_registry(&self.route_registry)
+    }
+
+    /// Retrieves the registry currently behind a swappable route registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `route_registry` - The swappable route registry.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<RouteRegistry>` - The active registry, or an error if the lock is poisoned.
+    fn read_registry(route_registry: &RwLock<RouteRegistry>) -> Result<RouteRegistry> {
+        route_registry
+            .read()
+            .map(|route_registry| route_registry.clone())
+            .map_err(|_| anyhow::anyhow!("failed to acquire lock for route registry"))
+    }
+
+    /// Spawns a batcher for every batch route in the registry.
+    ///
+    /// Batched messages stay in flight in `offsets` until their batch is handled successfully.
+    ///
+    /// # Arguments
+    ///
+    /// * `route_registry` - The registry holding the batch routes.
+    /// * `offsets` - The offsets of the consumed messages, completed as batches succeed.
+    /// * `dedup` - Remembers the message ids of successful batches, None without deduplication.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<HashMap<String, Batcher>>` - The batchers keyed by URI.
+    fn spawn_batchers(
+        route_registry: &RouteRegistry,
+        offsets: &TrackedOffsets,
+        dedup: Option<Arc<DedupCache>>,
+    ) -> Result<HashMap<String, Batcher>> {
+        let mut batchers = HashMap::new();
+
+        for (uri, route) in route_registry.get_batch_routes()? {
+            let offsets = offsets.clone();
+            let dedup = dedup.clone();
+            let committer: BatchCommitter = Arc::new(move |entries: &[BatchEntry], handled| {
+                for entry in entries {
+                    if let (true, Some(dedup)) = (handled, &dedup) {
+                        if !entry.message.message_id.is_empty() {
+                            dedup.insert(&entry.message.message_id);
+                        }
+                    }
+                    offsets.complete(&entry.topic, entry.partition, entry.offset);
+                }
+            });
+            let (batcher, _) = Batcher::spawn(uri.clone(), route, committer);
+            batchers.insert(uri, batcher);
+        }
+
+        Ok(batchers)
+    }
+
+    /// Builds the response message for a parsed message.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_id` - The source identifier for the response.
+    /// * `parsed_message` - The parsed message to respond to.
+    /// * `data` - The data payload of the response.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<SendMessage>` - The response and its topic, or None if no response should be sent.
+    fn response_message(
+        source_id: String,
+        parsed_message: &ParsedMessage,
+        data: serde_json::Value,
+    ) -> Option<SendMessage> {
+        let response_destination = Self::response_destination(parsed_message)?;
+
+        Some(create_message(
+            source_id,
+            parsed_message.message_id.clone(),
+            parsed_message.transaction_id.clone(),
+            response_destination.topic.clone(),
+            response_destination.uri.clone(),
+            data,
+            Some(MessageType::Response),
+            None,
+        ))
+    }
+
+    /// The response data sent for `HandlerResult::Acknowledge` when acknowledge responses are enabled.
+    fn acknowledged_data() -> serde_json::Value {
+        serde_json::json!({
+            "data": { "acknowledged": true }
+        })
+    }
+
+    /// Retrieves where to send the response for a parsed message.
+    ///
+    /// A destination with a URI but no topic is malformed: no response can be sent, so the
+    /// caller would wait until it times out. It is logged as a warning instead of skipped silently.
+    ///
+    /// # Arguments
+    ///
+    /// * `parsed_message` - The parsed message to respond to.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&ResponseDestination>` - The destination, or None if no response should be sent.
+    fn response_destination(parsed_message: &ParsedMessage) -> Option<&ResponseDestination> {
+        let destination = parsed_message.get_response_destination()?;
+
+        if destination.topic.is_empty() && !destination.uri.is_empty() {
+            warn!(
+                "response destination of request {} - {} has uri {} but no topic, the caller will not receive a response",
+                parsed_message.uri, parsed_message.transaction_id, destination.uri
+            );
+            return None;
+        }
+
+        destination.should_response().then_some(destination)
+    }
+
+    /// Handles the response for a parsed message.
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The KafkaProducer to use for sending the response.
+    /// * `source_id` - The source identifier for the message.
+    /// * `parsed_message` - The parsed message to handle.
+    /// * `start_time` - The time when the message processing started.
+    /// * `response_data` - The data to include in the response.
+    /// * `log_prefix` - A prefix for logging purposes.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the response is handled successfully, or an error if it fails.
+    async fn handle_response(
+        producer: Arc<KafkaProducer>,
+        source_id: String,
+        parsed_message: &ParsedMessage,
+        start_time: Instant,
+        response_data: serde_json::Value,
+        log_prefix: &str,
+    ) -> Result<()> {
+        if let Some(send_message) = Self::response_message(source_id, parsed_message, response_data)
+        {
+            producer
+                .send_response(&send_message.message, &send_message.topic)
+                .await
+                .map_err(|e| {
+                    KafkaError::InternalServerError(format!("failed to send response: {}", e))
+                })?;
+        }
+
+        let duration = format_duration(start_time.elapsed().as_millis() as i64);
+        info!(
+            "{} handle request {} - {} took: {}",
+            log_prefix, parsed_message.uri, parsed_message.transaction_id, duration
+        );
+
+        Ok(())
+    }
+
+    /// Sends a response indicating that the requested URI was not found.
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The KafkaProducer to use for sending the response.
+    /// * `parsed_message` - The parsed message to handle.
+    /// * `source_id` - The source identifier for the message.
+    /// * `start_time` - The time when the message processing started.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the response is sent successfully, or an error if it fails.
+    async fn send_not_found_uri_response(
+        producer: Arc<KafkaProducer>,
+        parsed_message: &ParsedMessage,
+        source_id: String,
+        start_time: Instant,
+    ) -> Result<()> {
+        warn!("no handler found for uri: {}", parsed_message.uri);
+
+        Self::handle_response(
+            producer,
+            source_id,
+            parsed_message,
+            start_time,
+            KafkaError::UriNotFound(parsed_message.uri.clone()).to_response_value(),
+            "1.",
+        )
+        .await
+    }
+
+    /// Handles an error response for a parsed message.
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The KafkaProducer to use for sending the response.
+    /// * `parsed_message` - The parsed message to handle.
+    /// * `source_id` - The source identifier for the message.
+    /// * `start_time` - The time when the message processing started.
+    /// * `error` - The error to include in the response.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the response is handled successfully, or an error if it fails.
+    async fn handle_response_error(
+        producer: Arc<KafkaProducer>,
+        parsed_message: &ParsedMessage,
+        source_id: String,
+        start_time: Instant,
+        error: KafkaError,
+    ) -> Result<()> {
+        Self::handle_response(
+            producer,
+            source_id,
+            parsed_message,
+            start_time,
+            error.to_response_value(),
+            "3.",
+        )
+        .await
+    }
+
+    /// Handles a successful response for a parsed message.
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The KafkaProducer to use for sending the response.
+    /// * `parsed_message` - The parsed message to handle.
+    /// * `source_id` - The source identifier for the message.
+    /// * `start_time` - The time when the message processing started.
+    /// * `response` - The response data to include.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the response is handled successfully, or an error if it fails.
+    async fn handle_response_ok(
+        producer: Arc<KafkaProducer>,
+        parsed_message: &ParsedMessage,
+        source_id: String,
+        start_time: Instant,
+        response: serde_json::Value,
+    ) -> Result<()> {
+        Self::handle_response(
+            producer,
+            source_id,
+            parsed_message,
+            start_time,
+            serde_json::json!({
+                "data": response
+            }),
+            "4.",
+        )
+        .await
+    }
+
+    /// Handles a binary response for a parsed message.
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The KafkaProducer to use for sending the response.
+    /// * `parsed_message` - The parsed message to handle.
+    /// * `source_id` - The source identifier for the message.
+    /// * `start_time` - The time when the message processing started.
+    /// * `bytes` - The raw response payload.
+    /// * `content_type` - The media type of the payload.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the response is handled successfully, or an error if it fails.
+    async fn handle_response_bytes(
+        producer: Arc<KafkaProducer>,
+        parsed_message: &ParsedMessage,
+        source_id: String,
+        start_time: Instant,
+        bytes: Vec<u8>,
+        content_type: ContentType,
+    ) -> Result<()> {
+        if let Some(send_message) =
+            Self::response_message(source_id, parsed_message, serde_json::Value::Null)
+        {
+            producer
+                .send_bytes_response(
+                    &send_message.message,
+                    &send_message.topic,
+                    &bytes,
+                    &content_type,
+                )
+                .await
+                .map_err(|e| {
+                    KafkaError::InternalServerError(format!("failed to send response: {}", e))
+                })?;
+        }
+
+        let duration = format_duration(start_time.elapsed().as_millis() as i64);
+        info!(
+            "4. handle request {} - {} took: {}",
+            parsed_message.uri, parsed_message.transaction_id, duration
+        );
+
+        Ok(())
+    }
+
+    /// Handles an incoming Kafka message by routing it to the appropriate handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The Kafka message to handle.
+    /// * `context` - The shared state (routes, producer, batchers, ...) used for processing.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns Ok if the message is handled successfully, `CompletionDeferred` if
+    ///   it was added to a batch, or an error if it fails.
+    async fn handle_message(message: OwnedMessage, context: MessageContext) -> Result<()> {
+        // The message's fields are recorded once it is parsed
+        let span = if context.correlation_spans {
+            info_span!(
+                "message",
+                transaction_id = tracing::field::Empty,
+                uri = tracing::field::Empty
+            )
+        } else {
+            Span::none()
+        };
+
+        Self::process_message(message, context, span.clone())
+            .instrument(span)
+            .await
+    }
+
+    /// Runs the processing of `handle_message` within its correlation span.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The Kafka message to handle.
+    /// * `context` - The shared state (routes, producer, batchers, ...) used for processing.
+    /// * `span` - The correlation span, receiving the message's transaction id and URI.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - The result of `handle_message`.
+    async fn process_message(
+        message: OwnedMessage,
+        context: MessageContext,
+        span: Span,
+    ) -> Result<()> {
+        let MessageContext {
+            source_id,
+            log_payloads,
+            route_registry,
+            producer,
+            batchers,
+            dedup,
+            parse_retry,
+            dead_letter,
+            pre_parser,
+            payload_limits,
+            on_unhandled,
+            middleware,
+            unhandled_count,
+            in_flight,
+            skip_before_ms,
+            correlation_spans: _,
+            handler_timeout,
+            acknowledge_response,
+            #[cfg(feature = "prometheus")]
+            prometheus,
+        } = context;
+
+        let start_time = Instant::now();
+
+        if let (Some(skip_before_ms), Timestamp::CreateTime(created_at_ms)) =
+            (skip_before_ms, message.timestamp())
+        {
+            if created_at_ms < skip_before_ms {
+                debug!(
+                    "skip historical message from topic {} [{}@{}]",
+                    message.topic(),
+                    message.partition(),
+                    message.offset()
+                );
+                return Ok(());
+            }
+        }
+
+        let payload = extract_payload(&message)?;
+
+        log_received(&message, &payload, log_payloads);
+
+        let payload = match &pre_parser {
+            Some(pre_parser) => match pre_parser(payload) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    if let Some(dead_letter) = &dead_letter {
+                        return dead_letter
+                            .forward(&producer, &message, &format!("pre-parser failed: {}", e))
+                            .await;
+                    }
+                    return Err(e).with_context(|| {
+                        format!(
+                            "pre-parser rejected payload from topic {} [{}@{}]",
+                            message.topic(),
+                            message.partition(),
+                            message.offset()
+                        )
+                    });
+                }
+            },
+            None => payload,
+        };
+
+        if let Some((max_depth, max_bytes)) = payload_limits {
+            if let Err(e) = validate_json(&payload, max_depth, max_bytes) {
+                if let Some(dead_letter) = &dead_letter {
+                    return dead_letter
+                        .forward(&producer, &message, &format!("rejected payload: {}", e))
+                        .await;
+                }
+                return Err(e).with_context(|| {
+                    format!(
+                        "rejected payload from topic {} [{}@{}]",
+                        message.topic(),
+                        message.partition(),
+                        message.offset()
+                    )
+                });
+            }
+        }
+
+        let Some(mut parsed_message) = ParsedMessage::parse_from_string(&payload) else {
+            if let Some(policy) = &parse_retry {
+                return policy.handle_failure(&producer, &message).await;
+            }
+            if let Some(dead_letter) = &dead_letter {
+                return dead_letter
+                    .forward(&producer, &message, "failed to parse message")
+                    .await;
+            }
+            anyhow::bail!("failed to parse message from kafka payload");
+        };
+        span.record(
+            "transaction_id",
+            tracing::field::display(&parsed_message.transaction_id),
+        );
+        span.record("uri", tracing::field::display(&parsed_message.uri));
+
+        if parsed_message.is_past_deadline() {
+            warn!(
+                "skip message {} for uri {} - {} past its deadline",
+                parsed_message.message_id, parsed_message.uri, parsed_message.transaction_id
+            );
+            return Ok(());
+        }
+
+        if let Some(dedup) = &dedup {
+            if !parsed_message.message_id.is_empty() && dedup.contains(&parsed_message.message_id) {
+                info!(
+                    "skip duplicate message {} for uri {} - {}",
+                    parsed_message.message_id, parsed_message.uri, parsed_message.transaction_id
+                );
+                return Ok(());
+            }
+        }
+
+        if let Some(batcher) = batchers.get(&parsed_message.uri) {
+            batcher
+                .push(BatchEntry {
+                    message: parsed_message,
+                    topic: message.topic().to_string(),
+                    partition: message.partition(),
+                    offset: message.offset(),
+                })
+                .await?;
+            // The offset completes once the batch is handled
+            return Err(CompletionDeferred.into());
+        }
+
+        #[cfg(feature = "prometheus")]
+        let mut timer = prometheus
+            .as_ref()
+            .map(|metrics| metrics.start(message.topic(), &parsed_message.uri));
+
+        let route_registry = Self::read_registry(&route_registry)?;
+        let outcome = Self::dispatch(
+            &route_registry,
+            producer,
+            source_id,
+            &mut parsed_message,
+            start_time,
+            DispatchOptions {
+                handler_timeout,
+                acknowledge_response,
+                dead_letter: dead_letter
+                    .as_deref()
+                    .map(|dead_letter| (dead_letter, &message)),
+                middleware: &middleware,
+                in_flight: &in_flight,
+            },
+        )
+        .await;
+
+        #[cfg(feature = "prometheus")]
+        if let (Some(timer), Ok(outcome)) = (&mut timer, &outcome) {
+            timer.set_outcome(*outcome);
+        }
+
+        // Only handled messages are remembered, so a failed or retried one is handled again
+        // when redelivered
+        if let (Some(dedup), Ok(DispatchOutcome::Ok | DispatchOutcome::Acknowledged)) =
+            (&dedup, &outcome)
+        {
+            if !parsed_message.message_id.is_empty() {
+                dedup.insert(&parsed_message.message_id);
+            }
+        }
+
+        if let Ok(DispatchOutcome::NotFound) = outcome {
+            unhandled_count.fetch_add(1, Ordering::Relaxed);
+            if let Some(on_unhandled) = &on_unhandled {
+                on_unhandled(&parsed_message);
+            }
+        }
+
+        match outcome? {
+            DispatchOutcome::Retry => Err(RedeliveryRequested.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Routes a parsed message to its handler and sends the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `route_registry` - The registry to look the handler up in.
+    /// * `producer` - The KafkaProducer to use for sending the response.
+    /// * `source_id` - The source identifier for the message.
+    /// * `parsed_message` - The parsed message to handle; receives the route's path parameters.
+    /// * `start_time` - The time when the message processing started.
+    /// * `options` - The handler timeout, acknowledge response, dead letter and middleware settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DispatchOutcome>` - How the message was handled, or an error if the response
+    ///   could not be sent.
+    async fn dispatch(
+        route_registry: &RouteRegistry,
+        producer: Arc<KafkaProducer>,
+        source_id: String,
+        parsed_message: &mut ParsedMessage,
+        start_time: Instant,
+        options: DispatchOptions<'_>,
+    ) -> Result<DispatchOutcome> {
+        for middleware in options.middleware {
+            if let Err(e) = middleware(parsed_message) {
+                warn!(
+                    "rejected request {} - {} by middleware: {}",
+                    parsed_message.uri, parsed_message.transaction_id, e
+                );
+                Self::handle_response_error(producer, parsed_message, source_id, start_time, e)
+                    .await?;
+                return Ok(DispatchOutcome::Rejected);
+            }
+        }
+
+        let (handler, limit) = match route_registry.resolve_with_limit(&parsed_message.uri)? {
+            Some(((handler, path_params), limit)) => {
+                parsed_message.path_params = path_params;
+                (Some(handler), limit)
+            }
+            None => (route_registry.get_fallback()?, None),
+        };
+
+        if let Some(handler) = handler {
+            let _permit = match limit {
+                Some(limit) => Some(
+                    limit
+                        .acquire_owned()
+                        .await
+                        .context("route concurrency limit closed")?,
+                ),
+                None => None,
+            };
+
+            let in_flight = InFlightGuard::new(options.in_flight);
+            let result = match options.handler_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, handler(parsed_message))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(KafkaError::TimeoutError(format!(
+                            "handler for {} did not complete within {}ms",
+                            parsed_message.uri,
+                            timeout.as_millis()
+                        )))
+                    }),
+                None => handler(parsed_message).await,
+            };
+            drop(in_flight);
+
+            match result {
                 Err(e) => {
                     error!(
                         "error handling request {} - {}: {}",
                         parsed_message.uri, parsed_message.transaction_id, e
                     );
+                    let reason = e.to_string();
                     Self::handle_response_error(
-                        producer,
-                        &parsed_message,
+                        producer.clone(),
+                        parsed_message,
                         source_id,
                         start_time,
                         e,
                     )
                     .await?;
+                    if let Some((dead_letter, message)) = options.dead_letter {
+                        dead_letter.forward(&producer, message, &reason).await?;
+                    }
+                    Ok(DispatchOutcome::Error)
                 }
                 Ok(result) => match result {
+                    HandlerResult::Acknowledge if options.acknowledge_response => {
+                        Self::handle_response(
+                            producer,
+                            source_id,
+                            parsed_message,
+                            start_time,
+                            Self::acknowledged_data(),
+                            "2.",
+                        )
+                        .await?;
+                        Ok(DispatchOutcome::Acknowledged)
+                    }
                     HandlerResult::Acknowledge => {
-                        let duration = start_time.elapsed().as_millis();
+                        let duration = format_duration(start_time.elapsed().as_millis() as i64);
                         info!(
-                            "2. acknowledge request {} - {} (no response) took: {}ms",
+                            "2. acknowledge request {} - {} (no response) took: {}",
                             parsed_message.uri, parsed_message.transaction_id, duration
                         );
+                        Ok(DispatchOutcome::Acknowledged)
+                    }
+                    HandlerResult::Retry => {
+                        info!(
+                            "2. retry request {} - {} (no response), left for redelivery",
+                            parsed_message.uri, parsed_message.transaction_id
+                        );
+                        Ok(DispatchOutcome::Retry)
                     }
                     HandlerResult::Response(response) => {
                         Self::handle_response_ok(
                             producer,
-                            &parsed_message,
+                            parsed_message,
                             source_id,
                             start_time,
                             response,
                         )
                         .await?;
+                        Ok(DispatchOutcome::Ok)
+                    }
+                    HandlerResult::Bytes(bytes, content_type) => {
+                        Self::handle_response_bytes(
+                            producer,
+                            parsed_message,
+                            source_id,
+                            start_time,
+                            bytes,
+                            content_type,
+                        )
+                        .await?;
+                        Ok(DispatchOutcome::Ok)
                     }
                 },
             }
         } else {
-            Self::send_not_found_uri_response(producer, &parsed_message, source_id, start_time)
+            Self::send_not_found_uri_response(producer, parsed_message, source_id, start_time)
                 .await?;
+            Ok(DispatchOutcome::NotFound)
         }
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::request_sender::{PendingRequest, RequestSender};
+    use crate::kafka::{
+        BatchConfig, BatchFailurePolicy, ConsumerMetrics, OffsetCommitter, OffsetTracker,
+    };
+    use crate::test_support::{capture_logs, TestMessage};
+    use std::sync::atomic::AtomicUsize;
+
+    fn test_config() -> KafkaClientConfig {
+        KafkaClientConfig::new("test-service".to_string(), "localhost:9092".to_string())
+    }
+
+    fn test_context(route_registry: RouteRegistry) -> MessageContext {
+        MessageContext {
+            source_id: "test-service".to_string(),
+            log_payloads: false,
+            route_registry: Arc::new(RwLock::new(route_registry)),
+            producer: Arc::new(KafkaProducer::new(test_config()).unwrap()),
+            batchers: Arc::new(HashMap::new()),
+            dedup: None,
+            parse_retry: None,
+            dead_letter: None,
+            pre_parser: None,
+            payload_limits: None,
+            on_unhandled: None,
+            middleware: Arc::new(Vec::new()),
+            unhandled_count: Arc::new(AtomicU64::new(0)),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            skip_before_ms: None,
+            correlation_spans: true,
+            handler_timeout: None,
+            acknowledge_response: true,
+            #[cfg(feature = "prometheus")]
+            prometheus: None,
+        }
+    }
+
+    fn test_message(uri: &str, message_id: &str) -> OwnedMessage {
+        test_message_at(uri, message_id, Timestamp::NotAvailable)
+    }
+
+    fn test_message_at(uri: &str, message_id: &str, timestamp: Timestamp) -> OwnedMessage {
+        test_envelope(uri, message_id)
+            .with_timestamp(timestamp)
+            .build()
+    }
+
+    fn test_envelope(uri: &str, message_id: &str) -> TestMessage {
+        let payload = serde_json::json!({
+            "messageType": "MESSAGE",
+            "sourceId": "caller-service",
+            "transactionId": format!("tx-{}", message_id),
+            "messageId": message_id,
+            "uri": uri,
+            "responseDestination": null,
+            "data": {},
+        });
+
+        TestMessage::new("test-service").with_payload(payload.to_string())
+    }
+
+    fn counting_registry(uri: &str, calls: Arc<AtomicUsize>) -> RouteRegistry {
+        let mut registry = RouteRegistry::new();
+        registry.register(uri, move |_| {
+            let calls = calls.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(HandlerResult::Acknowledge)
+            }
+        });
+        registry
+    }
+
+    /// Tracks offsets the way `KafkaConsumer::start` does, recording each committed offset.
+    fn recording_offsets() -> (TrackedOffsets, Arc<std::sync::Mutex<Vec<i64>>>) {
+        let committed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = committed.clone();
+        let committer: OffsetCommitter = Arc::new(move |_: &str, _: i32, next_offset: i64| {
+            recorded.lock().unwrap().push(next_offset);
+        });
+        let offsets = TrackedOffsets::new(Arc::new(OffsetTracker::new()), committer);
+        (offsets, committed)
+    }
+
+    /// Consumes the messages with `handle_message` as `StreamHandler::start` does.
+    async fn run_tracked(
+        messages: Vec<OwnedMessage>,
+        context: MessageContext,
+        offsets: TrackedOffsets,
+    ) {
+        let stream = futures::stream::iter(messages.into_iter().map(Ok));
+        let handler =
+            Arc::new(move |message| StreamHandler::handle_message(message, context.clone()));
+        let policy = RetryPolicy::new(3, Duration::from_millis(10), Duration::from_millis(10));
+        let handler = KafkaConsumer::redelivering(handler, policy);
+        let metrics = Arc::new(ConsumerMetrics::new());
+        KafkaConsumer::run_stream(stream, handler, 10, None, None, metrics, Some(offsets)).await;
+    }
+
+    /// Consumes three messages through a batch route flushing them at once, returning the
+    /// offsets committed after the flush.
+    async fn committed_after_batch(result: fn() -> Result<(), KafkaError>) -> Vec<i64> {
+        let (release, released) = tokio::sync::watch::channel(false);
+        let policy = RetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(1));
+        let mut registry = RouteRegistry::new();
+        registry.register_batch(
+            "/api/v1/bulk",
+            BatchConfig::new(3, Duration::from_secs(60))
+                .with_failure_policy(BatchFailurePolicy::Retry(policy)),
+            move |_| {
+                let mut released = released.clone();
+                async move {
+                    let _ = released.wait_for(|released| *released).await;
+                    result()
+                }
+            },
+        );
+
+        let (offsets, committed) = recording_offsets();
+        let mut context = test_context(registry.clone());
+        context.batchers =
+            Arc::new(StreamHandler::spawn_batchers(&registry, &offsets, None).unwrap());
+
+        let messages = (0..3)
+            .map(|offset| {
+                test_envelope("/api/v1/bulk", &format!("msg-{}", offset))
+                    .with_offset(offset)
+                    .build()
+            })
+            .collect();
+        run_tracked(messages, context, offsets).await;
+
+        // Messages handed to the batch are not committed before it is flushed
+        assert!(committed.lock().unwrap().is_empty());
+
+        release.send_replace(true);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let committed = committed.lock().unwrap().clone();
+        committed
+    }
+
+    #[tokio::test]
+    async fn test_batched_offsets_commit_once_batch_succeeds() {
+        assert_eq!(committed_after_batch(|| Ok(())).await, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_commits_offsets_once_given_up_on() {
+        let committed =
+            committed_after_batch(|| Err(KafkaError::InternalServerError("db down".to_string())))
+                .await;
+        assert_eq!(committed, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_message_id_handled_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut context = test_context(counting_registry("/api/v1/event", calls.clone()));
+        context.dedup = Some(Arc::new(DedupCache::new(100, Duration::from_secs(60))));
+
+        for _ in 0..2 {
+            StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+                .await
+                .unwrap();
+        }
+        StreamHandler::handle_message(test_message("/api/v1/event", "msg-2"), context.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_swapped_routes_apply_to_subsequent_messages() {
+        let old_calls = Arc::new(AtomicUsize::new(0));
+        let new_calls = Arc::new(AtomicUsize::new(0));
+        let handler = StreamHandler::new(
+            test_config(),
+            counting_registry("/api/v1/event", old_calls.clone()),
+        )
+        .unwrap();
+        let context = handler.message_context().unwrap();
+
+        StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+            .await
+            .unwrap();
+
+        handler.swap_routes(counting_registry("/api/v2/event", new_calls.clone()));
+
+        for (uri, message_id) in [("/api/v2/event", "msg-2"), ("/api/v1/event", "msg-3")] {
+            StreamHandler::handle_message(test_message(uri, message_id), context.clone())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(old_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(new_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(handler.unhandled_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_message_is_handled_again_when_redelivered() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut registry = RouteRegistry::new();
+        let attempts = calls.clone();
+        registry.register("/api/v1/event", move |_| {
+            let attempts = attempts.clone();
+            async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Err(KafkaError::InternalServerError("db down".to_string()));
+                }
+                Ok(HandlerResult::Acknowledge)
+            }
+        });
+        let mut context = test_context(registry);
+        context.dedup = Some(Arc::new(DedupCache::new(100, Duration::from_secs(60))));
+
+        for _ in 0..3 {
+            StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+                .await
+                .unwrap();
+        }
+
+        // The failed first delivery is not remembered; the successful second one is
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_without_deduplication_every_message_is_handled() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let context = test_context(counting_registry("/api/v1/event", calls.clone()));
+
+        for _ in 0..2 {
+            StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_result_requests_redelivery() {
+        let mut registry = RouteRegistry::new();
+        registry.register("/api/v1/event", |_| async { Ok(HandlerResult::Retry) });
+        let context = test_context(registry);
+
+        let error = StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context)
+            .await
+            .unwrap_err();
+
+        assert!(error.is::<RedeliveryRequested>());
+    }
+
+    #[tokio::test]
+    async fn test_retried_message_is_redelivered_before_its_offset_commits() {
+        let handled = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut registry = RouteRegistry::new();
+        let recorded = handled.clone();
+        registry.register("/api/v1/event", move |message: ParsedMessage| {
+            let recorded = recorded.clone();
+            async move {
+                let mut handled = recorded.lock().unwrap();
+                let first = !handled.contains(&message.message_id);
+                handled.push(message.message_id.clone());
+                if message.message_id == "msg-1" && first {
+                    return Ok(HandlerResult::Retry);
+                }
+                Ok(HandlerResult::Acknowledge)
+            }
+        });
+
+        let (offsets, committed) = recording_offsets();
+        let messages = (0..4)
+            .map(|offset| {
+                test_envelope("/api/v1/event", &format!("msg-{}", offset))
+                    .with_offset(offset)
+                    .build()
+            })
+            .collect();
+        run_tracked(messages, test_context(registry), offsets.clone()).await;
+
+        let mut handled = handled.lock().unwrap().clone();
+        handled.sort();
+        assert_eq!(handled, vec!["msg-0", "msg-1", "msg-1", "msg-2", "msg-3"]);
+
+        // The commit waits at the retried offset 1 until its redelivery is handled
+        assert_eq!(*committed.lock().unwrap(), vec![1, 4]);
+        assert_eq!(offsets.tracker().committable("test-service", 0), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_retried_message_reaches_handler_when_redelivered_with_deduplication() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut registry = RouteRegistry::new();
+        let attempts = calls.clone();
+        registry.register("/api/v1/event", move |_| {
+            let attempts = attempts.clone();
+            async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Ok(HandlerResult::Retry);
+                }
+                Ok(HandlerResult::Acknowledge)
+            }
+        });
+        let mut context = test_context(registry);
+        context.dedup = Some(Arc::new(DedupCache::new(100, Duration::from_secs(60))));
+
+        let first =
+            StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+                .await;
+        assert!(first.unwrap_err().is::<RedeliveryRequested>());
+
+        // The redelivery is handled, after which the message is a duplicate
+        for _ in 0..2 {
+            StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_route_limit_caps_concurrent_handlers() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let mut registry = RouteRegistry::new();
+        {
+            let (running, peak) = (running.clone(), peak.clone());
+            registry.register_with_limit("/api/v1/report", 2, move |_| {
+                let (running, peak) = (running.clone(), peak.clone());
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(HandlerResult::Acknowledge)
+                }
+            });
+        }
+        let context = test_context(registry);
+
+        let tasks: Vec<_> = (0..6)
+            .map(|i| {
+                let message = test_message("/api/v1/report", &format!("msg-{}", i));
+                tokio::spawn(StreamHandler::handle_message(message, context.clone()))
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_rises_while_handlers_run_then_falls_to_zero() {
+        let gate = Arc::new(tokio::sync::Semaphore::new(0));
+        let mut registry = RouteRegistry::new();
+        {
+            let gate = gate.clone();
+            registry.register("/api/v1/event", move |message: ParsedMessage| {
+                let gate = gate.clone();
+                async move {
+                    gate.acquire().await.unwrap().forget();
+                    if message.message_id == "msg-fail" {
+                        return Err(KafkaError::InternalServerError("boom".to_string()));
+                    }
+                    Ok(HandlerResult::Acknowledge)
+                }
+            });
+        }
+        let handler = StreamHandler::new(test_config(), registry).unwrap();
+        let context = handler.message_context().unwrap();
+
+        let tasks: Vec<_> = ["msg-1", "msg-2", "msg-fail"]
+            .into_iter()
+            .map(|message_id| {
+                let message = test_message("/api/v1/event", message_id);
+                tokio::spawn(StreamHandler::handle_message(message, context.clone()))
+            })
+            .collect();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while handler.in_flight() < 3 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(handler.in_flight(), 3);
+
+        gate.add_permits(3);
+        for task in tasks {
+            let _ = task.await.unwrap();
+        }
+
+        assert_eq!(handler.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_runs_in_order_and_rejects_before_handler() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let first = seen.clone();
+        let second = seen.clone();
+        let handler = StreamHandler::new(
+            test_config(),
+            counting_registry("/api/v1/event", calls.clone()),
+        )
+        .unwrap()
+        .with_middleware(move |message| {
+            first
+                .lock()
+                .unwrap()
+                .push(format!("auth {}", message.message_id));
+            if message.message_id == "msg-denied" {
+                return Err(KafkaError::InternalServerError("unauthorized".to_string()));
+            }
+            Ok(())
+        })
+        .with_middleware(move |message| {
+            second
+                .lock()
+                .unwrap()
+                .push(format!("audit {}", message.message_id));
+            Ok(())
+        });
+        let context = handler.message_context().unwrap();
+
+        let (writer, _guard) = capture_logs();
+        for message_id in ["msg-1", "msg-denied"] {
+            StreamHandler::handle_message(
+                test_message("/api/v1/event", message_id),
+                context.clone(),
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            ["auth msg-1", "audit msg-1", "auth msg-denied"]
+        );
+        assert!(writer
+            .output()
+            .lines()
+            .any(|line| line.contains("3. handle request /api/v1/event")));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_receives_unmatched_message() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fallback_uris = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut registry = counting_registry("/api/v1/event", calls.clone());
+        let uris = fallback_uris.clone();
+        registry.set_fallback(move |message| {
+            let uris = uris.clone();
+            async move {
+                uris.lock().unwrap().push(message.uri);
+                Ok(HandlerResult::Acknowledge)
+            }
+        });
+        let context = test_context(registry);
+
+        StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+            .await
+            .unwrap();
+        StreamHandler::handle_message(test_message("/api/v1/unknown", "msg-2"), context)
+            .await
+            .unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(*fallback_uris.lock().unwrap(), vec!["/api/v1/unknown"]);
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_message_without_fallback_is_not_found() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let registry = counting_registry("/api/v1/event", calls.clone());
+        assert!(registry.get_fallback().unwrap().is_none());
+        let context = test_context(registry);
+
+        StreamHandler::handle_message(test_message("/api/v1/unknown", "msg-1"), context)
+            .await
+            .unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_prometheus_metrics_label_topic_and_uri() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let metrics = Arc::new(PrometheusMetrics::new().unwrap());
+        let mut context = test_context(counting_registry("/api/v1/event", calls.clone()));
+        context.prometheus = Some(metrics.clone());
+
+        for (uri, message_id) in [("/api/v1/event", "msg-1"), ("/api/v1/event", "msg-2")] {
+            StreamHandler::handle_message(test_message(uri, message_id), context.clone())
+                .await
+                .unwrap();
+        }
+        StreamHandler::handle_message(test_message("/api/v1/unknown", "msg-3"), context)
+            .await
+            .unwrap();
+
+        let text = metrics.gather_metrics();
+        assert!(text.contains(
+            r#"kafka_messages_handled_total{outcome="acknowledged",topic="test-service",uri="/api/v1/event"} 2"#
+        ));
+        assert!(text.contains(
+            r#"kafka_messages_handled_total{outcome="not_found",topic="test-service",uri="/api/v1/unknown"} 1"#
+        ));
+        assert!(text.contains("kafka_message_handle_duration_seconds_bucket"));
+        assert!(text.contains("kafka_messages_in_flight"));
+    }
+
+    #[tokio::test]
+    async fn test_on_unhandled_fires_with_unmatched_uri() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let unhandled_uris = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut context = test_context(counting_registry("/api/v1/event", calls.clone()));
+        let uris = unhandled_uris.clone();
+        context.on_unhandled = Some(Arc::new(move |message: &ParsedMessage| {
+            uris.lock().unwrap().push(message.uri.clone());
+        }));
+
+        StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context.clone())
+            .await
+            .unwrap();
+        StreamHandler::handle_message(test_message("/api/v1/evnet", "msg-2"), context.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(*unhandled_uris.lock().unwrap(), vec!["/api/v1/evnet"]);
+        assert_eq!(context.unhandled_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pre_parser_unwraps_base64_payload() {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut context = test_context(counting_registry("/api/v1/event", calls.clone()));
+        context.pre_parser = Some(Arc::new(|payload: String| {
+            let decoded = STANDARD
+                .decode(payload.trim())
+                .map_err(|e| KafkaError::SerializationError(e.to_string()))?;
+            String::from_utf8(decoded).map_err(|e| KafkaError::SerializationError(e.to_string()))
+        }));
+
+        let envelope = test_message("/api/v1/event", "msg-1");
+        let wrapped = STANDARD.encode(envelope.payload().unwrap());
+        let message = TestMessage::new("test-service")
+            .with_payload(wrapped)
+            .build();
+        StreamHandler::handle_message(message, context.clone())
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // An unwrapped payload is not valid base64 and never reaches the handler
+        let result =
+            StreamHandler::handle_message(test_message("/api/v1/event", "msg-2"), context).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_payload_limits_reject_before_handler() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut context = test_context(counting_registry("/api/v1/event", calls.clone()));
+        context.payload_limits = Some((8, 64));
+
+        let result =
+            StreamHandler::handle_message(test_message("/api/v1/event", "msg-1"), context).await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_skip_historical_handles_only_new_messages() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut context = test_context(counting_registry("/api/v1/event", calls.clone()));
+        context.skip_before_ms = Some(1_700_000_000_000);
+
+        let old = test_message_at(
+            "/api/v1/event",
+            "msg-1",
+            Timestamp::CreateTime(1_600_000_000_000),
+        );
+        StreamHandler::handle_message(old, context.clone())
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let new = test_message_at(
+            "/api/v1/event",
+            "msg-2",
+            Timestamp::CreateTime(1_800_000_000_000),
+        );
+        StreamHandler::handle_message(new, context).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_message_past_deadline_is_skipped() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let context = test_context(counting_registry("/api/v1/event", calls.clone()));
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let with_deadline = |message_id: &str, deadline_ms: i64| {
+            let payload = serde_json::json!({
+                "messageType": "MESSAGE",
+                "sourceId": "caller-service",
+                "transactionId": "tx-1",
+                "messageId": message_id,
+                "uri": "/api/v1/event",
+                "deadlineMs": deadline_ms,
+                "data": {},
+            });
+            TestMessage::new("test-service")
+                .with_payload(payload.to_string())
+                .build()
+        };
+
+        StreamHandler::handle_message(with_deadline("msg-1", now - 1_000), context.clone())
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        StreamHandler::handle_message(with_deadline("msg-2", now + 60_000), context)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_response_destination_without_topic_is_warned() {
+        let mut registry = RouteRegistry::new();
+        registry.register("/api/v1/login", |_| async move {
+            Ok(HandlerResult::Response(serde_json::json!({ "ok": true })))
+        });
+        let context = test_context(registry);
+
+        let payload = serde_json::json!({
+            "messageType": "REQUEST",
+            "sourceId": "caller-service",
+            "transactionId": "tx-1",
+            "messageId": "msg-1",
+            "uri": "/api/v1/login",
+            "responseDestination": { "topic": "", "uri": "/api/v1/login/reply" },
+            "data": {},
+        });
+        let message = TestMessage::new("test-service")
+            .with_payload(payload.to_string())
+            .build();
+
+        let (writer, _guard) = capture_logs();
+        StreamHandler::handle_message(message, context)
+            .await
+            .unwrap();
+
+        assert!(writer.output().lines().any(|line| {
+            line.contains("WARN")
+                && line.contains("/api/v1/login/reply")
+                && line.contains("no topic")
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_handler_logs_carry_transaction_id() {
+        let (writer, _guard) = capture_logs();
+
+        let mut registry = RouteRegistry::new();
+        registry.register("/api/v1/orders", |_| async move {
+            info!("creating order");
+            Ok(HandlerResult::Acknowledge)
+        });
+        let mut context = test_context(registry);
+        context.dedup = Some(Arc::new(DedupCache::new(100, Duration::from_secs(60))));
+
+        for _ in 0..2 {
+            StreamHandler::handle_message(test_message("/api/v1/orders", "msg-1"), context.clone())
+                .await
+                .unwrap();
+        }
+
+        let output = writer.output();
+        let line = output
+            .lines()
+            .find(|line| line.contains("creating order"))
+            .unwrap();
+        assert!(line.contains("transaction_id=tx-msg-1"));
+        assert!(line.contains("uri=/api/v1/orders"));
+
+        // Lines logged by the pipeline itself carry them too
+        let line = output
+            .lines()
+            .find(|line| line.contains("skip duplicate message msg-1"))
+            .unwrap();
+        assert!(line.contains("transaction_id=tx-msg-1"));
+        assert!(line.contains("uri=/api/v1/orders"));
+    }
+
+    #[tokio::test]
+    async fn test_hung_handler_times_out_with_error_response() {
+        let mut registry = RouteRegistry::new();
+        registry.register_with_limit("/api/v1/orders", 1, |_| async move {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(HandlerResult::Acknowledge)
+        });
+        let (_, limit) = registry
+            .resolve_with_limit("/api/v1/orders")
+            .unwrap()
+            .unwrap();
+        let limit = limit.unwrap();
+
+        // Without a broker, the response fails delivery quickly and is logged with its payload
+        let config = test_config()
+            .set("message.timeout.ms", "100")
+            .with_log_payloads(true);
+        let mut context = test_context(registry);
+        context.producer = Arc::new(KafkaProducer::new(config).unwrap());
+        context.handler_timeout = Some(Duration::from_millis(50));
+
+        let payload = serde_json::json!({
+            "messageType": "REQUEST",
+            "sourceId": "caller-service",
+            "transactionId": "tx-1",
+            "messageId": "msg-1",
+            "uri": "/api/v1/orders",
+            "responseDestination": { "topic": "caller-service.reply", "uri": "REQUEST_RESPONSE" },
+            "data": {},
+        });
+        let message = TestMessage::new("test-service")
+            .with_payload(payload.to_string())
+            .build();
+
+        let (writer, _guard) = capture_logs();
+        let started = Instant::now();
+        let in_flight = context.in_flight.clone();
+        let _ = StreamHandler::handle_message(message, context).await;
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let output = writer.output();
+        assert!(output.lines().any(|line| {
+            line.contains("ERROR")
+                && line.contains("Timeout Error: handler for /api/v1/orders did not complete")
+        }));
+
+        // The timeout error response was handed to the producer for the response destination
+        let sent = output
+            .lines()
+            .find(|line| line.contains("failed to send message"))
+            .unwrap();
+        assert!(sent.contains("caller-service.reply"));
+        assert!(sent.contains(crate::kafka::error_codes::TIMEOUT_ERROR));
+
+        // The route's concurrency permit and the in-flight count are released
+        assert_eq!(limit.available_permits(), 1);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_request_sender_resolves_against_acknowledging_handler() {
+        let pending_requests = Arc::new(tokio::sync::RwLock::new(HashMap::new()));
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        pending_requests.write().await.insert(
+            "tx-ack".to_string(),
+            PendingRequest::new(tx, Duration::from_secs(60)),
+        );
+
+        let request = ParsedMessage::parse_from_string(
+            &serde_json::json!({
+                "messageType": "REQUEST",
+                "sourceId": "caller-service",
+                "transactionId": "tx-ack",
+                "messageId": "msg-1",
+                "uri": "/api/v1/events",
+                "responseDestination": { "topic": "caller-service.reply", "uri": "REQUEST_RESPONSE" },
+                "data": {},
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        // Transport: the acknowledge response is delivered straight to the sender's consumer
+        let response = StreamHandler::response_message(
+            "test-service".to_string(),
+            &request,
+            StreamHandler::acknowledged_data(),
+        )
+        .unwrap();
+        assert_eq!(response.topic, "caller-service.reply");
+        let payload = KafkaProducer::new(test_config())
+            .unwrap()
+            .encode(&response.message)
+            .unwrap();
+        let delivered = TestMessage::new(&response.topic)
+            .with_payload(payload)
+            .build();
+        RequestSender::handle_message(delivered, pending_requests, 60, false)
+            .await
+            .unwrap();
+
+        let resolved = rx.await.unwrap().unwrap();
+        assert_eq!(resolved.transaction_id, "tx-ack");
+        assert_eq!(resolved.data["data"]["acknowledged"], true);
     }
 }
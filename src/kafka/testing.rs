@@ -0,0 +1,98 @@
+//! Helpers for asserting wire-format compatibility of Kafka messages in tests.
+//!
+//! Available with the `testing` feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! rust-common = { version = "0.1", features = ["testing"] }
+//! ```
+
+use crate::kafka::{MessageType, ParsedMessage, ResponseDestination};
+
+/// Creates a representative request message with a response destination.
+///
+/// # Returns
+///
+/// * `ParsedMessage` - A message with every field populated.
+pub fn sample_message() -> ParsedMessage {
+    ParsedMessage {
+        message_type: MessageType::Request,
+        source_id: "sample-service".to_string(),
+        transaction_id: "sample-transaction-id".to_string(),
+        message_id: "sample-message-id".to_string(),
+        uri: "/api/v1/sample".to_string(),
+        response_destination: Some(ResponseDestination {
+            topic: "sample-service".to_string(),
+            uri: "REQUEST_RESPONSE".to_string(),
+        }),
+        content_encoding: None,
+        deadline_ms: None,
+        path_params: Default::default(),
+        data: serde_json::json!({ "id": 42, "name": "sample", "tags": ["a", "b"] }),
+    }
+}
+
+/// Asserts that a message survives JSON serialization unchanged.
+///
+/// The message is serialized the way producers send it and parsed back the way consumers
+/// receive it, then compared field by field so a failure names the field that changed.
+///
+/// # Arguments
+///
+/// * `msg` - The message to round-trip.
+///
+/// # Panics
+///
+/// Panics if the message cannot be serialized or parsed back, or if any field differs.
+pub fn assert_roundtrip(msg: &ParsedMessage) {
+    let json = serde_json::to_string(msg).expect("failed to serialize ParsedMessage");
+    let parsed = ParsedMessage::parse_from_string(&json)
+        .unwrap_or_else(|| panic!("failed to parse serialized ParsedMessage: {}", json));
+
+    assert_eq!(
+        parsed.message_type, msg.message_type,
+        "message_type differs"
+    );
+    assert_eq!(parsed.source_id, msg.source_id, "source_id differs");
+    assert_eq!(
+        parsed.transaction_id, msg.transaction_id,
+        "transaction_id differs"
+    );
+    assert_eq!(parsed.message_id, msg.message_id, "message_id differs");
+    assert_eq!(parsed.uri, msg.uri, "uri differs");
+    assert_eq!(
+        parsed.response_destination, msg.response_destination,
+        "response_destination differs"
+    );
+    assert_eq!(
+        parsed.content_encoding, msg.content_encoding,
+        "content_encoding differs"
+    );
+    assert_eq!(parsed.data, msg.data, "data differs");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_sample_message() {
+        assert_roundtrip(&sample_message());
+    }
+
+    #[test]
+    fn test_roundtrip_without_response_destination() {
+        let mut message = sample_message();
+        message.message_type = MessageType::Message;
+        message.response_destination = None;
+        assert_roundtrip(&message);
+    }
+
+    #[test]
+    fn test_wire_format_uses_camel_case() {
+        let json = serde_json::to_value(sample_message()).unwrap();
+        assert_eq!(json["messageType"], "REQUEST");
+        assert_eq!(json["transactionId"], "sample-transaction-id");
+        assert_eq!(json["responseDestination"]["uri"], "REQUEST_RESPONSE");
+    }
+}
@@ -1,8 +1,11 @@
 pub mod utils {
     use rdkafka::{message::OwnedMessage, Message};
-    use tracing::warn;
+    use tracing::{info, warn};
 
-    use crate::kafka::{MessageType, ParsedMessage, ResponseDestination, SendMessage};
+    use crate::kafka::{
+        KafkaError, MessageLatency, MessageType, ParsedMessage, PayloadError, ResponseDestination,
+        SendMessage,
+    };
 
     /// Extracts the payload from an OwnedMessage as a String.
     ///
@@ -12,16 +15,52 @@ pub mod utils {
     ///
     /// # Returns
     ///
-    /// * `Option<String>` - The payload as a String if it exists and is valid, otherwise None.
-    pub fn extract_payload(message: &OwnedMessage) -> Option<String> {
-        match message.payload_view::<str>() {
-            Some(Ok(payload)) => Some(payload.to_string()),
-            Some(Err(_)) => {
-                warn!("invalid payload from topic {}", message.topic());
-                None
+    /// * `Result<String, PayloadError>` - The payload as a String, `PayloadError::Empty` if there is
+    ///   no payload, or `PayloadError::InvalidUtf8` if the bytes are not valid UTF-8.
+    pub fn extract_payload(message: &OwnedMessage) -> Result<String, PayloadError> {
+        decode_payload(message.payload(), false).inspect_err(|e| {
+            if let PayloadError::InvalidUtf8(_) = e {
+                warn!("invalid payload from topic {}: {}", message.topic(), e);
             }
-            None => None,
+        })
+    }
+
+    /// Extracts the payload from an OwnedMessage, replacing invalid UTF-8 sequences with `U+FFFD`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The Kafka message from which to extract the payload.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, PayloadError>` - The decoded payload, or `PayloadError::Empty` if there is none.
+    pub fn extract_payload_lossy(message: &OwnedMessage) -> Result<String, PayloadError> {
+        decode_payload(message.payload(), true)
+    }
+
+    /// Decodes raw payload bytes as UTF-8 text.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The raw payload bytes, if any.
+    /// * `lossy` - Whether to replace invalid UTF-8 sequences instead of failing.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, PayloadError>` - The decoded payload or the reason it could not be decoded.
+    pub fn decode_payload(payload: Option<&[u8]>, lossy: bool) -> Result<String, PayloadError> {
+        let bytes = match payload {
+            Some(bytes) if !bytes.is_empty() => bytes,
+            _ => return Err(PayloadError::Empty),
+        };
+
+        if lossy {
+            return Ok(String::from_utf8_lossy(bytes).into_owned());
         }
+
+        std::str::from_utf8(bytes)
+            .map(|payload| payload.to_string())
+            .map_err(|e| PayloadError::InvalidUtf8(e.to_string()))
     }
 
     /// Creates a SendMessage with the specified parameters.
@@ -59,8 +98,286 @@ pub mod utils {
                 transaction_id,
                 uri,
                 response_destination,
+                content_encoding: None,
+                deadline_ms: None,
+                path_params: Default::default(),
                 data,
             },
         }
     }
+
+    /// Formats a duration in milliseconds as human-readable text.
+    ///
+    /// Durations under a second are shown in milliseconds (`"250ms"`), under a minute in
+    /// seconds with one decimal (`"1.5s"`), and longer ones in whole hours, minutes and
+    /// seconds (`"2m3s"`, `"1h5m"`). Negative durations, e.g. from clock skew, keep their sign.
+    ///
+    /// # Arguments
+    ///
+    /// * `ms` - The duration in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The formatted duration.
+    pub fn format_duration(ms: i64) -> String {
+        if ms < 0 {
+            return format!("-{}", format_duration_abs(ms.unsigned_abs()));
+        }
+        format_duration_abs(ms as u64)
+    }
+
+    fn format_duration_abs(ms: u64) -> String {
+        if ms < 1_000 {
+            return format!("{}ms", ms);
+        }
+
+        if ms < 60_000 {
+            let tenths = (ms + 50) / 100;
+            return if tenths.is_multiple_of(10) {
+                format!("{}s", tenths / 10)
+            } else {
+                format!("{}.{}s", tenths / 10, tenths % 10)
+            };
+        }
+
+        let total_secs = ms / 1_000;
+        let (hours, minutes, secs) = (total_secs / 3_600, total_secs / 60 % 60, total_secs % 60);
+
+        let mut formatted = String::new();
+        if hours > 0 {
+            formatted.push_str(&format!("{}h", hours));
+        }
+        if minutes > 0 {
+            formatted.push_str(&format!("{}m", minutes));
+        }
+        if secs > 0 {
+            formatted.push_str(&format!("{}s", secs));
+        }
+        formatted
+    }
+
+    /// Checks the size and nesting depth of a JSON payload without deserializing it.
+    ///
+    /// Meant to run before `serde_json::from_str` so that huge or deeply nested payloads are
+    /// rejected cheaply instead of exhausting memory or stack during parsing. The payload is
+    /// scanned once; brackets inside strings are ignored. The JSON itself is not validated.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The raw JSON text.
+    /// * `max_depth` - The maximum nesting depth of objects and arrays.
+    /// * `max_bytes` - The maximum payload size in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), KafkaError>` - Ok if within limits, or a `ValidationError` describing the limit exceeded.
+    pub fn validate_json(
+        payload: &str,
+        max_depth: usize,
+        max_bytes: usize,
+    ) -> Result<(), KafkaError> {
+        if payload.len() > max_bytes {
+            return Err(KafkaError::ValidationError {
+                path: "$".to_string(),
+                message: format!(
+                    "payload of {} bytes exceeds the limit of {} bytes",
+                    payload.len(),
+                    max_bytes
+                ),
+            });
+        }
+
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        for (offset, byte) in payload.bytes().enumerate() {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => in_string = true,
+                b'{' | b'[' => {
+                    depth += 1;
+                    if depth > max_depth {
+                        return Err(KafkaError::ValidationError {
+                            path: "$".to_string(),
+                            message: format!(
+                                "nesting depth exceeds the limit of {} at byte {}",
+                                max_depth, offset
+                            ),
+                        });
+                    }
+                }
+                b'}' | b']' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Logs the receipt of a consumed message.
+    ///
+    /// The payload may contain personal data, so unless `log_payloads` is set only metadata
+    /// (topic, partition, offset, size and latency) is logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The consumed Kafka message.
+    /// * `payload` - The decoded payload of the message.
+    /// * `log_payloads` - Whether to include the payload in the log.
+    pub fn log_received(message: &OwnedMessage, payload: &str, log_payloads: bool) {
+        let latency = message.get_latency_formatted();
+
+        if log_payloads {
+            info!(
+                "received message: '{}' from topic {}, latency: {}",
+                payload,
+                message.topic(),
+                latency,
+            );
+        } else {
+            info!(
+                "received message from topic {} [{}@{}], size: {} bytes, latency: {}",
+                message.topic(),
+                message.partition(),
+                message.offset(),
+                payload.len(),
+                latency,
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::test_support::{capture_logs, TestMessage};
+
+        fn message_with_payload(payload: Option<Vec<u8>>) -> OwnedMessage {
+            match payload {
+                Some(payload) => TestMessage::new("test-topic").with_payload(payload).build(),
+                None => TestMessage::new("test-topic").build(),
+            }
+        }
+
+        #[test]
+        fn test_extract_payload_empty() {
+            assert_eq!(
+                extract_payload(&message_with_payload(None)),
+                Err(PayloadError::Empty)
+            );
+            assert_eq!(
+                extract_payload(&message_with_payload(Some(Vec::new()))),
+                Err(PayloadError::Empty)
+            );
+        }
+
+        #[test]
+        fn test_extract_payload_valid_utf8() {
+            let message = message_with_payload(Some("{\"uri\":\"/a\"}".as_bytes().to_vec()));
+            assert_eq!(
+                extract_payload(&message),
+                Ok("{\"uri\":\"/a\"}".to_string())
+            );
+        }
+
+        #[test]
+        fn test_extract_payload_invalid_utf8() {
+            let message = message_with_payload(Some(vec![b'o', b'k', 0xff, 0xfe]));
+            assert!(matches!(
+                extract_payload(&message),
+                Err(PayloadError::InvalidUtf8(_))
+            ));
+            assert_eq!(
+                extract_payload_lossy(&message),
+                Ok("ok\u{fffd}\u{fffd}".to_string())
+            );
+        }
+
+        #[test]
+        fn test_format_duration_sub_second() {
+            assert_eq!(format_duration(0), "0ms");
+            assert_eq!(format_duration(250), "250ms");
+            assert_eq!(format_duration(999), "999ms");
+        }
+
+        #[test]
+        fn test_format_duration_seconds() {
+            assert_eq!(format_duration(1_000), "1s");
+            assert_eq!(format_duration(1_500), "1.5s");
+            assert_eq!(format_duration(12_340), "12.3s");
+            assert_eq!(format_duration(59_000), "59s");
+        }
+
+        #[test]
+        fn test_format_duration_minutes_and_hours() {
+            assert_eq!(format_duration(60_000), "1m");
+            assert_eq!(format_duration(123_000), "2m3s");
+            assert_eq!(format_duration(3_900_000), "1h5m");
+            assert_eq!(format_duration(3_723_000), "1h2m3s");
+        }
+
+        #[test]
+        fn test_format_duration_negative() {
+            assert_eq!(format_duration(-250), "-250ms");
+            assert_eq!(format_duration(-1_500), "-1.5s");
+        }
+
+        #[test]
+        fn test_validate_json_within_limits() {
+            let payload = r#"{"a": [1, {"b": "[[[{{{"}], "c": "\\"}"#;
+            assert!(validate_json(payload, 3, 1024).is_ok());
+        }
+
+        #[test]
+        fn test_validate_json_rejects_deep_nesting() {
+            let payload = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+
+            let error = validate_json(&payload, 64, usize::MAX).unwrap_err();
+            assert!(matches!(error, KafkaError::ValidationError { .. }));
+            assert!(error
+                .to_string()
+                .contains("nesting depth exceeds the limit of 64"));
+        }
+
+        #[test]
+        fn test_validate_json_rejects_oversized_payload() {
+            let payload = format!(r#"{{"data": "{}"}}"#, "x".repeat(2048));
+
+            let error = validate_json(&payload, 64, 1024).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("exceeds the limit of 1024 bytes"));
+        }
+
+        fn captured_log(log_payloads: bool) -> String {
+            let payload = r#"{"data":{"email":"jane@example.com"}}"#;
+            let message = message_with_payload(Some(payload.as_bytes().to_vec()));
+
+            let (writer, _guard) = capture_logs();
+            log_received(&message, payload, log_payloads);
+            writer.output()
+        }
+
+        #[test]
+        fn test_log_received_without_payload() {
+            let output = captured_log(false);
+            assert!(output.contains("received message from topic test-topic [0@0], size: 37 bytes"));
+            assert!(!output.contains("jane@example.com"));
+        }
+
+        #[test]
+        fn test_log_received_with_payload() {
+            let output = captured_log(true);
+            assert!(output.contains("jane@example.com"));
+        }
+    }
 }
@@ -6,6 +6,7 @@
 //! ## Features
 //!
 //! - **Logger**: Structured logging with tracing
+//! - **Math**: Numeric helpers, including exact decimal arithmetic (`decimal` feature)
 //! - **Extensible**: Easy to add new modules
 //! - **Well-tested**: Comprehensive test coverage
 //!
@@ -24,15 +25,25 @@
 //!
 //! ## Modules
 //!
+//! - `bootstrap`: One-call logger and Kafka setup from the environment
 //! - `logger`: Structured logging with tracing
+//! - `math`: Numeric helpers
 
 // Logger module is always available
+pub mod bootstrap;
 pub mod kafka;
 pub mod logger;
+pub mod math;
+
+#[cfg(test)]
+mod test_support;
+
+pub use bootstrap::bootstrap;
 
 /// Re-export commonly used items for convenience
 pub mod prelude {
     // Re-export specific types to avoid naming conflicts
+    pub use crate::bootstrap;
     pub use crate::kafka::{HandlerResult, KafkaClientConfig, KafkaError, StreamHandler};
     pub use crate::logger::{init_with_default, LoggerConfig};
 }
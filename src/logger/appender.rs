@@ -0,0 +1,242 @@
+//! # Appender Module
+//!
+//! Provides a rolling file appender that creates its log files with a file mode.
+//!
+//! Only available on Unix, where file permissions are expressed as a mode.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use rust_common::logger::LoggerConfig;
+//!
+//! // Log files readable and writable by the owner only
+//! let config = LoggerConfig::builder()
+//!     .enable_file(true)
+//!     .file_mode(0o600)
+//!     .build();
+//! ```
+
+use std::{
+    fs, io,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Datelike, Utc};
+use tracing_appender::rolling::{RollingFileAppender, RollingWriter, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Interval between checks for the files of upcoming rotations
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A `RollingFileAppender` whose log files are created with a mode
+///
+/// The file of the current rotation period is created before the inner appender
+/// opens it, and the file of the next period is created ahead of the rotation,
+/// checked at most once per second. The inner appender only appends to these
+/// files, so no log line is ever written with another mode.
+#[derive(Debug)]
+pub struct FileModeAppender {
+    inner: RollingFileAppender,
+    files: LogFiles,
+    last_check: Mutex<Instant>,
+}
+
+/// The naming and mode of the files of a `FileModeAppender`
+#[derive(Debug)]
+struct LogFiles {
+    log_dir: PathBuf,
+    log_filename: String,
+    rotation: Rotation,
+    mode: u32,
+}
+
+impl FileModeAppender {
+    /// Creates an appender writing to `log_dir/log_filename*` with `mode` for its files
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(
+        rotation: Rotation,
+        log_dir: P,
+        log_filename: S,
+        mode: u32,
+    ) -> io::Result<Self> {
+        let files = LogFiles {
+            log_dir: log_dir.into(),
+            log_filename: log_filename.into(),
+            rotation,
+            mode,
+        };
+        fs::create_dir_all(&files.log_dir)?;
+        files.create_files(Utc::now())?;
+
+        Ok(Self {
+            inner: RollingFileAppender::new(
+                files.rotation.clone(),
+                &files.log_dir,
+                &files.log_filename,
+            ),
+            files,
+            last_check: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Gets the mode of log files
+    pub fn mode(&self) -> u32 {
+        self.files.mode
+    }
+
+    /// Gets the name the inner appender gives the file of the period containing `time`
+    pub fn file_name(&self, time: DateTime<Utc>) -> String {
+        self.files.file_name(time)
+    }
+
+    /// Checks whether `name` is a file of this appender
+    ///
+    /// Only names of the exact form the inner appender uses match, so `app.log.bak`
+    /// is not a file of an `app.log` appender.
+    pub fn is_log_file(&self, name: &str) -> bool {
+        self.files.is_log_file(name)
+    }
+
+    /// Creates the upcoming files if the last check is older than `CHECK_INTERVAL`
+    fn create_files_periodically(&self) {
+        let Ok(mut last_check) = self.last_check.try_lock() else {
+            return;
+        };
+        if last_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        *last_check = Instant::now();
+
+        if let Err(e) = self.files.create_files(Utc::now()) {
+            eprintln!("failed to create log file: {}", e);
+        }
+    }
+}
+
+impl LogFiles {
+    /// Gets the name of the file of the period containing `time`
+    fn file_name(&self, time: DateTime<Utc>) -> String {
+        let date = match self.rotation {
+            Rotation::MINUTELY => time.format("%Y-%m-%d-%H-%M").to_string(),
+            Rotation::HOURLY => time.format("%Y-%m-%d-%H").to_string(),
+            Rotation::DAILY => time.format("%Y-%m-%d").to_string(),
+            Rotation::WEEKLY => {
+                let sunday =
+                    time - chrono::Duration::days(time.weekday().num_days_from_sunday().into());
+                sunday.format("%Y-%m-%d").to_string()
+            }
+            _ => return self.log_filename.clone(),
+        };
+        format!("{}.{}", self.log_filename, date)
+    }
+
+    /// Checks whether `name` has the form of `file_name`, whatever its date
+    fn is_log_file(&self, name: &str) -> bool {
+        let expected = self.file_name(Utc::now());
+        name.len() == expected.len()
+            && name.starts_with(&self.log_filename)
+            && name
+                .bytes()
+                .zip(expected.bytes())
+                .skip(self.log_filename.len())
+                .all(|(actual, pattern)| {
+                    actual == pattern || (actual.is_ascii_digit() && pattern.is_ascii_digit())
+                })
+    }
+
+    /// Creates the files of the period containing `now` and of the next period
+    fn create_files(&self, now: DateTime<Utc>) -> io::Result<()> {
+        self.create_file(&self.file_name(now))?;
+
+        let period = match self.rotation {
+            Rotation::MINUTELY => chrono::Duration::minutes(1),
+            Rotation::HOURLY => chrono::Duration::hours(1),
+            Rotation::DAILY => chrono::Duration::days(1),
+            Rotation::WEEKLY => chrono::Duration::weeks(1),
+            _ => return Ok(()),
+        };
+        self.create_file(&self.file_name(now + period))
+    }
+
+    /// Creates a log file with the mode, or gives an existing one the mode before it is written
+    fn create_file(&self, name: &str) -> io::Result<()> {
+        debug_assert!(self.is_log_file(name));
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(self.mode)
+            .open(self.log_dir.join(name))?;
+
+        // The umask may have cleared bits of the mode, and existing files keep theirs
+        if file.metadata()?.permissions().mode() & 0o777 != self.mode {
+            file.set_permissions(fs::Permissions::from_mode(self.mode))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for FileModeAppender {
+    type Writer = RollingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        // The inner appender rotates inside `make_writer`, so its next file must exist by then
+        self.create_files_periodically();
+        self.inner.make_writer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn file_mode(path: PathBuf) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn test_created_log_file_has_requested_mode() {
+        let dir = std::env::temp_dir().join(format!("file-mode-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let appender = FileModeAppender::new(Rotation::NEVER, &dir, "app.log", 0o600).unwrap();
+        appender.make_writer().write_all(b"hello\n").unwrap();
+
+        assert_eq!(file_mode(dir.join("app.log")), 0o600);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_next_rotated_file_is_created_with_requested_mode() {
+        let dir = std::env::temp_dir().join(format!("file-mode-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.log.bak"), b"backup\n").unwrap();
+        fs::set_permissions(dir.join("app.log.bak"), fs::Permissions::from_mode(0o644)).unwrap();
+
+        let appender = FileModeAppender::new(Rotation::MINUTELY, &dir, "app.log", 0o600).unwrap();
+
+        let now = Utc::now();
+        assert_eq!(
+            appender.file_name(now),
+            format!("app.log.{}", now.format("%Y-%m-%d-%H-%M"))
+        );
+        assert!(!appender.is_log_file("app.log.bak"));
+
+        // The current and the next minute
+        let log_files: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| appender.is_log_file(name))
+            .collect();
+        assert_eq!(log_files.len(), 2);
+        for name in log_files {
+            assert_eq!(file_mode(dir.join(name)), 0o600);
+        }
+        assert_eq!(file_mode(dir.join("app.log.bak")), 0o644);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,7 +5,7 @@
 //! ## Examples
 //!
 //! ```rust
-//! use rust_common::logger::LoggerConfig;
+//! use rust_common::logger::{LogFormat, LoggerConfig};
 //! use tracing::Level;
 //! use tracing_appender::rolling::Rotation;
 //!
@@ -25,12 +25,15 @@
 //!     .enable_file(true)
 //!     .rotation(Rotation::HOURLY)
 //!     .show_spans(false)
+//!     .format(LogFormat::Pretty)
 //!     .build();
 //! ```
 
 use tracing::Level;
 use tracing_appender::rolling::Rotation;
 
+use crate::logger::{LogFormat, TargetDisplay};
+
 /// Configuration for the logger system
 #[derive(Debug, Clone)]
 pub struct LoggerConfig {
@@ -40,11 +43,18 @@ pub struct LoggerConfig {
     show_file_line: bool,
     show_thread: bool,
     show_target: bool,
+    target_display: TargetDisplay,
     use_ansi: bool,
     enable_console: bool,
     enable_file: bool,
     rotation: Rotation,
     show_spans: bool,
+    format: LogFormat,
+    field_separator: String,
+    sample_rate: Option<u32>,
+    sample_threshold: Level,
+    #[cfg(unix)]
+    file_mode: Option<u32>,
 }
 
 impl LoggerConfig {
@@ -83,6 +93,17 @@ impl LoggerConfig {
         self.show_target
     }
 
+    /// Gets how the target is rendered
+    ///
+    /// Returns `TargetDisplay::None` when `show_target` is disabled.
+    pub fn target_display(&self) -> TargetDisplay {
+        if self.show_target {
+            self.target_display
+        } else {
+            TargetDisplay::None
+        }
+    }
+
     /// Gets whether to use ANSI colors
     pub fn use_ansi(&self) -> bool {
         self.use_ansi
@@ -107,6 +128,34 @@ impl LoggerConfig {
     pub fn show_spans(&self) -> bool {
         self.show_spans
     }
+
+    /// Gets the layout of each log line
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Gets the separator between fields in `LogFormat::KeyValue`
+    pub fn field_separator(&self) -> &str {
+        &self.field_separator
+    }
+
+    /// Gets the sampling rate, `Some(n)` keeps 1 in n events of each call site
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+    }
+
+    /// Gets the most severe level that is sampled
+    pub fn sample_threshold(&self) -> Level {
+        self.sample_threshold
+    }
+
+    /// Gets the mode applied to created log files
+    ///
+    /// `None` leaves the mode to the process umask.
+    #[cfg(unix)]
+    pub fn file_mode(&self) -> Option<u32> {
+        self.file_mode
+    }
 }
 
 impl Default for LoggerConfig {
@@ -118,11 +167,18 @@ impl Default for LoggerConfig {
             show_file_line: cfg!(debug_assertions),
             show_thread: true,
             show_target: false,
+            target_display: TargetDisplay::Full,
             use_ansi: true,
             enable_console: true,
             enable_file: false,
             rotation: Rotation::DAILY,
             show_spans: false,
+            format: LogFormat::Pretty,
+            field_separator: " ".to_string(),
+            sample_rate: None,
+            sample_threshold: Level::WARN,
+            #[cfg(unix)]
+            file_mode: None,
         }
     }
 }
@@ -177,6 +233,15 @@ impl LoggerConfigBuilder {
         self
     }
 
+    /// Sets how the target is rendered
+    ///
+    /// Any mode other than `TargetDisplay::None` also enables `show_target`.
+    pub fn target_display(mut self, display: TargetDisplay) -> Self {
+        self.config.target_display = display;
+        self.config.show_target = display != TargetDisplay::None;
+        self
+    }
+
     /// Sets whether to use ANSI colors
     pub fn use_ansi(mut self, use_ansi: bool) -> Self {
         self.config.use_ansi = use_ansi;
@@ -207,6 +272,41 @@ impl LoggerConfigBuilder {
         self
     }
 
+    /// Sets the layout of each log line
+    ///
+    /// `LogFormat::Json` never uses ANSI colors.
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.config.format = format;
+        self
+    }
+
+    /// Sets the separator between fields in `LogFormat::KeyValue`, a space by default
+    pub fn field_separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.config.field_separator = separator.into();
+        self
+    }
+
+    /// Keeps only 1 in `rate` events of each call site, see `SamplingLayer`
+    pub fn sample_rate(mut self, rate: u32) -> Self {
+        self.config.sample_rate = Some(rate);
+        self
+    }
+
+    /// Sets the most severe level that is sampled, `Level::WARN` by default
+    ///
+    /// Events from INFO up to this level are sampled; DEBUG and TRACE never are.
+    pub fn sample_threshold(mut self, level: Level) -> Self {
+        self.config.sample_threshold = level;
+        self
+    }
+
+    /// Sets the mode applied to created log files, e.g. `0o600`
+    #[cfg(unix)]
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.config.file_mode = Some(mode);
+        self
+    }
+
     /// Builds the LoggerConfig
     pub fn build(self) -> LoggerConfig {
         self.config
@@ -248,6 +348,7 @@ mod tests {
             .enable_file(true)
             .rotation(Rotation::HOURLY)
             .show_spans(true)
+            .format(LogFormat::Json)
             .build();
 
         assert_eq!(config.default_level(), Level::DEBUG);
@@ -260,6 +361,7 @@ mod tests {
         assert!(!config.enable_console());
         assert!(config.enable_file());
         assert!(config.show_spans());
+        assert_eq!(config.format(), LogFormat::Json);
     }
 
     #[test]
@@ -272,6 +374,31 @@ mod tests {
         assert_eq!(config.default_level(), default_config.default_level());
         assert_eq!(config.log_dir(), default_config.log_dir());
         assert_eq!(config.log_filename(), default_config.log_filename());
+        assert_eq!(config.format(), LogFormat::Pretty);
+        assert_eq!(config.sample_rate(), None);
+        assert_eq!(config.sample_threshold(), Level::WARN);
+    }
+
+    #[test]
+    fn test_target_display() {
+        assert_eq!(
+            LoggerConfig::default().target_display(),
+            TargetDisplay::None
+        );
+
+        let config = LoggerConfig::builder().show_target(true).build();
+        assert_eq!(config.target_display(), TargetDisplay::Full);
+
+        let config = LoggerConfig::builder()
+            .target_display(TargetDisplay::Leaf)
+            .build();
+        assert!(config.show_target());
+        assert_eq!(config.target_display(), TargetDisplay::Leaf);
+
+        let config = LoggerConfig::builder()
+            .target_display(TargetDisplay::None)
+            .build();
+        assert!(!config.show_target());
     }
 
     #[test]
@@ -0,0 +1,227 @@
+//! # Early Logs Module
+//!
+//! Captures log events emitted before the logger is initialized and replays them once it is.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use rust_common::logger::{self, EarlyLogs, LoggerConfig};
+//! use tracing::info;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let early = EarlyLogs::capture(256);
+//! info!("loading configuration");
+//! let config = LoggerConfig::default();
+//!
+//! // "loading configuration" is written through the new logger
+//! logger::init_with_early_logs(config, early)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    subscriber::DefaultGuard,
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+use crate::logger::{init, LoggerConfig, LoggerResult, ReloadHandle};
+
+/// A log event captured before the logger was initialized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EarlyEvent {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Buffers log events emitted on the current thread until the logger is initialized
+///
+/// At most `capacity` events are kept; older events are dropped once it is reached.
+/// Capturing stops when the value is replayed or dropped.
+pub struct EarlyLogs {
+    buffer: Arc<Mutex<EarlyBuffer>>,
+    _guard: DefaultGuard,
+}
+
+struct EarlyBuffer {
+    events: VecDeque<EarlyEvent>,
+    capacity: usize,
+    dropped: usize,
+}
+
+struct BufferLayer(Arc<Mutex<EarlyBuffer>>);
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl EarlyLogs {
+    /// Starts capturing log events emitted on the current thread
+    pub fn capture(capacity: usize) -> Self {
+        let buffer = Arc::new(Mutex::new(EarlyBuffer {
+            events: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+        }));
+        let subscriber = tracing_subscriber::registry().with(BufferLayer(buffer.clone()));
+
+        Self {
+            buffer,
+            _guard: tracing::subscriber::set_default(subscriber),
+        }
+    }
+
+    /// Returns the events captured so far
+    pub fn events(&self) -> Vec<EarlyEvent> {
+        self.lock().events.iter().cloned().collect()
+    }
+
+    /// Returns the number of events dropped because the buffer was full
+    pub fn dropped(&self) -> usize {
+        self.lock().dropped
+    }
+
+    /// Stops capturing and re-emits the captured events through the current subscriber
+    pub fn replay(self) {
+        let (events, dropped) = self.finish();
+        replay_events(&events, dropped);
+    }
+
+    fn finish(self) -> (Vec<EarlyEvent>, usize) {
+        let mut buffer = self.lock();
+        let events = buffer.events.drain(..).collect();
+        (events, buffer.dropped)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, EarlyBuffer> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Initializes the logger and replays the events captured before it
+///
+/// If initialization fails, the captured events are written to stderr instead so
+/// that the cause of a startup failure is not lost.
+pub fn init_with_early_logs(config: LoggerConfig, early: EarlyLogs) -> LoggerResult<ReloadHandle> {
+    let (events, dropped) = early.finish();
+
+    let result = init(config);
+
+    if result.is_ok() {
+        replay_events(&events, dropped);
+    } else {
+        for event in &events {
+            eprintln!("{} {}: {}", event.level, event.target, event.message);
+        }
+    }
+
+    result
+}
+
+fn replay_events(events: &[EarlyEvent], dropped: usize) {
+    if dropped > 0 {
+        tracing::warn!(
+            "{} log events emitted before logger init were dropped",
+            dropped
+        );
+    }
+
+    for event in events {
+        let (target, message) = (&event.target, &event.message);
+        match event.level {
+            Level::ERROR => tracing::error!(target: "pre_init", "{}: {}", target, message),
+            Level::WARN => tracing::warn!(target: "pre_init", "{}: {}", target, message),
+            Level::INFO => tracing::info!(target: "pre_init", "{}: {}", target, message),
+            Level::DEBUG => tracing::debug!(target: "pre_init", "{}: {}", target, message),
+            Level::TRACE => tracing::trace!(target: "pre_init", "{}: {}", target, message),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if buffer.events.len() == buffer.capacity {
+            buffer.events.pop_front();
+            buffer.dropped += 1;
+        }
+        buffer.events.push_back(EarlyEvent {
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::capture_logs;
+
+    #[test]
+    fn test_captured_event_replayed_after_init() {
+        let early = EarlyLogs::capture(16);
+        tracing::info!(path = "app.toml", "loading config");
+
+        assert_eq!(
+            early.events(),
+            vec![EarlyEvent {
+                level: Level::INFO,
+                target: module_path!().to_string(),
+                message: "loading config path=app.toml".to_string(),
+            }]
+        );
+
+        let (events, dropped) = early.finish();
+        let (writer, guard) = capture_logs();
+        replay_events(&events, dropped);
+        drop(guard);
+
+        let output = writer.output();
+        assert!(output.contains("INFO pre_init:"));
+        assert!(output.contains("loading config path=app.toml"));
+    }
+
+    #[test]
+    fn test_buffer_is_bounded() {
+        let early = EarlyLogs::capture(2);
+        for i in 0..5 {
+            tracing::warn!("event {}", i);
+        }
+
+        let messages: Vec<String> = early.events().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, vec!["event 3", "event 4"]);
+        assert_eq!(early.dropped(), 3);
+    }
+}
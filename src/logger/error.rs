@@ -58,13 +58,76 @@ pub fn validate_config(config: &crate::logger::LoggerConfig) -> LoggerResult<()>
         ));
     }
 
+    // File output needs a file name to write to
+    if config.enable_file() && config.log_filename().trim().is_empty() {
+        bail!(LoggerError::InvalidConfiguration(
+            "log_filename must not be empty when file logging is enabled".to_string(),
+        ));
+    }
+
+    // Fields must stay on the event's line
+    let separator = config.field_separator();
+    if separator.is_empty() || separator.contains(['\n', '\r']) {
+        bail!(LoggerError::InvalidConfiguration(
+            "field_separator must not be empty or contain line breaks".to_string(),
+        ));
+    }
+
+    // A rate of 0 would drop every sampled event
+    if config.sample_rate() == Some(0) {
+        bail!(LoggerError::InvalidConfiguration(
+            "sample_rate must be at least 1".to_string(),
+        ));
+    }
+
+    // Only levels from INFO up are sampled
+    if config.sample_threshold() > tracing::Level::INFO {
+        bail!(LoggerError::InvalidConfiguration(
+            "sample_threshold must be INFO, WARN or ERROR".to_string(),
+        ));
+    }
+
     Ok(())
 }
 
+/// Lists configuration options that are valid but have no effect together
+///
+/// Unlike `validate_config`, these combinations do not prevent initialization;
+/// `init` logs each warning once the logger is installed.
+pub fn config_warnings(config: &crate::logger::LoggerConfig) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    // ANSI colors only apply to console output, file output never uses them
+    if config.use_ansi() && !config.enable_console() {
+        warnings.push("use_ansi has no effect when console logging is disabled".to_string());
+    }
+
+    // The JSON format writes plain objects on every output
+    if config.use_ansi()
+        && config.enable_console()
+        && config.format() == crate::logger::LogFormat::Json
+    {
+        warnings.push("use_ansi has no effect with the JSON log format".to_string());
+    }
+
+    // Only the key=value format joins fields with the separator
+    if config.field_separator() != " " && config.format() != crate::logger::LogFormat::KeyValue {
+        warnings
+            .push("field_separator has no effect unless the log format is KeyValue".to_string());
+    }
+
+    // The threshold only narrows down which events are sampled
+    if config.sample_threshold() != tracing::Level::WARN && config.sample_rate().is_none() {
+        warnings.push("sample_threshold has no effect without a sample_rate".to_string());
+    }
+
+    warnings
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::logger::LoggerConfig;
+    use crate::logger::{LogFormat, LoggerConfig};
 
     #[test]
     fn test_error_display() {
@@ -119,7 +182,6 @@ mod tests {
         assert!(error_msg.contains("Must enable at least one"));
     }
 
-
     #[test]
     fn test_validate_config_console_only() {
         let config = LoggerConfig::builder()
@@ -142,6 +204,91 @@ mod tests {
         assert!(validate_config(&config).is_ok());
     }
 
+    #[test]
+    fn test_validate_config_empty_filename() {
+        let config = LoggerConfig::builder()
+            .enable_file(true)
+            .log_filename("  ")
+            .build();
+
+        let error_msg = format!("{}", validate_config(&config).unwrap_err());
+        assert!(error_msg.contains("log_filename must not be empty"));
+
+        // The filename is irrelevant without file output
+        let config = LoggerConfig::builder()
+            .enable_file(false)
+            .log_filename("")
+            .build();
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_config_warnings() {
+        assert!(config_warnings(&LoggerConfig::default()).is_empty());
+
+        let config = LoggerConfig::builder()
+            .enable_console(false)
+            .enable_file(true)
+            .use_ansi(true)
+            .build();
+        let warnings = config_warnings(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("use_ansi"));
+
+        let config = LoggerConfig::builder().format(LogFormat::Json).build();
+        let warnings = config_warnings(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("JSON"));
+
+        let config = LoggerConfig::builder()
+            .format(LogFormat::Json)
+            .use_ansi(false)
+            .build();
+        assert!(config_warnings(&config).is_empty());
+
+        let config = LoggerConfig::builder().field_separator(" | ").build();
+        let warnings = config_warnings(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("field_separator"));
+    }
+
+    #[test]
+    fn test_validate_config_field_separator() {
+        for separator in ["", "\n"] {
+            let config = LoggerConfig::builder()
+                .format(LogFormat::KeyValue)
+                .field_separator(separator)
+                .build();
+
+            let error_msg = format!("{}", validate_config(&config).unwrap_err());
+            assert!(error_msg.contains("field_separator"));
+        }
+    }
+
+    #[test]
+    fn test_validate_config_sampling() {
+        let config = LoggerConfig::builder().sample_rate(10).build();
+        assert!(validate_config(&config).is_ok());
+
+        let config = LoggerConfig::builder().sample_rate(0).build();
+        let error_msg = format!("{}", validate_config(&config).unwrap_err());
+        assert!(error_msg.contains("sample_rate"));
+
+        let config = LoggerConfig::builder()
+            .sample_rate(10)
+            .sample_threshold(tracing::Level::DEBUG)
+            .build();
+        let error_msg = format!("{}", validate_config(&config).unwrap_err());
+        assert!(error_msg.contains("sample_threshold"));
+
+        let config = LoggerConfig::builder()
+            .sample_threshold(tracing::Level::ERROR)
+            .build();
+        let warnings = config_warnings(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("sample_threshold"));
+    }
+
     #[test]
     fn test_anyhow_integration() {
         use anyhow::Context;
@@ -0,0 +1,549 @@
+//! # Logger Format Module
+//!
+//! Provides event formatting options beyond what `tracing_subscriber::fmt` offers out of the box.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use rust_common::logger::{LoggerConfig, TargetDisplay};
+//!
+//! // Render `my_crate::kafka::core::kafka_consumer` as `kafka_consumer`
+//! let config = LoggerConfig::builder()
+//!     .target_display(TargetDisplay::Leaf)
+//!     .build();
+//! ```
+//!
+//! ```rust
+//! use rust_common::logger::{LogFormat, LoggerConfig};
+//!
+//! // One JSON object per line, e.g. for Loki or ELK ingestion
+//! let config = LoggerConfig::builder()
+//!     .format(LogFormat::Json)
+//!     .build();
+//! ```
+//!
+//! ```rust
+//! use rust_common::logger::{LogFormat, LoggerConfig};
+//!
+//! // `message=done | user_id=7 | action=login` on a single line
+//! let config = LoggerConfig::builder()
+//!     .format(LogFormat::KeyValue)
+//!     .field_separator(" | ")
+//!     .build();
+//! ```
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    field::RecordFields,
+    fmt::{
+        format::{JsonFields, Writer},
+        time::{ChronoLocal, FormatTime},
+        FmtContext, FormatEvent, FormatFields, FormattedFields,
+    },
+    registry::LookupSpan,
+};
+
+/// Controls the layout of each log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines with timestamp, level, spans, target and fields
+    #[default]
+    Pretty,
+    /// Shorter human-readable lines, span fields are appended after the event fields
+    Compact,
+    /// One JSON object per line, with event and span fields flattened into the object
+    Json,
+    /// Like `Pretty`, but every field, the message included, is rendered as `key=value`
+    /// joined by the configured field separator, and line breaks in values are escaped so
+    /// each event stays on one line
+    KeyValue,
+}
+
+/// Controls how the event target (module path) is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TargetDisplay {
+    /// The full module path, e.g. `my_crate::kafka::core::kafka_consumer`
+    #[default]
+    Full,
+    /// Only the last path segment, e.g. `kafka_consumer`
+    Leaf,
+    /// No target at all
+    None,
+    /// At most the given number of characters, keeping the end of the path
+    /// and marking the cut with a leading `..`
+    Truncate(usize),
+}
+
+impl TargetDisplay {
+    /// Renders a target according to this display mode
+    ///
+    /// Returns `None` when the target should not be shown.
+    pub fn render<'a>(&self, target: &'a str) -> Option<&'a str> {
+        match self {
+            TargetDisplay::Full => Some(target),
+            TargetDisplay::Leaf => Some(target.rsplit("::").next().unwrap_or(target)),
+            TargetDisplay::None => None,
+            TargetDisplay::Truncate(max_len) => {
+                let char_count = target.chars().count();
+                if char_count <= *max_len {
+                    return Some(target);
+                }
+                let skip = char_count - max_len;
+                let start = target
+                    .char_indices()
+                    .nth(skip)
+                    .map(|(idx, _)| idx)
+                    .unwrap_or(target.len());
+                Some(&target[start..])
+            }
+        }
+    }
+
+    fn is_truncated(&self, target: &str) -> bool {
+        matches!(self, TargetDisplay::Truncate(max_len) if target.chars().count() > *max_len)
+    }
+}
+
+/// Event formatter that renders the target according to a `TargetDisplay`
+///
+/// The line layout follows the default `tracing_subscriber` full format:
+/// timestamp, level, thread, spans, target, file and line, then fields.
+/// With `with_compact`, it follows the compact format instead.
+#[derive(Debug, Clone)]
+pub struct TargetFormat {
+    timer: ChronoLocal,
+    target_display: TargetDisplay,
+    show_file_line: bool,
+    show_thread: bool,
+    compact: bool,
+}
+
+impl TargetFormat {
+    /// Creates a new formatter
+    pub fn new(
+        time_format: &str,
+        target_display: TargetDisplay,
+        show_file_line: bool,
+        show_thread: bool,
+    ) -> Self {
+        Self {
+            timer: ChronoLocal::new(time_format.to_string()),
+            target_display,
+            show_file_line,
+            show_thread,
+            compact: false,
+        }
+    }
+
+    /// Lists spans by name only and appends their fields after the event fields,
+    /// like the `tracing_subscriber` compact format
+    pub fn with_compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    fn write_level(writer: &mut Writer<'_>, level: &Level) -> fmt::Result {
+        if !writer.has_ansi_escapes() {
+            return write!(writer, "{:>5} ", level);
+        }
+
+        let color = match *level {
+            Level::TRACE => "35",
+            Level::DEBUG => "34",
+            Level::INFO => "32",
+            Level::WARN => "33",
+            Level::ERROR => "31",
+        };
+        write!(writer, "\x1b[{}m{:>5}\x1b[0m ", color, level)
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for TargetFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+
+        if self.timer.format_time(&mut writer).is_err() {
+            writer.write_str("<unknown time>")?;
+        }
+        writer.write_char(' ')?;
+
+        Self::write_level(&mut writer, metadata.level())?;
+
+        if self.show_thread {
+            let thread = std::thread::current();
+            if let Some(name) = thread.name() {
+                write!(writer, "{} ", name)?;
+            }
+            write!(writer, "{:?} ", thread.id())?;
+        }
+
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                writer.write_str(span.name())?;
+                let extensions = span.extensions();
+                if let Some(fields) = extensions.get::<FormattedFields<N>>() {
+                    if !fields.is_empty() && !self.compact {
+                        write!(writer, "{{{}}}", fields)?;
+                    }
+                }
+                writer.write_char(':')?;
+            }
+            writer.write_char(' ')?;
+        }
+
+        if let Some(target) = self.target_display.render(metadata.target()) {
+            if self.target_display.is_truncated(metadata.target()) {
+                writer.write_str("..")?;
+            }
+            write!(writer, "{}: ", target)?;
+        }
+
+        if self.show_file_line {
+            if let Some(file) = metadata.file() {
+                write!(writer, "{}:", file)?;
+                if let Some(line) = metadata.line() {
+                    write!(writer, "{}:", line)?;
+                }
+                writer.write_char(' ')?;
+            }
+        }
+
+        ctx.format_fields(writer.by_ref(), event)?;
+
+        if self.compact {
+            if let Some(scope) = ctx.event_scope() {
+                for span in scope.from_root() {
+                    let extensions = span.extensions();
+                    if let Some(fields) = extensions.get::<FormattedFields<N>>() {
+                        if !fields.is_empty() {
+                            write!(writer, " {}", fields)?;
+                        }
+                    }
+                }
+            }
+        }
+        writeln!(writer)
+    }
+}
+
+/// Event formatter that writes each event as a single-line JSON object
+///
+/// Event fields and the fields of all entered spans are flattened into the
+/// object; when names collide, event fields win over span fields and inner
+/// spans win over outer ones. Requires `JsonFields` as the field formatter so
+/// span fields are recorded as JSON.
+#[derive(Debug, Clone)]
+pub struct JsonFormat {
+    timer: ChronoLocal,
+    target_display: TargetDisplay,
+    show_file_line: bool,
+    show_thread: bool,
+}
+
+impl JsonFormat {
+    /// Creates a new formatter
+    pub fn new(
+        time_format: &str,
+        target_display: TargetDisplay,
+        show_file_line: bool,
+        show_thread: bool,
+    ) -> Self {
+        Self {
+            timer: ChronoLocal::new(time_format.to_string()),
+            target_display,
+            show_file_line,
+            show_thread,
+        }
+    }
+
+    fn parse_fields(fields: &str) -> Map<String, Value> {
+        match serde_json::from_str(fields) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+}
+
+impl<S> FormatEvent<S, JsonFields> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut object = Map::new();
+
+        let mut timestamp = String::new();
+        if self
+            .timer
+            .format_time(&mut Writer::new(&mut timestamp))
+            .is_ok()
+        {
+            object.insert("timestamp".to_string(), Value::String(timestamp));
+        }
+        object.insert(
+            "level".to_string(),
+            Value::String(metadata.level().to_string()),
+        );
+
+        if let Some(target) = self.target_display.render(metadata.target()) {
+            let target = if self.target_display.is_truncated(metadata.target()) {
+                format!("..{}", target)
+            } else {
+                target.to_string()
+            };
+            object.insert("target".to_string(), Value::String(target));
+        }
+
+        if self.show_file_line {
+            if let Some(file) = metadata.file() {
+                object.insert("filename".to_string(), Value::String(file.to_string()));
+            }
+            if let Some(line) = metadata.line() {
+                object.insert("line_number".to_string(), Value::from(line));
+            }
+        }
+
+        if self.show_thread {
+            let thread = std::thread::current();
+            if let Some(name) = thread.name() {
+                object.insert("threadName".to_string(), Value::String(name.to_string()));
+            }
+            object.insert(
+                "threadId".to_string(),
+                Value::String(format!("{:?}", thread.id())),
+            );
+        }
+
+        if let Some(scope) = ctx.event_scope() {
+            let mut spans = Vec::new();
+            for span in scope.from_root() {
+                spans.push(Value::String(span.name().to_string()));
+                let extensions = span.extensions();
+                if let Some(fields) = extensions.get::<FormattedFields<JsonFields>>() {
+                    object.extend(Self::parse_fields(fields));
+                }
+            }
+            object.insert("spans".to_string(), Value::Array(spans));
+        }
+
+        let mut fields = String::new();
+        ctx.format_fields(Writer::new(&mut fields), event)?;
+        object.extend(Self::parse_fields(&fields));
+
+        let line = serde_json::to_string(&object).map_err(|_| fmt::Error)?;
+        writeln!(writer, "{}", line)
+    }
+}
+
+/// Field formatter that renders fields as `key=value` pairs on a single line
+///
+/// Values containing whitespace, quotes, `=`, line breaks or the separator are
+/// quoted and escaped like a Rust string literal.
+#[derive(Debug, Clone)]
+pub struct KeyValueFields {
+    separator: String,
+}
+
+impl KeyValueFields {
+    /// Creates a new formatter joining fields with `separator`
+    pub fn new(separator: impl Into<String>) -> Self {
+        Self {
+            separator: separator.into(),
+        }
+    }
+}
+
+impl<'writer> FormatFields<'writer> for KeyValueFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = KeyValueVisitor {
+            separator: &self.separator,
+            line: String::new(),
+        };
+        fields.record(&mut visitor);
+        writer.write_str(&visitor.line)
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &span::Record<'_>,
+    ) -> fmt::Result {
+        if !current.fields.is_empty() {
+            current.fields.push_str(&self.separator);
+        }
+        self.format_fields(current.as_writer(), fields)
+    }
+}
+
+struct KeyValueVisitor<'a> {
+    separator: &'a str,
+    line: String,
+}
+
+impl KeyValueVisitor<'_> {
+    fn push(&mut self, field: &Field, value: &str) {
+        if !self.line.is_empty() {
+            self.line.push_str(self.separator);
+        }
+
+        let quote = value.is_empty()
+            || value
+                .chars()
+                .any(|c| c.is_whitespace() || c == '"' || c == '=')
+            || (!self.separator.trim().is_empty() && value.contains(self.separator.trim()));
+        if quote {
+            self.line.push_str(&format!("{}={:?}", field.name(), value));
+        } else {
+            self.line.push_str(&format!("{}={}", field.name(), value));
+        }
+    }
+}
+
+impl Visit for KeyValueVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.push(field, &format!("{:?}", value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::CaptureWriter;
+    use tracing_subscriber::{fmt, prelude::*};
+
+    #[test]
+    fn test_render_modes() {
+        let target = "my_crate::kafka::core::kafka_consumer";
+
+        assert_eq!(TargetDisplay::Full.render(target), Some(target));
+        assert_eq!(TargetDisplay::Leaf.render(target), Some("kafka_consumer"));
+        assert_eq!(TargetDisplay::None.render(target), None);
+        assert_eq!(
+            TargetDisplay::Truncate(20).render(target),
+            Some("core::kafka_consumer")
+        );
+        assert_eq!(TargetDisplay::Truncate(100).render(target), Some(target));
+        assert_eq!(TargetDisplay::Leaf.render("main"), Some("main"));
+    }
+
+    #[test]
+    fn test_leaf_target_in_formatted_event() {
+        let writer = CaptureWriter::new();
+
+        let subscriber = tracing_subscriber::registry().with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer.clone())
+                .event_format(TargetFormat::new(
+                    "%H:%M:%S",
+                    TargetDisplay::Leaf,
+                    false,
+                    false,
+                )),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "my_crate::kafka::core::kafka_consumer", "consumer ready");
+        });
+
+        let output = writer.output();
+        assert!(output.contains(" INFO kafka_consumer: consumer ready"));
+        assert!(!output.contains("my_crate::kafka"));
+    }
+
+    #[test]
+    fn test_key_value_fields_render_on_one_line() {
+        let writer = CaptureWriter::new();
+
+        let subscriber = tracing_subscriber::registry().with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer.clone())
+                .with_target(false)
+                .without_time()
+                .fmt_fields(KeyValueFields::new(" | ")),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(
+                user_id = 7,
+                action = "login",
+                note = "two\nlines",
+                "signed in"
+            );
+        });
+
+        let output = writer.output();
+        assert_eq!(output.lines().count(), 1);
+        assert!(output
+            .contains(r#"message="signed in" | user_id=7 | action=login | note="two\nlines""#));
+    }
+
+    #[test]
+    fn test_json_format_flattens_span_fields() {
+        let writer = CaptureWriter::new();
+
+        let subscriber = tracing_subscriber::registry().with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer.clone())
+                .fmt_fields(JsonFields::new())
+                .event_format(JsonFormat::new(
+                    "%Y-%m-%d",
+                    TargetDisplay::Leaf,
+                    false,
+                    false,
+                )),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("message", transaction_id = "tx-1", uri = "/a");
+            let _guard = span.enter();
+            tracing::info!(target: "my_crate::kafka::stream_handler", uri = "/b", took_ms = 12, "handled");
+        });
+
+        let output = writer.output();
+        assert_eq!(output.lines().count(), 1);
+
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "stream_handler");
+        assert_eq!(line["message"], "handled");
+        assert_eq!(line["transaction_id"], "tx-1");
+        assert_eq!(line["uri"], "/b");
+        assert_eq!(line["took_ms"], 12);
+        assert_eq!(line["spans"], serde_json::json!(["message"]));
+        assert_eq!(
+            line["timestamp"],
+            chrono::Local::now().format("%Y-%m-%d").to_string()
+        );
+    }
+}
@@ -35,11 +35,30 @@
 //! # }
 //! ```
 
-use crate::logger::{error::validate_config, LoggerConfig, LoggerError, LoggerResult};
+use crate::logger::{
+    error::{config_warnings, validate_config},
+    JsonFormat, KeyValueFields, LogFormat, LoggerConfig, LoggerError, LoggerResult, ReloadHandle,
+    SamplingLayer, TargetDisplay, TargetFormat,
+};
 use anyhow::Context;
 use std::io;
 use tracing_appender::rolling::RollingFileAppender;
-use tracing_subscriber::{fmt, prelude::*, registry, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{
+    fmt::{
+        self,
+        format::{DefaultFields, Format, Full, JsonFields},
+        time::ChronoLocal,
+        writer::BoxMakeWriter,
+        MakeWriter,
+    },
+    prelude::*,
+    registry, reload,
+    util::SubscriberInitExt,
+    EnvFilter, Registry,
+};
+
+/// The log filter, wrapped so it can be replaced through a `ReloadHandle`
+type FilterLayer = reload::Layer<EnvFilter, Registry>;
 
 /// Initializes the logger with default configuration
 ///
@@ -48,7 +67,8 @@ use tracing_subscriber::{fmt, prelude::*, registry, util::SubscriberInitExt, Env
 ///
 /// # Returns
 ///
-/// `LoggerResult<()>` - Ok(()) if initialization succeeds, Err otherwise
+/// `LoggerResult<ReloadHandle>` - A handle to change the log level at runtime if
+/// initialization succeeds, Err otherwise
 ///
 /// # Examples
 ///
@@ -61,7 +81,7 @@ use tracing_subscriber::{fmt, prelude::*, registry, util::SubscriberInitExt, Env
 /// info!("Logger is ready!");
 /// # Ok::<(), anyhow::Error>(())
 /// ```
-pub fn init_with_default() -> LoggerResult<()> {
+pub fn init_with_default() -> LoggerResult<ReloadHandle> {
     let config = LoggerConfig::default();
     init(config)
 }
@@ -77,7 +97,8 @@ pub fn init_with_default() -> LoggerResult<()> {
 ///
 /// # Returns
 ///
-/// `LoggerResult<()>` - Ok(()) if initialization succeeds, Err otherwise
+/// `LoggerResult<ReloadHandle>` - A handle to change the log level at runtime if
+/// initialization succeeds, Err otherwise
 ///
 /// # Examples
 ///
@@ -98,46 +119,60 @@ pub fn init_with_default() -> LoggerResult<()> {
 /// info!("Logger initialized successfully");
 /// # Ok::<(), anyhow::Error>(())
 /// ```
-pub fn init(config: LoggerConfig) -> LoggerResult<()> {
+pub fn init(config: LoggerConfig) -> LoggerResult<ReloadHandle> {
     // Validate configuration first
     validate_config(&config)?;
 
     // Create environment filter
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(config.default_level().as_str()));
+    let (env_filter, reload_handle) = reload::Layer::new(env_filter);
+    let sampling = config
+        .sample_rate()
+        .map(|rate| SamplingLayer::new(rate, config.sample_threshold()));
 
     // Time format for logs
     let time_format = "%Y-%m-%d %H:%M:%S%.3f";
 
     // Initialize based on enabled outputs
-    match (config.enable_file(), config.enable_console()) {
-        (true, true) => init_with_both_outputs(&config, env_filter, time_format)
+    let result = match (config.enable_file(), config.enable_console()) {
+        (true, true) => init_with_both_outputs(&config, env_filter, sampling, time_format)
             .context("Failed to initialize logger with both file and console output"),
-        (true, false) => init_with_file_output(&config, env_filter, time_format)
+        (true, false) => init_with_file_output(&config, env_filter, sampling, time_format)
             .context("Failed to initialize logger with file output"),
-        (false, true) => init_with_console_output(&config, env_filter, time_format)
+        (false, true) => init_with_console_output(&config, env_filter, sampling, time_format)
             .context("Failed to initialize logger with console output"),
         (false, false) => Err(LoggerError::InvalidConfiguration(
             "Must enable at least one of file or console logging".to_string(),
         )
         .into()),
+    };
+
+    // Report ineffective options through the logger that was just installed
+    if result.is_ok() {
+        for warning in config_warnings(&config) {
+            tracing::warn!("{}", warning);
+        }
     }
+
+    result.map(|()| ReloadHandle::new(reload_handle))
 }
 
 /// Initializes logger with both file and console output
 fn init_with_both_outputs(
     config: &LoggerConfig,
-    env_filter: EnvFilter,
+    env_filter: FilterLayer,
+    sampling: Option<SamplingLayer>,
     time_format: &str,
 ) -> LoggerResult<()> {
-    let file_appender =
-        RollingFileAppender::new(config.rotation(), config.log_dir(), config.log_filename());
+    let file_appender = create_file_appender(config)?;
 
     let file_layer = create_file_layer(file_appender, config, time_format);
     let console_layer = create_console_layer(config, time_format);
 
     registry()
         .with(env_filter)
+        .with(sampling)
         .with(file_layer)
         .with(console_layer)
         .try_init()
@@ -147,16 +182,17 @@ fn init_with_both_outputs(
 /// Initializes logger with file output only
 fn init_with_file_output(
     config: &LoggerConfig,
-    env_filter: EnvFilter,
+    env_filter: FilterLayer,
+    sampling: Option<SamplingLayer>,
     time_format: &str,
 ) -> LoggerResult<()> {
-    let file_appender =
-        RollingFileAppender::new(config.rotation(), config.log_dir(), config.log_filename());
+    let file_appender = create_file_appender(config)?;
 
     let file_layer = create_file_layer(file_appender, config, time_format);
 
     registry()
         .with(env_filter)
+        .with(sampling)
         .with(file_layer)
         .try_init()
         .map_err(|e| LoggerError::TracingError(e).into())
@@ -165,43 +201,65 @@ fn init_with_file_output(
 /// Initializes logger with console output only
 fn init_with_console_output(
     config: &LoggerConfig,
-    env_filter: EnvFilter,
+    env_filter: FilterLayer,
+    sampling: Option<SamplingLayer>,
     time_format: &str,
 ) -> LoggerResult<()> {
     let console_layer = create_console_layer(config, time_format);
 
     registry()
         .with(env_filter)
+        .with(sampling)
         .with(console_layer)
         .try_init()
         .map_err(|e| LoggerError::TracingError(e).into())
 }
 
+/// Creates the file appender, applying the configured file mode on Unix
+fn create_file_appender(config: &LoggerConfig) -> LoggerResult<BoxMakeWriter> {
+    #[cfg(unix)]
+    if let Some(mode) = config.file_mode() {
+        let appender = crate::logger::FileModeAppender::new(
+            config.rotation(),
+            config.log_dir(),
+            config.log_filename(),
+            mode,
+        )
+        .context("Failed to set log file mode")?;
+        return Ok(BoxMakeWriter::new(appender));
+    }
+
+    let file_appender =
+        RollingFileAppender::new(config.rotation(), config.log_dir(), config.log_filename());
+    Ok(BoxMakeWriter::new(file_appender))
+}
+
 /// Creates a file logging layer
-fn create_file_layer<S>(
-    file_appender: RollingFileAppender,
+fn create_file_layer<S, W>(
+    file_appender: W,
     config: &LoggerConfig,
     time_format: &str,
 ) -> Box<dyn tracing_subscriber::Layer<S> + Send + Sync + 'static>
 where
     S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
 {
-    Box::new(
-        fmt::layer()
-            .with_writer(file_appender)
-            .with_ansi(false) // No ANSI colors in files
-            .with_file(config.show_file_line())
-            .with_line_number(config.show_file_line())
-            .with_thread_ids(config.show_thread())
-            .with_thread_names(config.show_thread())
-            .with_target(config.show_target())
-            .with_span_events(if config.show_spans() {
-                fmt::format::FmtSpan::FULL
-            } else {
-                fmt::format::FmtSpan::NONE
-            })
-            .with_timer(fmt::time::ChronoLocal::new(time_format.to_string())),
-    )
+    let layer = fmt::layer()
+        .with_writer(file_appender)
+        .with_ansi(false) // No ANSI colors in files
+        .with_file(config.show_file_line())
+        .with_line_number(config.show_file_line())
+        .with_thread_ids(config.show_thread())
+        .with_thread_names(config.show_thread())
+        .with_target(config.show_target())
+        .with_span_events(if config.show_spans() {
+            fmt::format::FmtSpan::FULL
+        } else {
+            fmt::format::FmtSpan::NONE
+        })
+        .with_timer(ChronoLocal::new(time_format.to_string()));
+
+    apply_format(layer, config, time_format)
 }
 
 /// Creates a console logging layer
@@ -212,22 +270,78 @@ fn create_console_layer<S>(
 where
     S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
 {
-    Box::new(
-        fmt::layer()
-            .with_writer(io::stdout)
-            .with_ansi(config.use_ansi())
-            .with_file(config.show_file_line())
-            .with_line_number(config.show_file_line())
-            .with_thread_ids(config.show_thread())
-            .with_thread_names(config.show_thread())
-            .with_target(config.show_target())
-            .with_span_events(if config.show_spans() {
-                fmt::format::FmtSpan::FULL
-            } else {
-                fmt::format::FmtSpan::NONE
-            })
-            .with_timer(fmt::time::ChronoLocal::new(time_format.to_string())),
-    )
+    let layer = fmt::layer()
+        .with_writer(io::stdout)
+        .with_ansi(config.use_ansi())
+        .with_file(config.show_file_line())
+        .with_line_number(config.show_file_line())
+        .with_thread_ids(config.show_thread())
+        .with_thread_names(config.show_thread())
+        .with_target(config.show_target())
+        .with_span_events(if config.show_spans() {
+            fmt::format::FmtSpan::FULL
+        } else {
+            fmt::format::FmtSpan::NONE
+        })
+        .with_timer(ChronoLocal::new(time_format.to_string()));
+
+    apply_format(layer, config, time_format)
+}
+
+/// Applies the configured `LogFormat` to a layer and boxes it
+fn apply_format<S, W>(
+    layer: fmt::Layer<S, DefaultFields, Format<Full, ChronoLocal>, W>,
+    config: &LoggerConfig,
+    time_format: &str,
+) -> Box<dyn tracing_subscriber::Layer<S> + Send + Sync + 'static>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match config.format() {
+        LogFormat::Json => Box::new(
+            layer
+                .with_ansi(false)
+                .fmt_fields(JsonFields::new())
+                .event_format(JsonFormat::new(
+                    time_format,
+                    config.target_display(),
+                    config.show_file_line(),
+                    config.show_thread(),
+                )),
+        ),
+        LogFormat::Compact => match create_target_format(config, time_format) {
+            Some(format) => Box::new(layer.event_format(format.with_compact(true))),
+            None => Box::new(layer.compact()),
+        },
+        LogFormat::KeyValue => {
+            let layer = layer.fmt_fields(KeyValueFields::new(config.field_separator()));
+            match create_target_format(config, time_format) {
+                Some(format) => Box::new(layer.event_format(format)),
+                None => Box::new(layer),
+            }
+        }
+        LogFormat::Pretty => match create_target_format(config, time_format) {
+            Some(format) => Box::new(layer.event_format(format)),
+            None => Box::new(layer),
+        },
+    }
+}
+
+/// Creates a custom event format when the target display needs one
+///
+/// `Full` and `None` are handled by the default formatter, so only the
+/// shortened target modes need a custom format.
+fn create_target_format(config: &LoggerConfig, time_format: &str) -> Option<TargetFormat> {
+    match config.target_display() {
+        TargetDisplay::Full | TargetDisplay::None => None,
+        display => Some(TargetFormat::new(
+            time_format,
+            display,
+            config.show_file_line(),
+            config.show_thread(),
+        )),
+    }
 }
 
 /// Checks if a logger has already been initialized
@@ -245,6 +359,7 @@ pub fn is_initialized() -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::CaptureWriter;
     use tracing::Level;
     use tracing_appender::rolling::Rotation;
 
@@ -318,6 +433,59 @@ mod tests {
         // If we get here without panicking, the layer was created successfully
     }
 
+    #[test]
+    fn test_create_layers_for_each_format() {
+        for format in [
+            LogFormat::Pretty,
+            LogFormat::Compact,
+            LogFormat::Json,
+            LogFormat::KeyValue,
+        ] {
+            let config = LoggerConfig::builder()
+                .format(format)
+                .target_display(TargetDisplay::Leaf)
+                .build();
+
+            let _layer: Box<dyn tracing_subscriber::Layer<registry::Registry> + Send + Sync> =
+                create_console_layer(&config, "%Y-%m-%d %H:%M:%S");
+
+            let writer = CaptureWriter::new();
+            let layer = fmt::layer()
+                .with_writer(writer.clone())
+                .with_ansi(false)
+                .with_timer(ChronoLocal::new("%H:%M:%S".to_string()));
+            let subscriber = registry().with(apply_format(layer, &config, "%H:%M:%S"));
+
+            tracing::subscriber::with_default(subscriber, || {
+                let span = tracing::info_span!("message", transaction_id = "tx-1");
+                let _guard = span.enter();
+                tracing::info!(target: "my_crate::kafka::stream_handler", "handled");
+            });
+
+            let output = writer.output();
+            assert!(
+                output.contains("stream_handler"),
+                "{:?}: {}",
+                format,
+                output
+            );
+            assert!(
+                !output.contains("my_crate::kafka"),
+                "{:?}: {}",
+                format,
+                output
+            );
+            assert!(output.contains("tx-1"), "{:?}: {}", format, output);
+            if format == LogFormat::Compact {
+                assert!(
+                    output.contains(r#"handled transaction_id="tx-1""#),
+                    "{}",
+                    output
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_is_initialized() {
         // This function should work regardless of initialization state
@@ -7,6 +7,8 @@
 //! - Structured logging with tracing
 //! - File and console output support
 //! - Configurable log levels and formatting
+//! - Runtime log level reloading
+//! - Sampling of high-volume log lines
 //! - Rolling file appenders
 //! - Async-friendly logging
 //! - Custom error types
@@ -51,14 +53,26 @@
 //! # }
 //! ```
 
+#[cfg(unix)]
+pub mod appender;
 pub mod config;
+pub mod early;
 pub mod error;
+pub mod format;
 pub mod init;
+pub mod reload;
+pub mod sampling;
 
 // Re-export main types and functions
+#[cfg(unix)]
+pub use appender::*;
 pub use config::*;
+pub use early::*;
 pub use error::*;
+pub use format::*;
 pub use init::*;
+pub use reload::*;
+pub use sampling::*;
 
 #[cfg(test)]
 mod tests {
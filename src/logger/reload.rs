@@ -0,0 +1,125 @@
+//! # Logger Reload Module
+//!
+//! Provides a handle to change the log filter of an initialized logger at runtime.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use rust_common::logger;
+//! use tracing::Level;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let reload = logger::init_with_default()?;
+//!
+//! // Temporarily log everything at DEBUG, then narrow it down to one module
+//! reload.set_level(Level::DEBUG)?;
+//! reload.set_filter("info,rust_common::kafka=debug")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::logger::{LoggerError, LoggerResult};
+use tracing::Level;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Handle to the filter of an initialized logger
+///
+/// Returned by `init` and `init_with_default`. Clones share the same filter and
+/// can be used from any thread.
+#[derive(Clone, Debug)]
+pub struct ReloadHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl ReloadHandle {
+    /// Wraps the handle of the reloadable filter layer
+    pub(crate) fn new(handle: reload::Handle<EnvFilter, Registry>) -> Self {
+        Self { handle }
+    }
+
+    /// Replaces the filter with a single level applied to every target
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The most verbose level to log
+    ///
+    /// # Returns
+    ///
+    /// `LoggerResult<()>` - Ok(()) if the filter was replaced, Err if the logger is gone
+    pub fn set_level(&self, level: Level) -> LoggerResult<()> {
+        self.reload(EnvFilter::new(level.as_str()))
+    }
+
+    /// Replaces the filter with an `EnvFilter` directive string
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Directives in `RUST_LOG` syntax, e.g. `info,rust_common::kafka=debug`
+    ///
+    /// # Returns
+    ///
+    /// `LoggerResult<()>` - Ok(()) if the filter was replaced, Err if the directives are
+    /// invalid or the logger is gone
+    pub fn set_filter(&self, filter: &str) -> LoggerResult<()> {
+        let filter = EnvFilter::try_new(filter).map_err(|e| {
+            LoggerError::InvalidConfiguration(format!("invalid log filter '{}': {}", filter, e))
+        })?;
+        self.reload(filter)
+    }
+
+    /// Returns the active filter directives, None if the logger is gone
+    pub fn current_filter(&self) -> Option<String> {
+        self.handle.with_current(|filter| filter.to_string()).ok()
+    }
+
+    fn reload(&self, filter: EnvFilter) -> LoggerResult<()> {
+        self.handle.reload(filter).map_err(|e| {
+            LoggerError::InitializationFailed(format!("failed to reload log filter: {}", e)).into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    fn reloadable(level: Level) -> (impl tracing::Subscriber, ReloadHandle) {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new(level.as_str()));
+        (
+            tracing_subscriber::registry().with(layer),
+            ReloadHandle::new(handle),
+        )
+    }
+
+    #[test]
+    fn test_set_level_changes_enabled_events() {
+        let (subscriber, reload) = reloadable(Level::INFO);
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(Level::DEBUG));
+
+            let cloned = reload.clone();
+            std::thread::spawn(move || cloned.set_level(Level::DEBUG).unwrap())
+                .join()
+                .unwrap();
+
+            assert!(tracing::enabled!(Level::DEBUG));
+            assert_eq!(reload.current_filter().as_deref(), Some("debug"));
+        });
+    }
+
+    #[test]
+    fn test_set_filter_rejects_invalid_directives() {
+        let (_subscriber, reload) = reloadable(Level::INFO);
+
+        reload.set_filter("info,rust_common::kafka=debug").unwrap();
+        assert_eq!(
+            reload.current_filter().as_deref(),
+            Some("rust_common::kafka=debug,info")
+        );
+
+        let error = reload.set_filter("info,kafka=loud").unwrap_err();
+        assert!(error.to_string().contains("invalid log filter"));
+    }
+}
@@ -0,0 +1,171 @@
+//! # Logger Sampling Module
+//!
+//! Provides a layer that keeps only 1 in N events of each call site, so hot
+//! code paths logging the same line on every iteration do not flood the output.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use rust_common::logger::{self, LoggerConfig};
+//! use tracing::Level;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! // Keep every 100th INFO and WARN event of each call site
+//! let config = LoggerConfig::builder().sample_rate(100).build();
+//! logger::init(config)?;
+//!
+//! // Also sample ERROR events
+//! let config = LoggerConfig::builder()
+//!     .sample_rate(100)
+//!     .sample_threshold(Level::ERROR)
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+};
+
+use tracing::{callsite::Identifier, Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Layer dropping all but 1 in N events of each call site
+///
+/// Only events from INFO up to the threshold severity are sampled; DEBUG and
+/// TRACE events are only enabled while diagnosing and are always kept, as are
+/// events more severe than the threshold. The first event of a call site is
+/// always kept.
+#[derive(Debug)]
+pub struct SamplingLayer {
+    rate: u64,
+    threshold: Level,
+    counters: RwLock<HashMap<Identifier, AtomicU64>>,
+}
+
+impl SamplingLayer {
+    /// Creates a layer keeping 1 in `rate` events, a rate of 0 is treated as 1
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - Keep one event out of this many per call site
+    /// * `threshold` - The most severe level that is sampled, e.g. `Level::WARN`
+    pub fn new(rate: u32, threshold: Level) -> Self {
+        Self {
+            rate: u64::from(rate.max(1)),
+            threshold,
+            counters: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Checks whether events at a level are subject to sampling
+    fn is_sampled(&self, level: &Level) -> bool {
+        *level <= Level::INFO && *level >= self.threshold
+    }
+
+    /// Counts an event of a call site and decides whether it is kept
+    fn keep(&self, callsite: Identifier) -> bool {
+        if let Ok(counters) = self.counters.read() {
+            if let Some(counter) = counters.get(&callsite) {
+                return counter.fetch_add(1, Ordering::Relaxed) % self.rate == 0;
+            }
+        }
+
+        let Ok(mut counters) = self.counters.write() else {
+            return true;
+        };
+        let counter = counters
+            .entry(callsite)
+            .or_insert_with(|| AtomicU64::new(0));
+        counter.fetch_add(1, Ordering::Relaxed) % self.rate == 0
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SamplingLayer {
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let metadata = event.metadata();
+        if self.rate == 1 || !self.is_sampled(metadata.level()) {
+            return true;
+        }
+
+        self.keep(metadata.callsite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::prelude::*;
+
+    /// Records the level of every event that reaches it
+    struct LevelRecorder(Arc<Mutex<Vec<Level>>>);
+
+    impl<S: Subscriber> Layer<S> for LevelRecorder {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.lock().unwrap().push(*event.metadata().level());
+        }
+    }
+
+    fn record(layer: SamplingLayer, emit: impl FnOnce()) -> Vec<Level> {
+        let levels = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry()
+            .with(LevelRecorder(levels.clone()))
+            .with(layer);
+
+        tracing::subscriber::with_default(subscriber, emit);
+
+        let levels = levels.lock().unwrap().clone();
+        levels
+    }
+
+    fn count(levels: &[Level], level: Level) -> usize {
+        levels.iter().filter(|recorded| **recorded == level).count()
+    }
+
+    #[test]
+    fn test_samples_info_and_warn_by_default() {
+        let levels = record(SamplingLayer::new(3, Level::WARN), || {
+            for i in 0..10 {
+                tracing::debug!("debug {}", i);
+                tracing::info!("info {}", i);
+                tracing::warn!("warn {}", i);
+                tracing::error!("error {}", i);
+            }
+        });
+
+        assert_eq!(count(&levels, Level::DEBUG), 10);
+        assert_eq!(count(&levels, Level::INFO), 4);
+        assert_eq!(count(&levels, Level::WARN), 4);
+        assert_eq!(count(&levels, Level::ERROR), 10);
+    }
+
+    #[test]
+    fn test_counts_each_call_site_separately() {
+        let levels = record(SamplingLayer::new(5, Level::INFO), || {
+            for _ in 0..5 {
+                tracing::info!("first");
+                tracing::info!("second");
+                tracing::warn!("not sampled");
+            }
+        });
+
+        assert_eq!(count(&levels, Level::INFO), 2);
+        assert_eq!(count(&levels, Level::WARN), 5);
+    }
+
+    #[test]
+    fn test_threshold_error_samples_errors() {
+        let levels = record(SamplingLayer::new(2, Level::ERROR), || {
+            for _ in 0..4 {
+                tracing::error!("error");
+            }
+        });
+
+        assert_eq!(count(&levels, Level::ERROR), 2);
+    }
+}
@@ -0,0 +1,180 @@
+//! # Arithmetic Module
+//!
+//! Provides gcd and overflow-safe lcm over `u64`, and overflow-checked arithmetic over any
+//! integer type implementing the `num-traits` checked operation traits, e.g. `i64`, `u32` or
+//! `usize`.
+//!
+//! Plain integer operators wrap silently in release builds and panic on division by zero;
+//! these functions return None instead, so the caller decides how to handle the failure.
+//!
+//! The generic checked operations are only available with the `num` feature enabled.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use rust_common::math::arithmetic;
+//!
+//! assert_eq!(arithmetic::gcd(12, 18), 6);
+//! assert_eq!(arithmetic::lcm(4, 6), 12);
+//! assert_eq!(arithmetic::checked_lcm(u64::MAX, u64::MAX - 1), None);
+//! ```
+//!
+//! With the `num` feature:
+//!
+//! ```rust
+//! # #[cfg(feature = "num")]
+//! # {
+//! use rust_common::math::arithmetic;
+//!
+//! assert_eq!(arithmetic::checked_add(2_i64, 3), Some(5));
+//! assert_eq!(arithmetic::checked_mul(i64::MAX, 2), None);
+//! assert_eq!(arithmetic::checked_div(7_u32, 0), None);
+//! # }
+//! ```
+
+#[cfg(feature = "num")]
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedRem, CheckedSub};
+
+/// Adds two integers.
+///
+/// # Returns
+///
+/// * `Option<T>` - The sum, or None if it overflows `T`.
+#[cfg(feature = "num")]
+pub fn checked_add<T: CheckedAdd>(a: T, b: T) -> Option<T> {
+    a.checked_add(&b)
+}
+
+/// Subtracts `b` from `a`.
+///
+/// # Returns
+///
+/// * `Option<T>` - The difference, or None if it overflows `T`.
+#[cfg(feature = "num")]
+pub fn checked_sub<T: CheckedSub>(a: T, b: T) -> Option<T> {
+    a.checked_sub(&b)
+}
+
+/// Multiplies two integers.
+///
+/// # Returns
+///
+/// * `Option<T>` - The product, or None if it overflows `T`.
+#[cfg(feature = "num")]
+pub fn checked_mul<T: CheckedMul>(a: T, b: T) -> Option<T> {
+    a.checked_mul(&b)
+}
+
+/// Divides `a` by `b`, truncating toward zero.
+///
+/// # Returns
+///
+/// * `Option<T>` - The quotient, or None if `b` is zero or the result overflows
+///   (e.g. `i64::MIN / -1`).
+#[cfg(feature = "num")]
+pub fn checked_div<T: CheckedDiv>(a: T, b: T) -> Option<T> {
+    a.checked_div(&b)
+}
+
+/// Computes the remainder of `a` divided by `b`, with the sign of `a`.
+///
+/// # Returns
+///
+/// * `Option<T>` - The remainder, or None if `b` is zero or the division overflows.
+#[cfg(feature = "num")]
+pub fn checked_modulo<T: CheckedRem>(a: T, b: T) -> Option<T> {
+    a.checked_rem(&b)
+}
+
+/// Computes the greatest common divisor of two integers.
+///
+/// # Returns
+///
+/// * `u64` - The greatest common divisor, `gcd(0, 0) = 0`.
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Computes the least common multiple of two integers.
+///
+/// `a` is divided by the gcd before multiplying, so only results that do not fit in `u64`
+/// overflow.
+///
+/// # Returns
+///
+/// * `u64` - The least common multiple, 0 when either input is 0.
+///
+/// # Panics
+///
+/// Panics in debug builds if the result overflows `u64`; see `checked_lcm`.
+pub fn lcm(a: u64, b: u64) -> u64 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b)) * b
+}
+
+/// Computes the least common multiple of two integers.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The least common multiple (0 when either input is 0), or None if it
+///   overflows `u64`.
+pub fn checked_lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "num")]
+    fn test_in_range() {
+        assert_eq!(checked_add(2_i64, 3), Some(5));
+        assert_eq!(checked_sub(2_i32, 3), Some(-1));
+        assert_eq!(checked_mul(6_u8, 7), Some(42));
+        assert_eq!(checked_div(-7_i64, 2), Some(-3));
+        assert_eq!(checked_modulo(-7_i64, 2), Some(-1));
+    }
+
+    #[test]
+    #[cfg(feature = "num")]
+    fn test_overflow_and_zero_divisor() {
+        assert_eq!(checked_add(i64::MAX, 1), None);
+        assert_eq!(checked_sub(0_u32, 1), None);
+        assert_eq!(checked_mul(i64::MAX, 2), None);
+        assert_eq!(checked_div(i64::MIN, -1), None);
+        assert_eq!(checked_div(7_usize, 0), None);
+        assert_eq!(checked_modulo(7_i32, 0), None);
+    }
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(0, 6), 0);
+        assert_eq!(checked_lcm(0, 0), Some(0));
+    }
+
+    #[test]
+    fn test_lcm_of_large_inputs() {
+        // a * b overflows u64 although the result fits
+        assert_eq!(lcm(1_000_000_000, 999_999_999), 999_999_999_000_000_000);
+        assert_eq!(
+            checked_lcm(1_000_000_000, 999_999_999),
+            Some(999_999_999_000_000_000)
+        );
+        assert_eq!(lcm(u64::MAX, u64::MAX), u64::MAX);
+        assert_eq!(lcm(1 << 63, 1 << 40), 1 << 63);
+
+        assert_eq!(checked_lcm(u64::MAX, u64::MAX - 1), None);
+    }
+}
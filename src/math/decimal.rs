@@ -0,0 +1,137 @@
+//! # Decimal Module
+//!
+//! Provides exact base-10 arithmetic for currency and other values where
+//! binary floating point drift is not acceptable.
+//!
+//! This module is only available with the `decimal` feature enabled.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use rust_common::math::decimal;
+//!
+//! let a = decimal::from_str("0.1")?;
+//! let b = decimal::from_str("0.2")?;
+//!
+//! assert_eq!(decimal::add(a, b), decimal::from_str("0.3")?);
+//! assert_eq!(decimal::to_string(decimal::round_to(decimal::from_str("2.345")?, 2)), "2.35");
+//! # Ok::<(), rust_decimal::Error>(())
+//! ```
+
+use std::str::FromStr;
+
+pub use rust_decimal::Decimal;
+use rust_decimal::RoundingStrategy;
+
+/// Adds two decimal values.
+///
+/// # Panics
+///
+/// Panics if the result overflows the `Decimal` range.
+pub fn add(a: Decimal, b: Decimal) -> Decimal {
+    a + b
+}
+
+/// Subtracts `b` from `a`.
+///
+/// # Panics
+///
+/// Panics if the result overflows the `Decimal` range.
+pub fn sub(a: Decimal, b: Decimal) -> Decimal {
+    a - b
+}
+
+/// Multiplies two decimal values.
+///
+/// # Panics
+///
+/// Panics if the result overflows the `Decimal` range.
+pub fn mul(a: Decimal, b: Decimal) -> Decimal {
+    a * b
+}
+
+/// Divides `a` by `b`.
+///
+/// # Returns
+///
+/// * `Option<Decimal>` - The quotient, or None if `b` is zero or the result overflows.
+pub fn div(a: Decimal, b: Decimal) -> Option<Decimal> {
+    a.checked_div(b)
+}
+
+/// Rounds a value to the given number of decimal places.
+///
+/// Midpoints are rounded away from zero (`2.345` becomes `2.35`, `-2.345`
+/// becomes `-2.35`), which is the rounding most accounting rules expect.
+///
+/// # Arguments
+///
+/// * `value` - The value to round.
+/// * `places` - The number of digits to keep after the decimal point.
+///
+/// # Returns
+///
+/// * `Decimal` - The rounded value.
+pub fn round_to(value: Decimal, places: u32) -> Decimal {
+    value.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Parses a decimal value from its string representation.
+///
+/// # Arguments
+///
+/// * `value` - A plain decimal string such as `"1234.56"` or `"-0.01"`.
+///
+/// # Returns
+///
+/// * `Result<Decimal, rust_decimal::Error>` - The parsed value or a parse error.
+pub fn from_str(value: &str) -> Result<Decimal, rust_decimal::Error> {
+    Decimal::from_str(value.trim())
+}
+
+/// Formats a decimal value as a plain string without exponent notation.
+pub fn to_string(value: Decimal) -> String {
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_add_is_exact() {
+        assert_eq!(add(dec("0.1"), dec("0.2")), dec("0.3"));
+        assert_ne!(0.1_f64 + 0.2_f64, 0.3_f64);
+    }
+
+    #[test]
+    fn test_sub_and_mul() {
+        assert_eq!(sub(dec("1.00"), dec("0.99")), dec("0.01"));
+        assert_eq!(mul(dec("19.99"), dec("3")), dec("59.97"));
+    }
+
+    #[test]
+    fn test_div() {
+        assert_eq!(div(dec("10"), dec("4")), Some(dec("2.5")));
+        assert_eq!(div(dec("10"), Decimal::ZERO), None);
+    }
+
+    #[test]
+    fn test_round_to() {
+        assert_eq!(to_string(round_to(dec("2.345"), 2)), "2.35");
+        assert_eq!(to_string(round_to(dec("2.344"), 2)), "2.34");
+        assert_eq!(to_string(round_to(dec("-2.345"), 2)), "-2.35");
+        assert_eq!(to_string(round_to(dec("1.5"), 0)), "2");
+    }
+
+    #[test]
+    fn test_from_str_and_to_string() {
+        assert_eq!(to_string(dec(" 1234.50 ")), "1234.50");
+        assert!(from_str("12,34").is_err());
+        assert!(from_str("abc").is_err());
+    }
+}
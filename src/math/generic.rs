@@ -0,0 +1,134 @@
+//! # Generic Statistics Module
+//!
+//! Provides sum, mean and median over any numeric type implementing the `num-traits`
+//! `Num` and `FromPrimitive` traits, e.g. `i64`, `f32` or `Decimal`.
+//!
+//! This module is only available with the `num` feature enabled.
+//!
+//! ## Precision
+//!
+//! Results are computed in `T` itself, so precision follows the type:
+//!
+//! - Integers: division truncates toward zero, so the mean of `[1, 2]` is `1`. Sums are not
+//!   checked and overflow like ordinary integer addition.
+//! - `f32`: values are accumulated in `f32`, which keeps about 7 significant digits; convert
+//!   to `f64` and use `statistics` for large or wide-ranging series.
+//! - `Decimal`: sums are exact within the 28 digit range; division rounds to the last
+//!   representable digit.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use rust_common::math::generic;
+//!
+//! assert_eq!(generic::sum_generic(&[1_i64, 2, 3]), 6);
+//! assert_eq!(generic::mean_generic(&[1_i64, 2, 3, 4]), Some(2));
+//! assert_eq!(generic::mean_generic(&[1.0_f32, 2.0, 3.0, 4.0]), Some(2.5));
+//! assert_eq!(generic::median_generic(&[3_i32, 1, 2]), Some(2));
+//! ```
+
+use num_traits::{FromPrimitive, Num};
+
+/// Computes the sum of a dataset.
+///
+/// # Returns
+///
+/// * `T` - The sum, zero for empty input.
+pub fn sum_generic<T>(data: &[T]) -> T
+where
+    T: Num + Copy,
+{
+    data.iter().fold(T::zero(), |acc, &x| acc + x)
+}
+
+/// Computes the arithmetic mean of a dataset.
+///
+/// # Returns
+///
+/// * `Option<T>` - The mean, or None for empty input or a length not representable in `T`.
+pub fn mean_generic<T>(data: &[T]) -> Option<T>
+where
+    T: Num + FromPrimitive + Copy,
+{
+    if data.is_empty() {
+        return None;
+    }
+
+    Some(sum_generic(data) / T::from_usize(data.len())?)
+}
+
+/// Computes the median of a dataset.
+///
+/// For an even number of values the median is the mean of the two middle values, computed
+/// in `T`, so it truncates for integers. Values that cannot be ordered (e.g. NaN) sort last.
+///
+/// # Returns
+///
+/// * `Option<T>` - The median, or None for empty input.
+pub fn median_generic<T>(data: &[T]) -> Option<T>
+where
+    T: Num + FromPrimitive + Copy + PartialOrd,
+{
+    if data.is_empty() {
+        return None;
+    }
+
+    let mut sorted = data.to_vec();
+    sorted.sort_by(|a, b| {
+        a.partial_cmp(b)
+            .unwrap_or_else(|| is_unordered(a).cmp(&is_unordered(b)))
+    });
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / T::from_u8(2)?)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+/// Returns true for values not comparable to themselves, such as NaN.
+fn is_unordered<T: PartialOrd>(value: &T) -> bool {
+    value.partial_cmp(value).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_i32() {
+        let data = [4, 8, 15, 16, 23, 42];
+        assert_eq!(sum_generic(&data), 108);
+        assert_eq!(mean_generic(&data), Some(18));
+        assert_eq!(median_generic(&data), Some(15));
+        assert_eq!(mean_generic(&[1, 2]), Some(1));
+        assert_eq!(mean_generic::<i32>(&[]), None);
+    }
+
+    #[test]
+    fn test_f32() {
+        let data = [1.5_f32, 2.5, 3.5, 4.5];
+        assert_eq!(sum_generic(&data), 12.0);
+        assert_eq!(mean_generic(&data), Some(3.0));
+        assert_eq!(median_generic(&data), Some(3.0));
+        assert_eq!(median_generic(&[2.0_f32, f32::NAN, 1.0]), Some(2.0));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal() {
+        use rust_decimal::Decimal;
+
+        let data = [
+            Decimal::new(10, 1),
+            Decimal::new(20, 1),
+            Decimal::new(40, 1),
+        ];
+        assert_eq!(sum_generic(&data), Decimal::new(70, 1));
+        assert_eq!(
+            mean_generic(&[Decimal::new(1, 1), Decimal::new(2, 1)]),
+            Some(Decimal::new(15, 2))
+        );
+    }
+}
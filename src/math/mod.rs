@@ -0,0 +1,20 @@
+//! # Math Module
+//!
+//! Provides numeric helpers shared across services.
+//!
+//! ## Sub-modules
+//!
+//! - `arithmetic`: gcd and lcm, plus overflow-checked integer arithmetic (requires the `num`
+//!   feature)
+//! - `decimal`: Exact decimal arithmetic for money values (requires the `decimal` feature)
+//! - `generic`: Sum, mean and median over generic numeric types (requires the `num` feature)
+//! - `number_utils`: Parsing of human-formatted numbers and factorials
+//! - `statistics`: Descriptive statistics over `f64` samples
+
+pub mod arithmetic;
+#[cfg(feature = "decimal")]
+pub mod decimal;
+#[cfg(feature = "num")]
+pub mod generic;
+pub mod number_utils;
+pub mod statistics;
@@ -0,0 +1,261 @@
+//! # Number Utilities Module
+//!
+//! Provides helpers for parsing human-formatted numbers, and integer factorials.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use rust_common::math::number_utils::{parse_number, NumberFormat};
+//!
+//! assert_eq!(parse_number("1,234.56", &NumberFormat::US), Ok(1234.56));
+//! assert_eq!(parse_number("1.234,56", &NumberFormat::EUROPEAN), Ok(1234.56));
+//! assert_eq!(parse_number("1 234,56", &NumberFormat::SPACE_SEPARATED), Ok(1234.56));
+//!
+//! use rust_common::math::number_utils::{checked_factorial, factorial};
+//!
+//! assert_eq!(factorial(5), 120);
+//! assert_eq!(checked_factorial(21), None);
+//! ```
+
+use thiserror::Error;
+
+/// Describes the separators used in a formatted number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Separator between groups of three integer digits, if grouping is allowed
+    pub thousands_separator: Option<char>,
+    /// Separator between the integer and fractional parts
+    pub decimal_separator: char,
+}
+
+impl NumberFormat {
+    /// `1,234.56`
+    pub const US: NumberFormat = NumberFormat::new(Some(','), '.');
+    /// `1.234,56`
+    pub const EUROPEAN: NumberFormat = NumberFormat::new(Some('.'), ',');
+    /// `1 234,56`
+    pub const SPACE_SEPARATED: NumberFormat = NumberFormat::new(Some(' '), ',');
+
+    /// Creates a new number format
+    pub const fn new(thousands_separator: Option<char>, decimal_separator: char) -> Self {
+        Self {
+            thousands_separator,
+            decimal_separator,
+        }
+    }
+}
+
+/// Errors returned when a formatted number cannot be parsed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input is empty or contains only a sign
+    #[error("number is empty")]
+    Empty,
+
+    /// The input contains a character that is neither a digit nor a configured separator
+    #[error("invalid character '{0}' in number")]
+    InvalidCharacter(char),
+
+    /// The decimal separator appears more than once
+    #[error("number contains more than one decimal separator")]
+    MultipleDecimalSeparators,
+
+    /// Thousands separators do not split the integer part into groups of three digits
+    #[error("thousands separators must separate groups of three digits")]
+    InvalidGrouping,
+
+    /// A decimal separator is not followed by any digit
+    #[error("decimal separator must be followed by digits")]
+    MissingFraction,
+}
+
+/// Parses a human-formatted number using the given separators.
+///
+/// Grouping is validated strictly (`1,23,456` is rejected for `NumberFormat::US`) so that a
+/// number written in another locale is rejected instead of silently parsed to a wrong value.
+///
+/// # Arguments
+///
+/// * `s` - The formatted number, optionally signed and surrounded by whitespace.
+/// * `config` - The separators to expect.
+///
+/// # Returns
+///
+/// * `Result<f64, ParseError>` - The parsed value or the reason the input was rejected.
+pub fn parse_number(s: &str, config: &NumberFormat) -> Result<f64, ParseError> {
+    let trimmed = s.trim();
+    let (negative, unsigned) = match trimmed.chars().next() {
+        Some('-') => (true, &trimmed[1..]),
+        Some('+') => (false, &trimmed[1..]),
+        _ => (false, trimmed),
+    };
+
+    if unsigned.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let mut parts = unsigned.split(config.decimal_separator);
+    let integer_part = parts.next().unwrap_or_default();
+    let fraction_part = parts.next();
+    if parts.next().is_some() {
+        return Err(ParseError::MultipleDecimalSeparators);
+    }
+
+    let integer_digits = parse_integer_part(integer_part, config)?;
+
+    let mut normalized = String::with_capacity(unsigned.len() + 1);
+    if negative {
+        normalized.push('-');
+    }
+    normalized.push_str(if integer_digits.is_empty() {
+        "0"
+    } else {
+        &integer_digits
+    });
+
+    if let Some(fraction) = fraction_part {
+        if fraction.is_empty() {
+            return Err(ParseError::MissingFraction);
+        }
+        if let Some(invalid) = fraction.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(ParseError::InvalidCharacter(invalid));
+        }
+        normalized.push('.');
+        normalized.push_str(fraction);
+    } else if integer_digits.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    normalized.parse::<f64>().map_err(|_| ParseError::Empty)
+}
+
+fn parse_integer_part(integer_part: &str, config: &NumberFormat) -> Result<String, ParseError> {
+    let groups: Vec<&str> = match config.thousands_separator {
+        Some(separator) => integer_part.split(separator).collect(),
+        None => vec![integer_part],
+    };
+
+    for group in &groups {
+        if let Some(invalid) = group.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(ParseError::InvalidCharacter(invalid));
+        }
+    }
+
+    if groups.len() > 1 {
+        let first_valid = (1..=3).contains(&groups[0].len());
+        let rest_valid = groups[1..].iter().all(|group| group.len() == 3);
+        if !first_valid || !rest_valid {
+            return Err(ParseError::InvalidGrouping);
+        }
+    }
+
+    Ok(groups.concat())
+}
+
+/// Computes `n!`.
+///
+/// # Panics
+///
+/// Panics if the result exceeds `u64::MAX`, i.e. for `n >= 21`; see `checked_factorial`.
+pub fn factorial(n: u64) -> u64 {
+    checked_factorial(n).unwrap_or_else(|| panic!("factorial({}) overflows u64", n))
+}
+
+/// Computes `n!`.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The factorial, or None if it exceeds `u64::MAX`, i.e. for `n >= 21`.
+pub fn checked_factorial(n: u64) -> Option<u64> {
+    (2..=n).try_fold(1_u64, |acc, k| acc.checked_mul(k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_us_format() {
+        assert_eq!(parse_number("1,234.56", &NumberFormat::US), Ok(1234.56));
+        assert_eq!(parse_number("1,234,567", &NumberFormat::US), Ok(1234567.0));
+        assert_eq!(parse_number("-0.5", &NumberFormat::US), Ok(-0.5));
+        assert_eq!(parse_number(".5", &NumberFormat::US), Ok(0.5));
+        assert_eq!(parse_number(" 42 ", &NumberFormat::US), Ok(42.0));
+    }
+
+    #[test]
+    fn test_european_format() {
+        assert_eq!(
+            parse_number("1.234,56", &NumberFormat::EUROPEAN),
+            Ok(1234.56)
+        );
+        assert_eq!(parse_number("+12,5", &NumberFormat::EUROPEAN), Ok(12.5));
+    }
+
+    #[test]
+    fn test_space_separated_format() {
+        assert_eq!(
+            parse_number("1 234 567,89", &NumberFormat::SPACE_SEPARATED),
+            Ok(1234567.89)
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(
+            parse_number("12a4", &NumberFormat::US),
+            Err(ParseError::InvalidCharacter('a'))
+        );
+        assert_eq!(parse_number("", &NumberFormat::US), Err(ParseError::Empty));
+        assert_eq!(parse_number("-", &NumberFormat::US), Err(ParseError::Empty));
+        assert_eq!(
+            parse_number("1.", &NumberFormat::US),
+            Err(ParseError::MissingFraction)
+        );
+        assert_eq!(
+            parse_number("1.2.3", &NumberFormat::US),
+            Err(ParseError::MultipleDecimalSeparators)
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_grouping_rejected() {
+        assert_eq!(
+            parse_number("1,23,456", &NumberFormat::US),
+            Err(ParseError::InvalidGrouping)
+        );
+        assert_eq!(
+            parse_number("1234,567", &NumberFormat::US),
+            Err(ParseError::InvalidGrouping)
+        );
+        assert_eq!(
+            parse_number("1.234,56", &NumberFormat::US),
+            Err(ParseError::InvalidCharacter(','))
+        );
+        assert_eq!(
+            parse_number("1,234.56", &NumberFormat::EUROPEAN),
+            Err(ParseError::InvalidCharacter('.'))
+        );
+    }
+
+    #[test]
+    fn test_factorial() {
+        assert_eq!(factorial(0), 1);
+        assert_eq!(factorial(1), 1);
+        assert_eq!(factorial(10), 3_628_800);
+        assert_eq!(factorial(20), 2_432_902_008_176_640_000);
+    }
+
+    #[test]
+    fn test_checked_factorial_overflow_boundary() {
+        assert_eq!(checked_factorial(20), Some(2_432_902_008_176_640_000));
+        assert_eq!(checked_factorial(21), None);
+        assert_eq!(checked_factorial(u64::MAX), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "factorial(21) overflows u64")]
+    fn test_factorial_panics_on_overflow() {
+        factorial(21);
+    }
+}
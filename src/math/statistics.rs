@@ -0,0 +1,798 @@
+//! # Statistics Module
+//!
+//! Provides descriptive statistics over `f64` samples.
+//!
+//! Functions returning `Option` yield `None` for empty input and for input containing
+//! NaN or infinite values, rather than silently propagating them into the result. Use the
+//! `*_ignore_nan` variants to drop non-finite values before computing.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use rust_common::math::statistics;
+//!
+//! let latencies = [12.0, 15.0, 20.0, 35.0];
+//! assert_eq!(statistics::mean(&latencies), Some(20.5));
+//! assert_eq!(statistics::median(&latencies), Some(17.5));
+//! assert_eq!(statistics::rank_of(&latencies, 15.0), 0.5);
+//!
+//! assert_eq!(statistics::mean(&[1.0, f64::NAN]), None);
+//! assert_eq!(statistics::mean_ignore_nan(&[1.0, f64::NAN, 3.0]), Some(2.0));
+//! ```
+
+/// Returns the finite values of a dataset, dropping NaN and infinities.
+pub fn finite_values(data: &[f64]) -> Vec<f64> {
+    data.iter().copied().filter(|x| x.is_finite()).collect()
+}
+
+fn is_all_finite(data: &[f64]) -> bool {
+    data.iter().all(|x| x.is_finite())
+}
+
+/// Computes the arithmetic mean of a dataset.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The mean, or None for empty input or input containing non-finite values.
+pub fn mean(data: &[f64]) -> Option<f64> {
+    if data.is_empty() || !is_all_finite(data) {
+        return None;
+    }
+
+    Some(data.iter().sum::<f64>() / data.len() as f64)
+}
+
+/// Computes the population variance of a dataset.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The variance, or None for empty input or input containing non-finite values.
+pub fn variance(data: &[f64]) -> Option<f64> {
+    let mean = mean(data)?;
+    let squared_deviations: f64 = data.iter().map(|x| (x - mean).powi(2)).sum();
+    Some(squared_deviations / data.len() as f64)
+}
+
+/// Computes the population standard deviation of a dataset.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The standard deviation, or None for empty input or input containing
+///   non-finite values.
+pub fn std_dev(data: &[f64]) -> Option<f64> {
+    variance(data).map(f64::sqrt)
+}
+
+/// Computes the sample variance of a dataset, dividing by `n - 1` (Bessel's correction).
+///
+/// # Returns
+///
+/// * `Option<f64>` - The sample variance, or None for fewer than two values or input containing
+///   non-finite values.
+pub fn sample_variance(data: &[f64]) -> Option<f64> {
+    if data.len() < 2 {
+        return None;
+    }
+
+    let mean = mean(data)?;
+    let squared_deviations: f64 = data.iter().map(|x| (x - mean).powi(2)).sum();
+    Some(squared_deviations / (data.len() - 1) as f64)
+}
+
+/// Computes the sample standard deviation of a dataset.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The sample standard deviation, or None under the same conditions as
+///   `sample_variance`.
+pub fn sample_std_dev(data: &[f64]) -> Option<f64> {
+    sample_variance(data).map(f64::sqrt)
+}
+
+/// Computes the standard error of the mean, `sample_std_dev / sqrt(n)`.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The standard error, or None for fewer than two values or input containing
+///   non-finite values.
+pub fn standard_error(data: &[f64]) -> Option<f64> {
+    Some(sample_std_dev(data)? / (data.len() as f64).sqrt())
+}
+
+/// Computes a 95% confidence interval for the mean using the normal approximation.
+///
+/// The interval is `mean ± 1.96 * standard_error`; for small samples it is narrower than the
+/// Student's t interval.
+///
+/// # Returns
+///
+/// * `Option<(f64, f64)>` - `(lower, upper)`, or None under the same conditions as
+///   `standard_error`.
+pub fn confidence_interval_95(data: &[f64]) -> Option<(f64, f64)> {
+    let mean = mean(data)?;
+    let margin = 1.96 * standard_error(data)?;
+    Some((mean - margin, mean + margin))
+}
+
+/// Computes the median of a dataset.
+///
+/// For an even number of values the median is the mean of the two middle values.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The median, or None for empty input or input containing non-finite values.
+pub fn median(data: &[f64]) -> Option<f64> {
+    if data.is_empty() || !is_all_finite(data) {
+        return None;
+    }
+
+    let mut sorted = data.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+/// Computes the median absolute deviation (MAD) of a dataset.
+///
+/// The MAD is the median of the absolute deviations from the median. Unlike the standard
+/// deviation, a few outliers barely move it.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The median absolute deviation, or None for empty input or input containing
+///   non-finite values.
+pub fn median_absolute_deviation(data: &[f64]) -> Option<f64> {
+    let center = median(data)?;
+    let deviations: Vec<f64> = data.iter().map(|x| (x - center).abs()).collect();
+    median(&deviations)
+}
+
+/// Computes the median absolute deviation scaled by 1.4826, a robust estimate of the standard
+/// deviation for normally distributed data.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The scaled median absolute deviation, or None for empty input or input
+///   containing non-finite values.
+pub fn mad_normalized(data: &[f64]) -> Option<f64> {
+    const NORMAL_CONSISTENCY: f64 = 1.4826;
+    median_absolute_deviation(data).map(|mad| mad * NORMAL_CONSISTENCY)
+}
+
+/// Computes a percentile of a dataset using the nearest-rank method.
+///
+/// The result is the smallest value such that at least `p` percent of the values are less than
+/// or equal to it, so it is always one of the values in `data`.
+///
+/// # Arguments
+///
+/// * `data` - The dataset; it does not need to be sorted.
+/// * `p` - The percentile in `[0.0, 100.0]`.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The percentile, or None for empty input, input containing non-finite
+///   values, or `p` outside `[0.0, 100.0]`.
+pub fn percentile(data: &[f64], p: f64) -> Option<f64> {
+    weighted_percentile(data, &vec![1.0; data.len()], p)
+}
+
+/// Computes a weighted percentile of a dataset using cumulative weights.
+///
+/// Values are sorted and their weights accumulated; the result is the first value at which the
+/// cumulative weight reaches `p` percent of the total weight. With equal weights this is the
+/// same as `percentile`.
+///
+/// # Arguments
+///
+/// * `values` - The dataset; it does not need to be sorted.
+/// * `weights` - The non-negative weight of each value, paired with `values` by index.
+/// * `p` - The percentile in `[0.0, 100.0]`.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The percentile, or None if the slices differ in length, are empty, contain
+///   non-finite values or negative weights, the weights sum to zero, or `p` is outside
+///   `[0.0, 100.0]`.
+pub fn weighted_percentile(values: &[f64], weights: &[f64], p: f64) -> Option<f64> {
+    if values.len() != weights.len()
+        || values.is_empty()
+        || !(0.0..=100.0).contains(&p)
+        || !is_all_finite(values)
+        || !is_all_finite(weights)
+        || weights.iter().any(|&w| w < 0.0)
+    {
+        return None;
+    }
+
+    let mut pairs: Vec<(f64, f64)> = values
+        .iter()
+        .zip(weights)
+        .filter(|(_, &w)| w > 0.0)
+        .map(|(&v, &w)| (v, w))
+        .collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let total: f64 = pairs.iter().map(|(_, w)| w).sum();
+    if total == 0.0 {
+        return None;
+    }
+
+    let target = p / 100.0 * total;
+    let mut cumulative = 0.0;
+    for &(value, weight) in &pairs {
+        cumulative += weight;
+        if cumulative >= target {
+            return Some(value);
+        }
+    }
+
+    pairs.last().map(|&(value, _)| value)
+}
+
+/// Computes the mean of the finite values of a dataset, ignoring NaN and infinities.
+pub fn mean_ignore_nan(data: &[f64]) -> Option<f64> {
+    mean(&finite_values(data))
+}
+
+/// Computes the population variance of the finite values of a dataset, ignoring NaN and infinities.
+pub fn variance_ignore_nan(data: &[f64]) -> Option<f64> {
+    variance(&finite_values(data))
+}
+
+/// Computes the population standard deviation of the finite values of a dataset, ignoring NaN
+/// and infinities.
+pub fn std_dev_ignore_nan(data: &[f64]) -> Option<f64> {
+    std_dev(&finite_values(data))
+}
+
+/// Computes the median of the finite values of a dataset, ignoring NaN and infinities.
+pub fn median_ignore_nan(data: &[f64]) -> Option<f64> {
+    median(&finite_values(data))
+}
+
+/// Sums a dataset with compensated (Kahan–Babuška) summation.
+///
+/// Tracks the low-order bits lost by each addition and adds them back at the end, so the
+/// result stays accurate for long inputs and for values of widely varying magnitude where a
+/// naive sum drifts.
+///
+/// # Returns
+///
+/// * `f64` - The sum, 0.0 for empty input. Non-finite values propagate as in a naive sum.
+pub fn kahan_sum(data: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+
+    for &x in data {
+        let t = sum + x;
+        if sum.abs() >= x.abs() {
+            compensation += (sum - t) + x;
+        } else {
+            compensation += (x - t) + sum;
+        }
+        sum = t;
+    }
+
+    sum + compensation
+}
+
+/// Computes the arithmetic mean of a dataset using compensated summation.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The mean, or None for empty input or input containing non-finite values.
+pub fn mean_precise(data: &[f64]) -> Option<f64> {
+    if data.is_empty() || !is_all_finite(data) {
+        return None;
+    }
+
+    Some(kahan_sum(data) / data.len() as f64)
+}
+
+/// Computes the population variance of a dataset using compensated summation.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The variance, or None for empty input or input containing non-finite values.
+pub fn variance_precise(data: &[f64]) -> Option<f64> {
+    let mean = mean_precise(data)?;
+    let squared_deviations: Vec<f64> = data.iter().map(|x| (x - mean).powi(2)).collect();
+    Some(kahan_sum(&squared_deviations) / data.len() as f64)
+}
+
+/// Computes the percentile rank of a value within a dataset.
+///
+/// The rank is the fraction of values in `data` that are less than or equal to
+/// `value`, so it is the inverse lookup of a percentile.
+///
+/// # Arguments
+///
+/// * `data` - The dataset; it does not need to be sorted.
+/// * `value` - The value to rank.
+///
+/// # Returns
+///
+/// * `f64` - The rank in `[0.0, 1.0]`, or 0.0 for empty input. NaN values never count as
+///   less than or equal to `value`, and a NaN `value` has rank 0.0.
+pub fn rank_of(data: &[f64], value: f64) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let at_or_below = data.iter().filter(|&&x| x <= value).count();
+    at_or_below as f64 / data.len() as f64
+}
+
+/// Fits a line `y = slope * x + intercept` to paired samples by least squares.
+///
+/// # Arguments
+///
+/// * `xs` - The independent values.
+/// * `ys` - The dependent values, paired with `xs` by index.
+///
+/// # Returns
+///
+/// * `Option<(f64, f64)>` - `(slope, intercept)`, or None if the slices differ in length, are
+///   empty, contain non-finite values, or all `xs` are equal.
+pub fn linear_regression(xs: &[f64], ys: &[f64]) -> Option<(f64, f64)> {
+    if xs.len() != ys.len() {
+        return None;
+    }
+
+    let mean_x = mean(xs)?;
+    let mean_y = mean(ys)?;
+
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+    }
+
+    if variance_x == 0.0 {
+        return None;
+    }
+
+    let slope = covariance / variance_x;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+/// Computes the coefficient of determination (r²) of the least squares line through paired samples.
+///
+/// # Arguments
+///
+/// * `xs` - The independent values.
+/// * `ys` - The dependent values, paired with `xs` by index.
+///
+/// # Returns
+///
+/// * `Option<f64>` - r² in `[0.0, 1.0]`, with 1.0 for a perfect fit (including constant `ys`),
+///   or None under the same conditions as `linear_regression`.
+pub fn r_squared(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let (slope, intercept) = linear_regression(xs, ys)?;
+    let mean_y = mean(ys)?;
+
+    let mut residual_sum = 0.0;
+    let mut total_sum = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        residual_sum += (y - (slope * x + intercept)).powi(2);
+        total_sum += (y - mean_y).powi(2);
+    }
+
+    if total_sum == 0.0 {
+        return Some(1.0);
+    }
+
+    Some(1.0 - residual_sum / total_sum)
+}
+
+/// How far the probabilities passed to `shannon_entropy` may sum from 1.0.
+const PROBABILITY_SUM_TOLERANCE: f64 = 1e-6;
+
+/// Computes the Shannon entropy of a probability distribution, in bits.
+///
+/// Zero probabilities contribute nothing, following the convention `0 * log2(0) = 0`.
+///
+/// # Arguments
+///
+/// * `probabilities` - The probability of each category.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The entropy, from 0.0 for a certain outcome up to `log2(n)` for a uniform
+///   distribution, or None for empty input, non-finite or negative probabilities, or
+///   probabilities not summing to 1.0 (within 1e-6).
+pub fn shannon_entropy(probabilities: &[f64]) -> Option<f64> {
+    if probabilities.is_empty()
+        || !is_all_finite(probabilities)
+        || probabilities.iter().any(|&p| p < 0.0)
+    {
+        return None;
+    }
+
+    if (kahan_sum(probabilities) - 1.0).abs() > PROBABILITY_SUM_TOLERANCE {
+        return None;
+    }
+
+    let entropy: f64 = probabilities
+        .iter()
+        .filter(|&&p| p > 0.0)
+        .map(|&p| -p * p.log2())
+        .sum();
+
+    // Rounding can leave a tiny negative value for a certain outcome
+    Some(entropy.max(0.0))
+}
+
+/// Computes the Shannon entropy of a distribution divided by its maximum, `log2(n)`.
+///
+/// # Arguments
+///
+/// * `probabilities` - The probability of each of the `n` categories.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The normalized entropy in `[0.0, 1.0]`, with 1.0 for a uniform
+///   distribution and 0.0 for a single category, or None under the same conditions as
+///   `shannon_entropy`.
+pub fn normalized_entropy(probabilities: &[f64]) -> Option<f64> {
+    let entropy = shannon_entropy(probabilities)?;
+    if probabilities.len() == 1 {
+        return Some(0.0);
+    }
+
+    Some((entropy / (probabilities.len() as f64).log2()).min(1.0))
+}
+
+/// Computes a bootstrap confidence interval for any statistic of a dataset.
+///
+/// The dataset is resampled with replacement `iterations` times, the statistic computed on each
+/// resample, and the interval taken from the percentiles of those values, so no distribution is
+/// assumed. Resampling draws from a generator seeded with `seed`, so the same inputs always give
+/// the same interval.
+///
+/// # Arguments
+///
+/// * `data` - The dataset.
+/// * `statistic` - The statistic to estimate, e.g. `|d| mean(d).unwrap()`.
+/// * `iterations` - The number of resamples; a few thousand give stable bounds.
+/// * `confidence` - The confidence level in `(0.0, 1.0)`, e.g. 0.95.
+/// * `seed` - The seed of the resampling generator.
+///
+/// # Returns
+///
+/// * `Option<(f64, f64)>` - `(lower, upper)`, or None for empty input, input containing
+///   non-finite values, zero iterations, a confidence outside `(0.0, 1.0)`, or a statistic
+///   returning non-finite values.
+pub fn bootstrap_ci(
+    data: &[f64],
+    statistic: impl Fn(&[f64]) -> f64,
+    iterations: usize,
+    confidence: f64,
+    seed: u64,
+) -> Option<(f64, f64)> {
+    if data.is_empty() || !is_all_finite(data) || iterations == 0 {
+        return None;
+    }
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return None;
+    }
+
+    let mut rng = SplitMix64(seed);
+    let mut resample = vec![0.0; data.len()];
+    let estimates: Vec<f64> = (0..iterations)
+        .map(|_| {
+            for value in resample.iter_mut() {
+                *value = data[rng.below(data.len())];
+            }
+            statistic(&resample)
+        })
+        .collect();
+
+    let tail = (1.0 - confidence) / 2.0 * 100.0;
+    Some((
+        percentile(&estimates, tail)?,
+        percentile(&estimates, 100.0 - tail)?,
+    ))
+}
+
+/// SplitMix64 is a small seeded generator, enough for reproducible resampling.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `[0, n)`.
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_variance_std_dev() {
+        let data = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(mean(&data), Some(5.0));
+        assert_eq!(variance(&data), Some(4.0));
+        assert_eq!(std_dev(&data), Some(2.0));
+    }
+
+    #[test]
+    fn test_standard_error_and_confidence_interval() {
+        let data = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        // Squared deviations sum to 32, so the sample variance is 32 / 7 and
+        // SEM = sqrt(32 / 7) / sqrt(8) = sqrt(4 / 7).
+        let expected_sem = (4.0f64 / 7.0).sqrt();
+
+        assert!((sample_variance(&data).unwrap() - 32.0 / 7.0).abs() < 1e-12);
+        assert!((standard_error(&data).unwrap() - expected_sem).abs() < 1e-12);
+
+        let (lower, upper) = confidence_interval_95(&data).unwrap();
+        assert!((lower - (5.0 - 1.96 * expected_sem)).abs() < 1e-12);
+        assert!((upper - (5.0 + 1.96 * expected_sem)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_standard_error_needs_two_values() {
+        assert_eq!(standard_error(&[]), None);
+        assert_eq!(standard_error(&[1.0]), None);
+        assert_eq!(confidence_interval_95(&[1.0]), None);
+        assert_eq!(standard_error(&[1.0, f64::NAN]), None);
+    }
+
+    #[test]
+    fn test_weighted_percentile_uniform_weights_match_percentile() {
+        let data = [15.0, 20.0, 35.0, 40.0, 50.0];
+        let weights = [2.0; 5];
+
+        assert_eq!(percentile(&data, 30.0), Some(20.0));
+        assert_eq!(percentile(&data, 40.0), Some(20.0));
+        assert_eq!(percentile(&data, 50.0), Some(35.0));
+        assert_eq!(percentile(&data, 100.0), Some(50.0));
+        for p in [0.0, 5.0, 30.0, 40.0, 50.0, 75.0, 99.0, 100.0] {
+            assert_eq!(
+                weighted_percentile(&data, &weights, p),
+                percentile(&data, p)
+            );
+        }
+    }
+
+    #[test]
+    fn test_weighted_percentile_skewed_weights() {
+        // Latency buckets: 90 requests at 10ms, 9 at 100ms, 1 at 1000ms
+        let latencies = [1000.0, 10.0, 100.0];
+        let counts = [1.0, 90.0, 9.0];
+
+        assert_eq!(weighted_percentile(&latencies, &counts, 50.0), Some(10.0));
+        assert_eq!(weighted_percentile(&latencies, &counts, 90.0), Some(10.0));
+        assert_eq!(weighted_percentile(&latencies, &counts, 95.0), Some(100.0));
+        assert_eq!(weighted_percentile(&latencies, &counts, 99.5), Some(1000.0));
+    }
+
+    #[test]
+    fn test_weighted_percentile_invalid_input() {
+        assert_eq!(weighted_percentile(&[1.0, 2.0], &[1.0], 50.0), None);
+        assert_eq!(weighted_percentile(&[], &[], 50.0), None);
+        assert_eq!(weighted_percentile(&[1.0], &[0.0], 50.0), None);
+        assert_eq!(weighted_percentile(&[1.0, 2.0], &[1.0, -1.0], 50.0), None);
+        assert_eq!(
+            weighted_percentile(&[1.0, f64::NAN], &[1.0, 1.0], 50.0),
+            None
+        );
+        assert_eq!(percentile(&[1.0, 2.0], 101.0), None);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_median_absolute_deviation_is_robust_to_outliers() {
+        let clean = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let mut with_outlier = clean;
+        with_outlier[7] = 900.0;
+
+        assert_eq!(median_absolute_deviation(&clean), Some(0.5));
+        assert_eq!(median_absolute_deviation(&with_outlier), Some(0.5));
+        assert!((mad_normalized(&clean).unwrap() - 0.7413).abs() < 1e-12);
+
+        // the outlier inflates the standard deviation more than a hundredfold
+        assert_eq!(std_dev(&clean), Some(2.0));
+        assert!(std_dev(&with_outlier).unwrap() > 200.0);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(mean(&[]), None);
+        assert_eq!(variance(&[]), None);
+        assert_eq!(std_dev(&[]), None);
+        assert_eq!(median(&[]), None);
+        assert_eq!(median_absolute_deviation(&[]), None);
+        assert_eq!(mad_normalized(&[]), None);
+    }
+
+    #[test]
+    fn test_non_finite_input_returns_none() {
+        let with_nan = [1.0, f64::NAN, 3.0];
+        let with_inf = [1.0, f64::INFINITY, 3.0];
+
+        assert_eq!(mean(&with_nan), None);
+        assert_eq!(variance(&with_nan), None);
+        assert_eq!(std_dev(&with_inf), None);
+        assert_eq!(median(&with_nan), None);
+        assert_eq!(median(&with_inf), None);
+    }
+
+    #[test]
+    fn test_ignore_nan_variants() {
+        let data = [1.0, f64::NAN, 3.0, f64::NEG_INFINITY];
+
+        assert_eq!(finite_values(&data), vec![1.0, 3.0]);
+        assert_eq!(mean_ignore_nan(&data), Some(2.0));
+        assert_eq!(variance_ignore_nan(&data), Some(1.0));
+        assert_eq!(std_dev_ignore_nan(&data), Some(1.0));
+        assert_eq!(median_ignore_nan(&data), Some(2.0));
+        assert_eq!(mean_ignore_nan(&[f64::NAN]), None);
+    }
+
+    #[test]
+    fn test_linear_regression_perfect_line() {
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys: Vec<f64> = xs.iter().map(|x| 2.0 * x + 1.0).collect();
+
+        assert_eq!(linear_regression(&xs, &ys), Some((2.0, 1.0)));
+        assert!((r_squared(&xs, &ys).unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_r_squared_noisy_data() {
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        let ys = [1.0, 3.0, 2.0, 4.0];
+
+        assert_eq!(linear_regression(&xs, &ys), Some((0.8, 0.5)));
+        assert!((r_squared(&xs, &ys).unwrap() - 0.64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_linear_regression_invalid_input() {
+        assert_eq!(linear_regression(&[1.0, 2.0], &[1.0]), None);
+        assert_eq!(linear_regression(&[], &[]), None);
+        assert_eq!(linear_regression(&[2.0, 2.0], &[1.0, 3.0]), None);
+        assert_eq!(r_squared(&[2.0, 2.0], &[1.0, 3.0]), None);
+        assert_eq!(linear_regression(&[1.0, f64::NAN], &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn test_kahan_sum_is_closer_than_naive_sum() {
+        let data = vec![0.1; 1_000_000];
+        let expected = 100_000.0;
+
+        let naive: f64 = data.iter().sum();
+        let compensated = kahan_sum(&data);
+
+        assert!((compensated - expected).abs() < (naive - expected).abs());
+        assert!((compensated - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_kahan_sum_mixed_magnitudes() {
+        let data = [1.0, 1e100, 1.0, -1e100];
+        assert_eq!(data.iter().sum::<f64>(), 0.0);
+        assert_eq!(kahan_sum(&data), 2.0);
+        assert_eq!(kahan_sum(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_mean_and_variance_precise() {
+        let data = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(mean_precise(&data), Some(5.0));
+        assert_eq!(variance_precise(&data), Some(4.0));
+        assert_eq!(mean_precise(&[]), None);
+        assert_eq!(variance_precise(&[1.0, f64::NAN]), None);
+
+        let data = vec![0.1; 1_000_000];
+        assert!((mean_precise(&data).unwrap() - 0.1).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_rank_of_with_nan() {
+        assert_eq!(rank_of(&[1.0, f64::NAN, 3.0, 4.0], 3.0), 0.5);
+        assert_eq!(rank_of(&[1.0, 2.0], f64::NAN), 0.0);
+    }
+
+    #[test]
+    fn test_rank_of_empty() {
+        assert_eq!(rank_of(&[], 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_rank_of_median_and_max() {
+        let data: Vec<f64> = (1..=101).map(f64::from).collect();
+        assert!((rank_of(&data, 51.0) - 0.5).abs() < 0.01);
+        assert_eq!(rank_of(&data, 101.0), 1.0);
+    }
+
+    #[test]
+    fn test_rank_of_outside_range() {
+        let data = [3.0, 1.0, 2.0];
+        assert_eq!(rank_of(&data, 0.5), 0.0);
+        assert_eq!(rank_of(&data, 10.0), 1.0);
+    }
+
+    #[test]
+    fn test_entropy_of_uniform_distribution_is_maximal() {
+        let uniform = [0.25; 4];
+        assert!((shannon_entropy(&uniform).unwrap() - 2.0).abs() < 1e-12);
+        assert!((normalized_entropy(&uniform).unwrap() - 1.0).abs() < 1e-12);
+
+        let skewed = [0.7, 0.1, 0.1, 0.1];
+        assert!(shannon_entropy(&skewed).unwrap() < 2.0);
+        assert!(normalized_entropy(&skewed).unwrap() < 1.0);
+    }
+
+    #[test]
+    fn test_entropy_of_degenerate_distribution_is_zero() {
+        assert_eq!(shannon_entropy(&[0.0, 1.0, 0.0]), Some(0.0));
+        assert_eq!(normalized_entropy(&[0.0, 1.0, 0.0]), Some(0.0));
+        assert_eq!(normalized_entropy(&[1.0]), Some(0.0));
+    }
+
+    #[test]
+    fn test_entropy_invalid_input() {
+        assert_eq!(shannon_entropy(&[]), None);
+        assert_eq!(shannon_entropy(&[0.5, 0.4]), None);
+        assert_eq!(shannon_entropy(&[1.5, -0.5]), None);
+        assert_eq!(shannon_entropy(&[f64::NAN, 1.0]), None);
+        assert_eq!(normalized_entropy(&[0.6, 0.6]), None);
+
+        // Within tolerance of 1.0
+        assert!(shannon_entropy(&[0.1; 10]).is_some());
+    }
+
+    #[test]
+    fn test_bootstrap_ci_is_reproducible_and_contains_mean() {
+        let data = [
+            12.0, 15.0, 9.0, 20.0, 14.0, 11.0, 18.0, 16.0, 13.0, 17.0, 10.0, 19.0,
+        ];
+        let sample_mean = mean(&data).unwrap();
+        let mean_of = |sample: &[f64]| mean(sample).unwrap();
+
+        let (lower, upper) = bootstrap_ci(&data, mean_of, 2000, 0.95, 42).unwrap();
+        assert_eq!(
+            bootstrap_ci(&data, mean_of, 2000, 0.95, 42),
+            Some((lower, upper))
+        );
+        assert!(lower < sample_mean && sample_mean < upper);
+
+        // A lower confidence level gives a narrower interval
+        let (narrow_lower, narrow_upper) = bootstrap_ci(&data, mean_of, 2000, 0.5, 42).unwrap();
+        assert!(narrow_upper - narrow_lower < upper - lower);
+    }
+
+    #[test]
+    fn test_bootstrap_ci_invalid_input() {
+        let mean_of = |sample: &[f64]| mean(sample).unwrap_or(f64::NAN);
+        assert_eq!(bootstrap_ci(&[], mean_of, 100, 0.95, 1), None);
+        assert_eq!(bootstrap_ci(&[1.0, f64::NAN], mean_of, 100, 0.95, 1), None);
+        assert_eq!(bootstrap_ci(&[1.0, 2.0], mean_of, 0, 0.95, 1), None);
+        assert_eq!(bootstrap_ci(&[1.0, 2.0], mean_of, 100, 1.0, 1), None);
+        assert_eq!(bootstrap_ci(&[1.0, 2.0], |_| f64::NAN, 100, 0.95, 1), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Fixtures shared by the unit tests of this crate.
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use rdkafka::{
+    message::{Headers, OwnedHeaders, OwnedMessage},
+    Timestamp,
+};
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::{fmt, fmt::MakeWriter, prelude::*};
+
+/// Writer collecting formatted log output in memory.
+#[derive(Clone, Default)]
+pub struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+impl CaptureWriter {
+    /// Creates an empty writer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retrieves everything written so far.
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CaptureWriter {
+    type Writer = CaptureWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Formats the current thread's log events without colors into a CaptureWriter.
+///
+/// Capturing stops when the returned guard is dropped.
+pub fn capture_logs() -> (CaptureWriter, DefaultGuard) {
+    let writer = CaptureWriter::new();
+    let subscriber = tracing_subscriber::registry()
+        .with(fmt::layer().with_ansi(false).with_writer(writer.clone()));
+
+    (writer, tracing::subscriber::set_default(subscriber))
+}
+
+/// TestMessage builds consumed Kafka messages, by default at offset 0 of partition 0 with no
+/// payload, key, timestamp or headers.
+pub struct TestMessage {
+    topic: String,
+    partition: i32,
+    offset: i64,
+    payload: Option<Vec<u8>>,
+    key: Option<Vec<u8>>,
+    timestamp: Timestamp,
+    headers: Option<OwnedHeaders>,
+}
+
+impl TestMessage {
+    /// Starts a message consumed from `topic`.
+    pub fn new(topic: &str) -> Self {
+        Self {
+            topic: topic.to_string(),
+            partition: 0,
+            offset: 0,
+            payload: None,
+            key: None,
+            timestamp: Timestamp::NotAvailable,
+            headers: None,
+        }
+    }
+
+    pub fn with_partition(mut self, partition: i32) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: i64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_payload(mut self, payload: impl Into<Vec<u8>>) -> Self {
+        self.payload = Some(payload.into());
+        self
+    }
+
+    pub fn with_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_headers(mut self, headers: OwnedHeaders) -> Self {
+        self.headers = Some(headers);
+        self
+    }
+
+    pub fn build(self) -> OwnedMessage {
+        OwnedMessage::new(
+            self.payload,
+            self.key,
+            self.topic,
+            self.timestamp,
+            self.partition,
+            self.offset,
+            self.headers,
+        )
+    }
+}
+
+/// Retrieves the value of the first header with the given key, as UTF-8.
+pub fn header_value(headers: &OwnedHeaders, key: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|header| header.key == key)
+        .and_then(|header| header.value)
+        .map(|value| String::from_utf8(value.to_vec()).unwrap())
+}